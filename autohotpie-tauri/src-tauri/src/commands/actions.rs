@@ -1,14 +1,54 @@
-use super::{AppError, AppState, Result};
-use crate::domain::{Action, ActionEventPayload, ActionId};
+use super::{AppError, AppState, Result, SystemState};
+use crate::domain::{Action, ActionEventPayload, ActionId, ActionOutput};
+use crate::services::action_plan::ActionPlan;
+use crate::services::action_runner::RunningAction;
+use crate::services::custom_handlers::CustomHandlerInfo;
+use crate::services::execution_history::{HistoryQuery, HistoryReport};
+use crate::services::execution_policy::PolicyDecision;
+use crate::services::interpreters::ScriptLanguageInfo;
+use crate::services::profile_router::ProfileRouterState;
+use crate::services::templating::TemplateContext;
 use tauri::{AppHandle, Runtime, State};
+use uuid::Uuid;
 
 fn action_not_found(id: &ActionId) -> AppError {
     AppError::Message(format!("action {id} not found"))
 }
 
+/// Placeholder values for a run started now: the foreground window and the
+/// profile the router selected, or the stored active profile before the
+/// router has picked one. The profile id is recorded in the run's history.
+pub(crate) fn template_context(
+    state: &AppState,
+    system: &SystemState,
+    router: &ProfileRouterState,
+) -> TemplateContext {
+    let window = system
+        .status
+        .lock()
+        .map(|status| status.window.clone())
+        .unwrap_or_default();
+    let current = router.current();
+    let store = state.profiles.lock().ok();
+    let record = store.as_ref().and_then(|store| {
+        let id = current
+            .as_ref()
+            .map(|current| current.profile_id)
+            .or(store.active_profile_id)?;
+        store.profiles.iter().find(|record| record.profile.id == id)
+    });
+    let profile_id = record.map(|record| record.profile.id);
+    // Name and id come from the same record; the snapshot's name only
+    // covers a profile deleted since it was selected.
+    let profile_name = record
+        .map(|record| record.profile.name.clone())
+        .or_else(|| current.map(|profile| profile.name));
+    TemplateContext::new(window, profile_name).with_profile_id(profile_id)
+}
+
 #[tauri::command]
 pub fn list_actions(state: State<'_, AppState>) -> Result<Vec<Action>> {
-    Ok(state.actions_snapshot())
+    Ok(state.profiles_snapshot()?.actions)
 }
 
 #[tauri::command]
@@ -16,31 +56,200 @@ pub fn recent_action_events(state: State<'_, AppState>) -> Result<Vec<ActionEven
     Ok(state.action_events_channel().recent())
 }
 
+/// Returns the stdout/stderr and exit code captured for an action event.
+/// Recent events are served from memory; older ones from the audit log.
+#[tauri::command]
+pub fn get_action_output(
+    state: State<'_, AppState>,
+    event_id: Uuid,
+) -> Result<Option<ActionOutput>> {
+    if let Some(event) = state
+        .action_events_channel()
+        .recent()
+        .into_iter()
+        .find(|event| event.event_id == event_id)
+    {
+        return Ok(event.output);
+    }
+    state
+        .audit
+        .find_action_event(event_id)?
+        .map(|event| event.output)
+        .ok_or_else(|| AppError::Message(format!("action event {event_id} not found")))
+}
+
+/// Past runs from the persistent history, newest first, with run count,
+/// failure rate and duration percentiles per action.
+#[tauri::command]
+pub fn query_action_history(
+    state: State<'_, AppState>,
+    query: Option<HistoryQuery>,
+) -> Result<HistoryReport> {
+    Ok(state
+        .action_runner
+        .history()
+        .query(&query.unwrap_or_default())?)
+}
+
 #[tauri::command]
 pub fn save_actions<R: Runtime>(
     _app: AppHandle<R>,
     state: State<'_, AppState>,
-    actions: Vec<Action>,
+    mut actions: Vec<Action>,
 ) -> Result<Vec<Action>> {
-    state.storage.save_actions(&actions)?;
-    state.replace_actions(actions.clone());
+    state.with_profiles_mut(|store| {
+        for action in &mut actions {
+            action.untrusted |= store.is_untrusted(&action.id);
+        }
+        store.actions = actions.clone();
+        Ok(())
+    })?;
     state.audit.log("INFO", "Actions saved")?;
     Ok(actions)
 }
 
+/// Clears the `untrusted` flag imports put on actions, after the user has
+/// looked at them. Returns the actions that changed.
+#[tauri::command]
+pub fn mark_actions_reviewed(
+    state: State<'_, AppState>,
+    action_ids: Vec<ActionId>,
+) -> Result<Vec<Action>> {
+    let reviewed = state.with_profiles_mut(|store| {
+        Ok(store
+            .all_actions_mut()
+            .filter(|action| action.untrusted && action_ids.contains(&action.id))
+            .map(|action| {
+                action.untrusted = false;
+                action.clone()
+            })
+            .collect::<Vec<_>>())
+    })?;
+    for action in &reviewed {
+        state.audit.log(
+            "INFO",
+            &format!(
+                "Action '{}' ({}) marked as reviewed",
+                action.name, action.id
+            ),
+        )?;
+    }
+    Ok(reviewed)
+}
+
+/// What the execution policy says about running the action. Callers prompt
+/// on `confirm` and pass `confirmed: true` to `run_action`.
+#[tauri::command]
+pub fn check_action_policy(
+    state: State<'_, AppState>,
+    system: State<'_, SystemState>,
+    router: State<'_, ProfileRouterState>,
+    action_id: ActionId,
+) -> Result<PolicyDecision> {
+    let profile_id = template_context(&state, &system, &router).profile_id;
+    let action = state
+        .lookup_action(profile_id, &action_id)
+        .ok_or_else(|| action_not_found(&action_id))?;
+    let actions = state.actions_for(profile_id);
+    Ok(state.action_runner.check_policy(&action, &actions))
+}
+
 #[tauri::command]
 pub async fn run_action(
     state: State<'_, AppState>,
+    system: State<'_, SystemState>,
+    router: State<'_, ProfileRouterState>,
     action_id: ActionId,
+    invocation_id: Option<Uuid>,
+    confirmed: Option<bool>,
 ) -> Result<ActionEventPayload> {
+    // Ids resolve in the profile the run is recorded against.
+    let context = template_context(&state, &system, &router);
+    let actions = state.actions_for(context.profile_id);
     let action = state
-        .lookup_action(&action_id)
+        .lookup_action(context.profile_id, &action_id)
         .ok_or_else(|| action_not_found(&action_id))?;
-    state.action_runner.run(&action, state.inner()).await
+    state
+        .action_runner
+        .authorize(&action, &actions, confirmed.unwrap_or(false))?;
+    let invocation_id = invocation_id.unwrap_or_else(Uuid::new_v4);
+    state
+        .action_runner
+        .run_with_invocation(&action, &actions, invocation_id, context)
+        .await
+}
+
+#[tauri::command]
+pub async fn test_action(
+    state: State<'_, AppState>,
+    system: State<'_, SystemState>,
+    router: State<'_, ProfileRouterState>,
+    mut action: Action,
+    invocation_id: Option<Uuid>,
+    confirmed: Option<bool>,
+) -> Result<ActionEventPayload> {
+    // Test mode executes the provided action without persisting it, but
+    // under the stored copy's `untrusted` flag.
+    action.untrusted |= state
+        .profiles
+        .lock()
+        .map_err(|_| AppError::StatePoisoned)?
+        .is_untrusted(&action.id);
+    let context = template_context(&state, &system, &router);
+    let actions = state.actions_for(context.profile_id);
+    state
+        .action_runner
+        .authorize(&action, &actions, confirmed.unwrap_or(false))?;
+    let invocation_id = invocation_id.unwrap_or_else(Uuid::new_v4);
+    state
+        .action_runner
+        .run_with_invocation(&action, &actions, invocation_id, context)
+        .await
+}
+
+/// Dry run of `action`: returns what it would launch, type or run, with
+/// placeholders resolved, without executing anything.
+#[tauri::command]
+pub async fn explain_action(
+    state: State<'_, AppState>,
+    system: State<'_, SystemState>,
+    router: State<'_, ProfileRouterState>,
+    action: Action,
+) -> Result<ActionPlan> {
+    let context = template_context(&state, &system, &router);
+    let actions = state.actions_for(context.profile_id);
+    state
+        .action_runner
+        .explain(&action, &actions, context)
+        .await
+}
+
+#[tauri::command]
+pub fn list_running_actions(state: State<'_, AppState>) -> Result<Vec<RunningAction>> {
+    Ok(state.action_runner.list_running())
+}
+
+/// Cancels a queued or running invocation. Callers that need to cancel
+/// should pass their own `invocationId` to `run_action` / `test_action`.
+#[tauri::command]
+pub fn cancel_action(state: State<'_, AppState>, invocation_id: Uuid) -> Result<()> {
+    if state.action_runner.cancel(invocation_id) {
+        Ok(())
+    } else {
+        Err(AppError::Message(format!(
+            "no running action with invocation {invocation_id}"
+        )))
+    }
+}
+
+#[tauri::command]
+pub fn list_custom_handlers(state: State<'_, AppState>) -> Result<Vec<CustomHandlerInfo>> {
+    Ok(state.action_runner.custom_handlers().list())
 }
 
+/// Script languages usable in `RunScript` payloads, with whether each
+/// interpreter was found on this machine.
 #[tauri::command]
-pub async fn test_action(state: State<'_, AppState>, action: Action) -> Result<ActionEventPayload> {
-    // Test mode executes the provided action without persisting it.
-    state.action_runner.run(&action, state.inner()).await
+pub fn available_script_languages(state: State<'_, AppState>) -> Result<Vec<ScriptLanguageInfo>> {
+    Ok(state.action_runner.script_languages())
 }
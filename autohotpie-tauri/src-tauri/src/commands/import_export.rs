@@ -1,6 +1,7 @@
 use super::{AppError, AppState, Result};
 use crate::commands::profiles::emit_profiles_changed;
 use crate::services::import_export::ImportExportService;
+use crate::services::triggers;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use tauri::{AppHandle, Manager, Runtime, State};
@@ -71,7 +72,8 @@ pub fn import_profiles<R: Runtime>(
 ) -> Result<crate::services::import_export::ImportResult> {
     let service = ImportExportService::new(state.storage(), state.audit());
     let bundle = service.decode_bundle(&payload.data)?;
-    let (store, settings, result) = service.process_import_bundle(bundle)?;
+    let (store, mut settings, result) = service.process_import_bundle(bundle)?;
+    settings.keep_action_settings_of(&*lock_settings(&state)?);
 
     state.with_profiles_mut(|current| {
         *current = store.clone();
@@ -79,12 +81,14 @@ pub fn import_profiles<R: Runtime>(
     })?;
 
     state.storage.save_with_backup(&settings)?;
+    state.action_runner.apply_settings(&settings);
     {
         let mut guard = lock_settings(&state)?;
         *guard = settings;
     }
 
     emit_profiles_changed(&app, &state)?;
+    triggers::triggers_changed(&app);
 
     Ok(result)
 }
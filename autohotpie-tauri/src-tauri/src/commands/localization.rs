@@ -9,8 +9,10 @@ pub fn list_localization_languages() -> Vec<String> {
 #[tauri::command]
 pub fn get_localization_pack(language: Option<String>) -> Result<localization::LocalizationPack> {
     let code = language.unwrap_or_default();
-    localization::get_pack(&code)
-        .ok_or_else(|| AppError::Message(format!("language '{code}' not available")))
+    let pack = localization::get_pack(&code)
+        .ok_or_else(|| AppError::Message(format!("language '{code}' not available")))?;
+    localization::set_current(&pack.language);
+    Ok(pack)
 }
 
 #[tauri::command]
@@ -10,10 +10,11 @@ pub mod pie_overlay;
 pub mod profiles;
 pub mod settings;
 pub mod system;
+pub mod triggers;
 pub mod updates;
 
 use self::hotkeys::HotkeyState;
-use crate::domain::{Action, ActionId};
+use crate::domain::{Action, ActionId, ProfileId};
 use crate::models::Settings;
 use crate::services::action_runner::{
     ActionProvider, ActionRunner, ACTION_EXECUTED_EVENT, ACTION_FAILED_EVENT,
@@ -23,17 +24,20 @@ use crate::services::tray;
 use crate::services::{
     action_events::ActionEventsChannel,
     audit_log::AuditLogger,
-    connectivity, localization as localization_service,
+    connectivity,
+    custom_handlers::NotifyHandler,
+    localization as localization_service,
     pie_overlay as pie_overlay_service,
     profile_router::{self, ProfileRouterState},
     storage_guard,
     system_status::SystemStatus,
+    triggers::{self, TriggerState},
     update_checker::{self, UpdateChecker},
     window_info,
 };
 use crate::storage::profile_repository::{ProfileRecoveryInfo, ProfileStore};
 use crate::storage::StorageManager;
-use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::{ipc::InvokeError, App, AppHandle, Emitter, Manager, Runtime};
 use tokio::sync::broadcast::error::RecvError;
@@ -63,8 +67,9 @@ pub struct AppState {
     pub action_runner: ActionRunner,
     pub action_events: ActionEventsChannel,
     pub profiles: Mutex<ProfileStore>,
+    /// Bumped on every change made through [`AppState::with_profiles_mut`].
+    profiles_revision: AtomicU64,
     profiles_recovery: Mutex<Option<ProfileRecoveryInfo>>,
-    actions: Mutex<HashMap<ActionId, Action>>,
 }
 
 impl AppState {
@@ -76,26 +81,25 @@ impl AppState {
         &self.audit
     }
 
-    pub fn lookup_action(&self, id: &ActionId) -> Option<Action> {
-        self.actions
+    /// Resolves an action id for a run started from `profile_id`, which is
+    /// the router's choice rather than the stored active profile; see
+    /// [`ProfileStore::find_action`] for the lookup order.
+    pub fn lookup_action(&self, profile_id: Option<ProfileId>, id: &ActionId) -> Option<Action> {
+        self.profiles
             .lock()
             .ok()
-            .and_then(|guard| guard.get(id).cloned())
+            .and_then(|guard| guard.find_action(profile_id, id).cloned())
     }
 
-    pub fn replace_actions(&self, actions: Vec<Action>) {
-        if let Ok(mut guard) = self.actions.lock() {
-            *guard = collect_actions(&actions);
+    /// Provider resolving composite children in the same scope as
+    /// [`lookup_action`](Self::lookup_action).
+    pub fn actions_for(&self, profile_id: Option<ProfileId>) -> ScopedActions<'_> {
+        ScopedActions {
+            state: self,
+            profile_id,
         }
     }
 
-    pub fn actions_snapshot(&self) -> Vec<Action> {
-        self.actions
-            .lock()
-            .map(|guard| guard.values().cloned().collect())
-            .unwrap_or_default()
-    }
-
     pub fn action_events_channel(&self) -> ActionEventsChannel {
         self.action_events.clone()
     }
@@ -106,6 +110,7 @@ impl AppState {
     ) -> Result<R> {
         let mut guard = self.profiles.lock().map_err(|_| AppError::StatePoisoned)?;
         let output = f(&mut guard)?;
+        self.profiles_revision.fetch_add(1, Ordering::Relaxed);
         self.storage.save_profiles(&guard).map_err(AppError::from)?;
         if let Ok(mut recovery) = self.profiles_recovery.lock() {
             *recovery = None;
@@ -113,6 +118,12 @@ impl AppState {
         Ok(output)
     }
 
+    /// Changes whenever the stored profiles do. Read it while holding the
+    /// `profiles` lock to pair it with the matching store.
+    pub fn profiles_revision(&self) -> u64 {
+        self.profiles_revision.load(Ordering::Relaxed)
+    }
+
     pub fn profiles_snapshot(&self) -> Result<ProfileStore> {
         self.profiles
             .lock()
@@ -132,19 +143,16 @@ impl AppState {
             *guard = info;
         }
     }
+}
 
-    pub fn request_action<R>(
-        &self,
-        f: impl FnOnce(&mut HashMap<ActionId, Action>) -> R,
-    ) -> Result<R> {
-        let mut guard = self.actions.lock().map_err(|_| AppError::StatePoisoned)?;
-        Ok(f(&mut guard))
-    }
+pub struct ScopedActions<'a> {
+    state: &'a AppState,
+    profile_id: Option<ProfileId>,
 }
 
-impl ActionProvider for AppState {
+impl ActionProvider for ScopedActions<'_> {
     fn get_action(&self, id: &ActionId) -> Option<Action> {
-        self.lookup_action(id)
+        self.state.lookup_action(self.profile_id, id)
     }
 }
 
@@ -184,20 +192,24 @@ pub fn init<R: Runtime>(app: &mut App<R>) -> anyhow::Result<()> {
     let storage_mode = storage_guard::detect_mode(&storage);
     let shared_status = Arc::new(Mutex::new(SystemStatus::new(storage_mode)));
 
-    let actions = storage.load_actions().unwrap_or_default();
-    let actions_map = collect_actions(&actions);
+    let checker = Arc::new(UpdateChecker::new(version.clone())?);
 
     let action_runner = ActionRunner::new(
         storage.base_dir().to_path_buf(),
         audit.clone(),
         action_events.clone(),
-    );
+    )
+    .with_http_client(checker.http_client());
+    action_runner.apply_settings(&settings);
+    let custom_handlers = action_runner.custom_handlers();
+    custom_handlers.register(Arc::new(NotifyHandler::new(handle.clone())));
+    if let Err(err) = custom_handlers.load_external(storage.base_dir()) {
+        eprintln!("failed to load custom handlers: {err}");
+    }
 
     localization_service::init(&handle)?;
     pie_overlay_service::init(&handle)?;
 
-    let checker = Arc::new(UpdateChecker::new(version.clone())?);
-
     update_checker::emit_status(&handle, &checker.cached_status());
     update_checker::start_polling(handle.clone(), checker.clone());
 
@@ -208,8 +220,8 @@ pub fn init<R: Runtime>(app: &mut App<R>) -> anyhow::Result<()> {
         action_runner,
         action_events,
         profiles: Mutex::new(profiles),
+        profiles_revision: AtomicU64::new(0),
         profiles_recovery: Mutex::new(recovery.clone()),
-        actions: Mutex::new(actions_map),
     });
 
     app.manage(UpdatesState { checker });
@@ -236,8 +248,10 @@ pub fn init<R: Runtime>(app: &mut App<R>) -> anyhow::Result<()> {
                 Ok(payload) => {
                     let event_name = match payload.status {
                         crate::domain::ActionEventStatus::Success
-                        | crate::domain::ActionEventStatus::Skipped => ACTION_EXECUTED_EVENT,
-                        crate::domain::ActionEventStatus::Failure => ACTION_FAILED_EVENT,
+                        | crate::domain::ActionEventStatus::Skipped
+                        | crate::domain::ActionEventStatus::Cancelled => ACTION_EXECUTED_EVENT,
+                        crate::domain::ActionEventStatus::Failure
+                        | crate::domain::ActionEventStatus::TimedOut => ACTION_FAILED_EVENT,
                     };
                     let cloned = payload.clone();
                     if let Err(err) = dispatch_handle.emit(event_name, cloned) {
@@ -262,12 +276,14 @@ pub fn init<R: Runtime>(app: &mut App<R>) -> anyhow::Result<()> {
 
     connectivity::start_monitor(handle.clone(), shared_status.clone());
     storage_guard::start_monitor(handle.clone(), storage.clone(), shared_status.clone());
-    window_info::start_monitor(handle.clone(), shared_status);
+    window_info::start_monitor(handle.clone(), shared_status, window_info::default_source());
     #[cfg(all(feature = "tray-icon", not(target_os = "linux")))]
     if let Err(err) = crate::services::tray::setup_tray(&handle) {
         eprintln!("failed to set up tray icon: {err}");
     }
     profile_router::start_router(handle.clone());
+    app.manage(TriggerState::default());
+    triggers::start(handle.clone());
     #[cfg(feature = "tray-icon")]
     tauri::async_runtime::block_on(tray::ensure_tray_state(&handle)).ok();
 
@@ -286,14 +302,6 @@ pub fn current_version<R: Runtime>(app: &AppHandle<R>) -> String {
         .to_string()
 }
 
-fn collect_actions(actions: &[Action]) -> HashMap<ActionId, Action> {
-    let mut map = HashMap::with_capacity(actions.len());
-    for action in actions {
-        map.insert(action.id, action.clone());
-    }
-    map
-}
-
 #[tauri::command]
 pub fn resolve_active_profile<R: Runtime>(
     app: AppHandle<R>,
@@ -1,6 +1,7 @@
 use super::{AppError, AppState, Result};
 use crate::domain::profile::ProfileId;
 use crate::domain::validation::{validate_profile, DomainValidationError};
+use crate::domain::Action;
 use crate::services::profile_router;
 use crate::storage::profile_repository::{
     build_default_profile_record, ProfileRecord, ProfileRecoveryInfo, ProfileStore,
@@ -73,7 +74,7 @@ pub fn create_profile<R: Runtime>(
         }
         record.profile.name = final_name;
 
-        if let Err(errors) = validate_record(&record) {
+        if let Err(errors) = validate_record(&record, &store.actions) {
             let payload = json!({
                 "kind": "profile-validation",
                 "errors": errors.into_iter().map(|err| err.to_string()).collect::<Vec<_>>(),
@@ -127,14 +128,17 @@ pub fn save_profile<R: Runtime>(
 ) -> Result<ProfileRecord> {
     ensure_no_recovery(&state)?;
     record = normalize_record(record)?;
-    if let Err(errors) = validate_record(&record) {
-        let payload = json!({
-            "kind": "profile-validation",
-            "errors": errors.into_iter().map(|err| err.to_string()).collect::<Vec<_>>(),
-        });
-        return Err(AppError::Message(payload.to_string()));
-    }
     let updated = state.with_profiles_mut(|store| {
+        if let Err(errors) = validate_record(&record, &store.actions) {
+            let payload = json!({
+                "kind": "profile-validation",
+                "errors": errors.into_iter().map(|err| err.to_string()).collect::<Vec<_>>(),
+            });
+            return Err(AppError::Message(payload.to_string()));
+        }
+        for action in &mut record.actions {
+            action.untrusted |= store.is_untrusted(&action.id);
+        }
         upsert_record(store, record.clone())?;
         Ok(record.clone())
     })?;
@@ -176,6 +180,13 @@ pub fn activate_profile<R: Runtime>(
         .map_err(|err| AppError::Message(err.to_string()))
 }
 
+/// How the router chooses between profiles matching the same window, most
+/// significant first.
+#[tauri::command]
+pub fn profile_ranking_order() -> Vec<profile_router::RankingCriterion> {
+    profile_router::RANKING_ORDER.to_vec()
+}
+
 #[tauri::command]
 pub fn open_profiles_backups<R: Runtime>(
     app: AppHandle<R>,
@@ -262,8 +273,15 @@ fn format_timestamp(moment: OffsetDateTime) -> String {
         .unwrap_or_else(|_| moment.to_string())
 }
 
-fn validate_record(record: &ProfileRecord) -> std::result::Result<(), Vec<DomainValidationError>> {
-    validate_profile(&record.profile, &record.menus, &record.actions)
+fn validate_record(
+    record: &ProfileRecord,
+    shared_actions: &[Action],
+) -> std::result::Result<(), Vec<DomainValidationError>> {
+    validate_profile(
+        &record.profile,
+        &record.menus,
+        record.actions.iter().chain(shared_actions),
+    )
 }
 
 #[cfg(test)]
@@ -271,9 +289,7 @@ mod tests {
     use super::*;
     use crate::domain::action::MacroStepDefinition;
     use crate::domain::pie_menu::{PieAppearance, PieMenu, PieSlice};
-    use crate::domain::{
-        ActionDefinition, ActionId, MacroStepKind, PieMenuId, PieSliceId, Profile,
-    };
+    use crate::domain::{ActionId, ActionPayload, MacroStepKind, PieMenuId, PieSliceId, Profile};
 
     fn sample_record() -> ProfileRecord {
         let action_id = ActionId::new();
@@ -286,6 +302,7 @@ mod tests {
             activation_rules: Vec::new(),
             root_menu: PieMenuId::new(),
             hold_to_open: false,
+            priority: 0,
         };
 
         let mut menu = PieMenu {
@@ -312,22 +329,22 @@ mod tests {
             order: 1,
         });
 
-        let action = ActionDefinition {
-            id: action_id,
-            name: "Macro".to_string(),
-            description: None,
-            timeout_ms: 3000,
-            last_validated_at: None,
-            steps: vec![MacroStepDefinition {
-                id: ActionId::new(),
-                order: 0,
-                kind: MacroStepKind::Keys {
-                    keys: "Ctrl+Alt+P".to_string(),
-                    repeat: 1,
-                },
-                note: None,
-            }],
-        };
+        let mut action = Action::new(
+            "Macro",
+            ActionPayload::Macro {
+                steps: vec![MacroStepDefinition {
+                    id: ActionId::new(),
+                    order: 0,
+                    kind: MacroStepKind::Keys {
+                        keys: "Ctrl+Alt+P".to_string(),
+                        repeat: 1,
+                    },
+                    retry: None,
+                    note: None,
+                }],
+            },
+        );
+        action.id = action_id;
 
         ProfileRecord {
             profile,
@@ -342,7 +359,7 @@ mod tests {
     fn validate_record_fails_without_actions() {
         let mut record = sample_record();
         record.actions.clear();
-        let outcome = validate_record(&record);
+        let outcome = validate_record(&record, &[]);
         assert!(
             outcome.is_err(),
             "expected validation failure for missing action"
@@ -359,10 +376,21 @@ mod tests {
     #[test]
     fn validate_record_succeeds_when_actions_present() {
         let record = sample_record();
-        let outcome = validate_record(&record);
+        let outcome = validate_record(&record, &[]);
         assert!(
             outcome.is_ok(),
             "expected validation success, got {outcome:?}"
         );
     }
+
+    #[test]
+    fn validate_record_resolves_shared_actions() {
+        let mut record = sample_record();
+        let shared = std::mem::take(&mut record.actions);
+        let outcome = validate_record(&record, &shared);
+        assert!(
+            outcome.is_ok(),
+            "expected shared actions to satisfy slices, got {outcome:?}"
+        );
+    }
 }
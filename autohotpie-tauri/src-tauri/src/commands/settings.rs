@@ -23,6 +23,7 @@ pub fn save_settings<R: Runtime>(
     let version = current_version(&app);
     settings.set_app_version(&version);
     state.storage.save_with_backup(&settings)?;
+    state.action_runner.apply_settings(&settings);
 
     {
         let mut guard = lock_settings(&state)?;
@@ -54,6 +55,7 @@ pub fn reset_settings<R: Runtime>(
     let mut settings = Settings::default();
     settings.set_app_version(&version);
     state.storage.save_with_backup(&settings)?;
+    state.action_runner.apply_settings(&settings);
 
     {
         let mut guard = lock_settings(&state)?;
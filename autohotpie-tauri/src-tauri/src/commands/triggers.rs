@@ -0,0 +1,35 @@
+use super::{AppState, Result};
+use crate::domain::{Trigger, TriggerId};
+use crate::services::triggers;
+use tauri::{AppHandle, Runtime, State};
+
+#[tauri::command]
+pub fn list_triggers(state: State<'_, AppState>) -> Result<Vec<Trigger>> {
+    Ok(state.profiles_snapshot()?.triggers)
+}
+
+/// Replaces the stored triggers after validating them.
+#[tauri::command]
+pub fn save_triggers<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, AppState>,
+    triggers: Vec<Trigger>,
+) -> Result<Vec<Trigger>> {
+    state.with_profiles_mut(|store| {
+        triggers::validate(&triggers, store)?;
+        store.triggers = triggers.clone();
+        Ok(())
+    })?;
+    state.audit().log("INFO", "Triggers saved")?;
+    triggers::triggers_changed(&app);
+    Ok(triggers)
+}
+
+#[tauri::command]
+pub fn set_trigger_enabled<R: Runtime>(
+    app: AppHandle<R>,
+    trigger_id: TriggerId,
+    enabled: bool,
+) -> Result<Trigger> {
+    triggers::set_enabled(&app, trigger_id, enabled)
+}
@@ -1,7 +1,9 @@
 #![allow(dead_code)]
 
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -40,8 +42,29 @@ fn default_enabled() -> bool {
     true
 }
 
+pub const DEFAULT_MACRO_TIMEOUT_MS: u32 = 3000;
+
+/// How a run interacts with other runs in the action queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConcurrencyPolicy {
+    /// Waits until nothing else runs and keeps every other run waiting.
+    Exclusive,
+    /// Takes one slot; several runs of the same action may overlap.
+    Parallel,
+    /// Ignored while another run of the same action is queued or running.
+    DropIfRunning,
+    /// Takes one slot; runs of the same action execute one after another.
+    #[default]
+    Queue,
+}
+
+/// The single action model shared by storage, validation and the runner.
+///
+/// Deserialization also accepts the schema v1 `ActionDefinition` shape (a bare
+/// list of macro steps) so older profile stores and editors keep loading.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", from = "StoredAction")]
 pub struct Action {
     pub id: ActionId,
     pub name: String,
@@ -50,6 +73,20 @@ pub struct Action {
     pub payload: ActionPayload,
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+    /// Per-run limit in milliseconds. `0` means no limit, matching the
+    /// global `actions.timeoutMs` setting; it is stored as `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u32>,
+    #[serde(default)]
+    pub concurrency: ConcurrencyPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// Set on actions that arrived through an import bundle. Running one
+    /// needs confirmation until the user marks it reviewed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub untrusted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_validated_at: Option<String>,
 }
 
 impl Action {
@@ -60,6 +97,108 @@ impl Action {
             description: None,
             payload,
             enabled: true,
+            timeout_ms: None,
+            concurrency: ConcurrencyPolicy::default(),
+            retry: None,
+            untrusted: false,
+            last_validated_at: None,
+        }
+    }
+
+    /// Ids of other actions this action delegates to, including the
+    /// branches of parallel groups.
+    pub fn referenced_actions(&self) -> Vec<ActionId> {
+        match &self.payload {
+            ActionPayload::Composite { actions } => actions
+                .iter()
+                .flat_map(|step| step.children())
+                .map(|child| child.action)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl From<ActionDefinition> for Action {
+    fn from(definition: ActionDefinition) -> Self {
+        Self {
+            id: definition.id,
+            name: definition.name,
+            description: definition.description,
+            payload: ActionPayload::Macro {
+                steps: definition.steps,
+            },
+            enabled: true,
+            timeout_ms: Some(definition.timeout_ms).filter(|ms| *ms > 0),
+            concurrency: ConcurrencyPolicy::default(),
+            retry: None,
+            untrusted: false,
+            last_validated_at: definition.last_validated_at,
+        }
+    }
+}
+
+enum StoredAction {
+    Current(CurrentAction),
+    Legacy(ActionDefinition),
+}
+
+/// Picks the shape by its fields instead of trying both, so a mistake in
+/// either reports the offending field rather than a generic mismatch.
+impl<'de> Deserialize<'de> for StoredAction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        // v1 definitions keep their macro steps at the top level.
+        if value.get("payload").is_none() && value.get("steps").is_some() {
+            ActionDefinition::deserialize(value)
+                .map(StoredAction::Legacy)
+                .map_err(D::Error::custom)
+        } else {
+            CurrentAction::deserialize(value)
+                .map(StoredAction::Current)
+                .map_err(D::Error::custom)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CurrentAction {
+    id: ActionId,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    payload: ActionPayload,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    #[serde(default)]
+    timeout_ms: Option<u32>,
+    #[serde(default)]
+    concurrency: ConcurrencyPolicy,
+    #[serde(default)]
+    retry: Option<RetryPolicy>,
+    #[serde(default)]
+    untrusted: bool,
+    #[serde(default)]
+    last_validated_at: Option<String>,
+}
+
+impl From<StoredAction> for Action {
+    fn from(stored: StoredAction) -> Self {
+        match stored {
+            StoredAction::Current(action) => Self {
+                id: action.id,
+                name: action.name,
+                description: action.description,
+                payload: action.payload,
+                enabled: action.enabled,
+                timeout_ms: action.timeout_ms.filter(|ms| *ms > 0),
+                concurrency: action.concurrency,
+                retry: action.retry,
+                untrusted: action.untrusted,
+                last_validated_at: action.last_validated_at,
+            },
+            StoredAction::Legacy(definition) => definition.into(),
         }
     }
 }
@@ -67,12 +206,16 @@ impl Action {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case", tag = "kind")]
 pub enum ActionPayload {
+    /// Starts a program. Bare names are looked up on the `PATH` the child
+    /// will see.
     LaunchProgram {
         executable: String,
         #[serde(default)]
         arguments: Vec<String>,
         #[serde(default, skip_serializing_if = "Option::is_none")]
         working_dir: Option<String>,
+        #[serde(flatten)]
+        options: LaunchOptions,
     },
     SendKeys {
         sequence: String,
@@ -87,15 +230,526 @@ pub enum ActionPayload {
         command: String,
     },
     Composite {
-        actions: Vec<ActionId>,
+        actions: Vec<CompositeStep>,
     },
     Custom {
         handler: String,
         #[serde(default)]
         params: Value,
     },
+    Macro {
+        #[serde(default)]
+        steps: Vec<MacroStepDefinition>,
+    },
+    Window {
+        operation: WindowOperation,
+    },
+    Clipboard {
+        operation: ClipboardOperation,
+    },
+    /// Pastes `text` through the clipboard, which is much faster than
+    /// typing long text with `SendKeys`.
+    TypeText {
+        text: String,
+        /// Keys that paste in the target app; `^v` when unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        paste_keys: Option<String>,
+        /// Puts the previous clipboard text back after pasting.
+        #[serde(default)]
+        restore_clipboard: bool,
+    },
+    /// Sends an HTTP request. The response body becomes the action's
+    /// stdout and the status code its exit code.
+    HttpRequest {
+        #[serde(default = "default_http_method")]
+        method: String,
+        url: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        body: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_ms: Option<u32>,
+        /// Statuses that count as success; any 2xx when empty.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        success_status: Vec<StatusMatcher>,
+    },
+    /// Opens a URL, file or folder in its default application. Relative
+    /// paths are resolved against the data directory.
+    Open {
+        target: String,
+    },
+}
+
+fn default_http_method() -> String {
+    "GET".into()
+}
+
+impl ActionPayload {
+    pub fn kind(&self) -> PayloadKind {
+        match self {
+            ActionPayload::LaunchProgram { .. } => PayloadKind::LaunchProgram,
+            ActionPayload::SendKeys { .. } => PayloadKind::SendKeys,
+            ActionPayload::RunScript { .. } => PayloadKind::RunScript,
+            ActionPayload::SystemCommand { .. } => PayloadKind::SystemCommand,
+            ActionPayload::Composite { .. } => PayloadKind::Composite,
+            ActionPayload::Custom { .. } => PayloadKind::Custom,
+            ActionPayload::Macro { .. } => PayloadKind::Macro,
+            ActionPayload::Window { .. } => PayloadKind::Window,
+            ActionPayload::Clipboard { .. } => PayloadKind::Clipboard,
+            ActionPayload::TypeText { .. } => PayloadKind::TypeText,
+            ActionPayload::HttpRequest { .. } => PayloadKind::HttpRequest,
+            ActionPayload::Open { .. } => PayloadKind::Open,
+        }
+    }
+}
+
+/// The `kind` tag of an [`ActionPayload`], without its data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadKind {
+    LaunchProgram,
+    SendKeys,
+    RunScript,
+    SystemCommand,
+    Composite,
+    Custom,
+    Macro,
+    Window,
+    Clipboard,
+    TypeText,
+    HttpRequest,
+    Open,
+}
+
+impl std::fmt::Display for PayloadKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PayloadKind::LaunchProgram => "launch_program",
+            PayloadKind::SendKeys => "send_keys",
+            PayloadKind::RunScript => "run_script",
+            PayloadKind::SystemCommand => "system_command",
+            PayloadKind::Composite => "composite",
+            PayloadKind::Custom => "custom",
+            PayloadKind::Macro => "macro",
+            PayloadKind::Window => "window",
+            PayloadKind::Clipboard => "clipboard",
+            PayloadKind::TypeText => "type_text",
+            PayloadKind::HttpRequest => "http_request",
+            PayloadKind::Open => "open",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Optional settings of a `launch_program` payload. Relative paths are
+/// resolved against the data directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchOptions {
+    #[serde(default, skip_serializing_if = "LaunchEnvironment::is_empty")]
+    pub env: LaunchEnvironment,
+    /// File read as the program's standard input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+    /// File the program's standard output is appended to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<String>,
+    /// File the program's standard error is appended to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+    #[serde(default, skip_serializing_if = "LaunchMode::is_detached")]
+    pub mode: LaunchMode,
+    /// Focuses an open window of the program instead of starting another
+    /// copy.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub single_instance: bool,
+}
+
+/// Changes to the environment the program inherits, applied in field
+/// order: `unset`, then `set`, then `path_prepend`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchEnvironment {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub set: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unset: Vec<String>,
+    /// Directories searched before the inherited `PATH`, first one first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_prepend: Vec<String>,
+}
+
+impl LaunchEnvironment {
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.unset.is_empty() && self.path_prepend.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchMode {
+    /// Starts the program and succeeds right away.
+    #[default]
+    Detached,
+    /// Waits for the program to exit; a non-zero exit code fails the
+    /// action and unredirected output is captured like a script's.
+    Wait,
+}
+
+impl LaunchMode {
+    pub fn is_detached(&self) -> bool {
+        matches!(self, LaunchMode::Detached)
+    }
+}
+
+/// What a `window` payload does. Every operation except `focus_or_launch`
+/// acts on the foreground window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum WindowOperation {
+    Minimize,
+    Maximize,
+    Close,
+    /// Moves the window to monitor `monitor`, counted from 1 in the order
+    /// the system reports them, keeping its relative position.
+    MoveToMonitor {
+        monitor: u32,
+    },
+    /// Fills part of the work area of the monitor the window is on.
+    Snap {
+        position: SnapPosition,
+    },
+    ToggleAlwaysOnTop,
+    /// Focuses the topmost window of a process named `process_name`, or
+    /// launches `executable` (default: `process_name`) when there is none.
+    FocusOrLaunch {
+        process_name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        executable: Option<String>,
+        #[serde(default)]
+        arguments: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapPosition {
+    LeftHalf,
+    RightHalf,
+    TopHalf,
+    BottomHalf,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// What a `clipboard` payload does with the clipboard text. Text-changing
+/// operations skip when the clipboard holds no text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ClipboardOperation {
+    /// Reports the text as the action's output, e.g. for
+    /// `{{steps.NAME.stdout}}`.
+    Read,
+    Set {
+        text: String,
+    },
+    Append {
+        text: String,
+    },
+    Uppercase,
+    Lowercase,
+    Trim,
+    /// Replaces every match of `pattern`; `replacement` may refer to groups
+    /// as `$1` or `${name}`.
+    RegexReplace {
+        pattern: String,
+        replacement: String,
+    },
+}
+
+/// A status code (`204`) or range (`"2xx"`, `"200-299"`) that an
+/// `http_request` payload accepts as success.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StatusMatcher {
+    Code(u16),
+    Pattern(String),
+}
+
+impl StatusMatcher {
+    /// `Err` describes a malformed pattern.
+    pub fn matches(&self, status: u16) -> Result<bool, String> {
+        let (min, max) = match self {
+            Self::Code(code) => (*code, *code),
+            Self::Pattern(pattern) => parse_status_pattern(pattern.trim())
+                .ok_or_else(|| format!("invalid status pattern '{pattern}'"))?,
+        };
+        Ok((min..=max).contains(&status))
+    }
+}
+
+fn parse_status_pattern(pattern: &str) -> Option<(u16, u16)> {
+    if let Some(class) = pattern
+        .strip_suffix("xx")
+        .or_else(|| pattern.strip_suffix("XX"))
+    {
+        let class: u16 = class.parse().ok().filter(|class| (1..=5).contains(class))?;
+        return Some((class * 100, class * 100 + 99));
+    }
+    if let Some((min, max)) = pattern.split_once('-') {
+        let min: u16 = min.trim().parse().ok()?;
+        let max: u16 = max.trim().parse().ok()?;
+        return (min <= max).then_some((min, max));
+    }
+    pattern.parse().ok().map(|code| (code, code))
+}
+
+/// One entry of a composite action: a single child, or a group of children
+/// that run side by side.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CompositeStep {
+    Parallel(ParallelGroup),
+    Child(CompositeChild),
+}
+
+impl CompositeStep {
+    pub fn children(&self) -> &[CompositeChild] {
+        match self {
+            CompositeStep::Parallel(group) => &group.parallel,
+            CompositeStep::Child(child) => std::slice::from_ref(child),
+        }
+    }
+
+    pub fn condition(&self) -> Option<&StepCondition> {
+        match self {
+            CompositeStep::Parallel(group) => group.when.as_ref(),
+            CompositeStep::Child(child) => child.when.as_ref(),
+        }
+    }
+
+    pub fn continue_on_error(&self) -> bool {
+        match self {
+            CompositeStep::Parallel(group) => group.continue_on_error,
+            CompositeStep::Child(child) => child.continue_on_error,
+        }
+    }
+
+    /// Key under which the step's result is exposed as `{{steps.<name>.*}}`.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            CompositeStep::Parallel(group) => group.name.as_deref(),
+            CompositeStep::Child(child) => child.name.as_deref(),
+        }
+    }
+}
+
+impl From<ActionId> for CompositeStep {
+    fn from(action: ActionId) -> Self {
+        CompositeStep::Child(action.into())
+    }
+}
+
+/// A child action inside a composite. Children without options are stored as
+/// a bare action id, which is also the schema v2 shape.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredCompositeChild", into = "StoredCompositeChild")]
+pub struct CompositeChild {
+    pub action: ActionId,
+    pub name: Option<String>,
+    pub when: Option<StepCondition>,
+    pub continue_on_error: bool,
+}
+
+impl From<ActionId> for CompositeChild {
+    fn from(action: ActionId) -> Self {
+        Self {
+            action,
+            name: None,
+            when: None,
+            continue_on_error: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredCompositeChild {
+    Id(ActionId),
+    #[serde(rename_all = "camelCase")]
+    Detailed {
+        action: ActionId,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        when: Option<StepCondition>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        continue_on_error: bool,
+    },
+}
+
+impl From<StoredCompositeChild> for CompositeChild {
+    fn from(stored: StoredCompositeChild) -> Self {
+        match stored {
+            StoredCompositeChild::Id(action) => action.into(),
+            StoredCompositeChild::Detailed {
+                action,
+                name,
+                when,
+                continue_on_error,
+            } => Self {
+                action,
+                name,
+                when,
+                continue_on_error,
+            },
+        }
+    }
+}
+
+impl From<CompositeChild> for StoredCompositeChild {
+    fn from(child: CompositeChild) -> Self {
+        if child.name.is_none() && child.when.is_none() && !child.continue_on_error {
+            return StoredCompositeChild::Id(child.action);
+        }
+        StoredCompositeChild::Detailed {
+            action: child.action,
+            name: child.name,
+            when: child.when,
+            continue_on_error: child.continue_on_error,
+        }
+    }
 }
 
+/// Children started together. The group finishes according to `join`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParallelGroup {
+    pub parallel: Vec<CompositeChild>,
+    #[serde(default)]
+    pub join: JoinMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<StepCondition>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub continue_on_error: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinMode {
+    /// Waits for every branch. The first failure stops the remaining
+    /// branches and fails the group.
+    #[default]
+    All,
+    /// Finishes with the first branch that succeeds and stops the others;
+    /// fails only if every branch fails.
+    Any,
+}
+
+/// Guard checked right before a composite step runs. Steps whose guard does
+/// not hold are passed over without affecting `previous_*` checks.
+///
+/// A step counts as failed when it ended with `failure` or `timed_out`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum StepCondition {
+    /// Foreground process name equals `value` (ignoring case), or matches
+    /// it as a regular expression when prefixed with `regex:`, as in
+    /// activation rules.
+    ProcessMatches {
+        value: String,
+    },
+    WindowTitleMatches {
+        value: String,
+    },
+    PreviousSucceeded,
+    PreviousFailed,
+    StepSucceeded {
+        step: String,
+    },
+    StepFailed {
+        step: String,
+    },
+}
+
+/// Re-runs a failed action or macro step. Attempts after the first wait
+/// `delay_ms`, doubled per attempt with `exponential` backoff and capped at
+/// `max_delay_ms`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicy {
+    /// Total attempts including the first one.
+    pub max_attempts: u32,
+    #[serde(default)]
+    pub backoff: Backoff,
+    #[serde(default = "default_retry_delay")]
+    pub delay_ms: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_delay_ms: Option<u32>,
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<RetryOn>,
+}
+
+pub const MAX_RETRY_ATTEMPTS: u32 = 20;
+
+fn default_retry_delay() -> u32 {
+    500
+}
+
+fn default_retry_on() -> Vec<RetryOn> {
+    vec![RetryOn::Error, RetryOn::Failure]
+}
+
+impl RetryPolicy {
+    /// `max_attempts` clamped to `1..=MAX_RETRY_ATTEMPTS`.
+    pub fn attempts(&self) -> u32 {
+        self.max_attempts.clamp(1, MAX_RETRY_ATTEMPTS)
+    }
+
+    /// Wait before `attempt` (2 for the first retry).
+    pub fn delay_before(&self, attempt: u32) -> Duration {
+        let base = u64::from(self.delay_ms);
+        let delay = match self.backoff {
+            Backoff::Fixed => base,
+            Backoff::Exponential => base.saturating_mul(1u64 << attempt.saturating_sub(2).min(32)),
+        };
+        let delay = match self.max_delay_ms {
+            Some(cap) => delay.min(u64::from(cap)),
+            None => delay,
+        };
+        Duration::from_millis(delay)
+    }
+
+    pub fn retries(&self, kind: RetryOn) -> bool {
+        self.retry_on.contains(&kind)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backoff {
+    #[default]
+    Fixed,
+    Exponential,
+}
+
+/// Kind of failed attempt a [`RetryPolicy`] can retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryOn {
+    /// The payload could not run at all, e.g. the program failed to start.
+    Error,
+    /// It ran and reported failure, e.g. a non-zero exit code.
+    Failure,
+    /// It exceeded its timeout.
+    TimedOut,
+}
+
+/// Schema v1 macro shape stored inside profile records. Only read during
+/// migration; everything else works with [`Action`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ActionDefinition {
@@ -107,12 +761,13 @@ pub struct ActionDefinition {
     pub timeout_ms: u32,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_validated_at: Option<String>,
-    #[serde(default)]
+    // Required so a malformed current-shape action is reported instead of
+    // silently falling back to an empty legacy macro.
     pub steps: Vec<MacroStepDefinition>,
 }
 
 fn default_macro_timeout() -> u32 {
-    3000
+    DEFAULT_MACRO_TIMEOUT_MS
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -123,6 +778,8 @@ pub struct MacroStepDefinition {
     #[serde(flatten)]
     pub kind: MacroStepKind,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub note: Option<String>,
 }
 
@@ -43,6 +43,26 @@ pub enum ActionEventStatus {
     Success,
     Failure,
     Skipped,
+    TimedOut,
+    Cancelled,
+}
+
+/// Output captured from a script or command run. Streams keep only their
+/// last bytes; the `*_truncated` flags say whether anything was cut.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Response status of an `http_request` payload, which has no exit code.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    pub stdout: String,
+    pub stderr: String,
+    #[serde(default)]
+    pub stdout_truncated: bool,
+    #[serde(default)]
+    pub stderr_truncated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +78,19 @@ pub struct ActionEventPayload {
     pub timestamp: OffsetDateTime,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub invocation_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<ActionOutput>,
+    /// Which attempt this event reports, starting at 1. Retried actions and
+    /// steps emit one event per attempt.
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    /// Profile that was active when the run started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_id: Option<ProfileId>,
+}
+
+fn first_attempt() -> u32 {
+    1
 }
 
 impl ActionEventPayload {
@@ -80,6 +113,24 @@ impl ActionEventPayload {
             message,
             timestamp,
             invocation_id,
+            output: None,
+            attempt: 1,
+            profile_id: None,
         }
     }
+
+    pub fn with_output(mut self, output: Option<ActionOutput>) -> Self {
+        self.output = output;
+        self
+    }
+
+    pub fn with_attempt(mut self, attempt: u32) -> Self {
+        self.attempt = attempt;
+        self
+    }
+
+    pub fn with_profile(mut self, profile_id: Option<ProfileId>) -> Self {
+        self.profile_id = profile_id;
+        self
+    }
 }
@@ -5,10 +5,16 @@ pub mod context;
 pub mod context_rules;
 pub mod pie_menu;
 pub mod profile;
+pub mod trigger;
 pub mod validation;
 
-pub use action::{Action, ActionDefinition, ActionId, ActionPayload, MacroStepKind};
-pub use context::{ActionEventPayload, ActionEventStatus};
+pub use action::{
+    Action, ActionId, ActionPayload, ClipboardOperation, CompositeChild, CompositeStep,
+    ConcurrencyPolicy, JoinMode, LaunchEnvironment, LaunchMode, LaunchOptions, MacroStepKind,
+    PayloadKind, RetryOn, RetryPolicy, SnapPosition, StatusMatcher, StepCondition, WindowOperation,
+};
+pub use context::{ActionEventPayload, ActionEventStatus, ActionOutput};
 pub use context_rules::MatchMode;
 pub use pie_menu::{PieMenu, PieMenuId, PieSliceId};
 pub use profile::{Profile, ProfileId};
+pub use trigger::{Trigger, TriggerEvent, TriggerId};
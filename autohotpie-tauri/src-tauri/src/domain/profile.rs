@@ -40,6 +40,10 @@ fn is_false(value: &bool) -> bool {
     !*value
 }
 
+fn is_zero(value: &i32) -> bool {
+    *value == 0
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Profile {
@@ -56,6 +60,10 @@ pub struct Profile {
     pub root_menu: PieMenuId,
     #[serde(default, skip_serializing_if = "is_false")]
     pub hold_to_open: bool,
+    /// When several profiles match the foreground window, the highest
+    /// priority wins; see `profile_router::RANKING_ORDER`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i32,
 }
 
 impl Profile {
@@ -69,6 +77,7 @@ impl Profile {
             activation_rules: Vec::new(),
             root_menu,
             hold_to_open: false,
+            priority: 0,
         }
     }
 }
@@ -82,6 +91,12 @@ pub enum ActivationMatchMode {
     WindowClass,
     ScreenArea,
     Custom,
+    /// Every nested rule matches.
+    All,
+    /// At least one nested rule matches.
+    Any,
+    /// None of the nested rules match.
+    Not,
 }
 
 impl Default for ActivationMatchMode {
@@ -90,6 +105,14 @@ impl Default for ActivationMatchMode {
     }
 }
 
+impl ActivationMatchMode {
+    /// Whether the rule combines its nested `rules` instead of matching a
+    /// value itself.
+    pub fn is_group(&self) -> bool {
+        matches!(self, Self::All | Self::Any | Self::Not)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ActivationRule {
@@ -105,4 +128,7 @@ pub struct ActivationRule {
     pub case_sensitive: Option<bool>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub screen_area: Option<ScreenArea>,
+    /// Nested rules of an `all`, `any` or `not` group.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ActivationRule>,
 }
@@ -0,0 +1,69 @@
+use super::{action::ActionId, profile::ProfileId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TriggerId(pub Uuid);
+
+impl TriggerId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for TriggerId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Uuid> for TriggerId {
+    fn from(value: Uuid) -> Self {
+        Self(value)
+    }
+}
+
+impl std::fmt::Display for TriggerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Runs an action when something other than a hotkey or slice happens.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trigger {
+    #[serde(default)]
+    pub id: TriggerId,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub action: ActionId,
+    pub on: TriggerEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum TriggerEvent {
+    /// Five-field cron expression (`minute hour day-of-month month
+    /// day-of-week`) in local time.
+    Schedule { cron: String },
+    /// The profile router switched to the profile.
+    ProfileActivated { profile: ProfileId },
+    /// The profile router switched away from the profile.
+    ProfileDeactivated { profile: ProfileId },
+    /// Once, when the app has started.
+    Startup,
+    /// A file under `path` was created, modified or removed. Relative paths
+    /// are resolved against the data directory.
+    FileChanged {
+        path: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        recursive: bool,
+    },
+}
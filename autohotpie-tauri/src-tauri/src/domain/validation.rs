@@ -1,5 +1,5 @@
 use super::{ActionId, PieMenu, PieMenuId, PieSliceId, Profile, ProfileId};
-use crate::domain::action::ActionDefinition;
+use crate::domain::action::Action;
 use std::collections::{HashMap, HashSet};
 use thiserror::Error;
 
@@ -21,6 +21,8 @@ pub enum DomainValidationError {
         slice: PieSliceId,
         action: ActionId,
     },
+    #[error("action {action} references missing action {child}")]
+    MissingReferencedAction { action: ActionId, child: ActionId },
     #[error("pie menu {menu} slice {slice} references missing child menu {child}")]
     MissingChildMenu {
         menu: PieMenuId,
@@ -49,10 +51,13 @@ pub enum DomainValidationError {
     },
 }
 
-pub fn validate_profile(
+/// Validates a profile against the actions it can reach. Callers pass the
+/// profile's own actions followed by the shared library, mirroring the order
+/// used by the action runner when resolving ids.
+pub fn validate_profile<'a>(
     profile: &Profile,
     menus: &[PieMenu],
-    actions: &[ActionDefinition],
+    actions: impl IntoIterator<Item = &'a Action>,
 ) -> Result<(), Vec<DomainValidationError>> {
     let mut errors: Vec<DomainValidationError> = Vec::new();
     if profile.name.trim().is_empty() {
@@ -62,8 +67,10 @@ pub fn validate_profile(
     }
 
     let menu_map: HashMap<PieMenuId, &PieMenu> = menus.iter().map(|menu| (menu.id, menu)).collect();
-    let action_map: HashMap<ActionId, &ActionDefinition> =
-        actions.iter().map(|action| (action.id, action)).collect();
+    let mut action_map: HashMap<ActionId, &Action> = HashMap::new();
+    for action in actions {
+        action_map.entry(action.id).or_insert(action);
+    }
 
     if !menu_map.contains_key(&profile.root_menu) {
         errors.push(DomainValidationError::MissingRootMenu {
@@ -76,6 +83,8 @@ pub fn validate_profile(
         validate_menu(menu, &menu_map, &action_map, &mut errors);
     }
 
+    validate_action_references(&action_map, &mut errors);
+
     if menu_map.contains_key(&profile.root_menu) {
         enforce_depth_limits(profile.root_menu, &menu_map, &mut errors);
     }
@@ -90,7 +99,7 @@ pub fn validate_profile(
 fn validate_menu(
     menu: &PieMenu,
     menu_map: &HashMap<PieMenuId, &PieMenu>,
-    action_map: &HashMap<ActionId, &ActionDefinition>,
+    action_map: &HashMap<ActionId, &Action>,
     errors: &mut Vec<DomainValidationError>,
 ) {
     let mut seen_orders: HashSet<u32> = HashSet::new();
@@ -147,6 +156,24 @@ fn validate_menu(
     }
 }
 
+fn validate_action_references(
+    action_map: &HashMap<ActionId, &Action>,
+    errors: &mut Vec<DomainValidationError>,
+) {
+    let mut actions: Vec<&Action> = action_map.values().copied().collect();
+    actions.sort_by_key(|action| action.id.as_uuid());
+    for action in actions {
+        for child in action.referenced_actions() {
+            if !action_map.contains_key(&child) {
+                errors.push(DomainValidationError::MissingReferencedAction {
+                    action: action.id,
+                    child,
+                });
+            }
+        }
+    }
+}
+
 fn enforce_depth_limits(
     root: PieMenuId,
     menu_map: &HashMap<PieMenuId, &PieMenu>,
@@ -189,17 +216,11 @@ fn traverse_depth(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::action::ActionPayload;
     use crate::domain::pie_menu::{PieAppearance, PieSlice};
 
-    fn sample_action() -> ActionDefinition {
-        ActionDefinition {
-            id: ActionId::new(),
-            name: "Launch".to_string(),
-            description: None,
-            timeout_ms: 3000,
-            last_validated_at: None,
-            steps: Vec::new(),
-        }
+    fn sample_action() -> Action {
+        Action::new("Launch", ActionPayload::Macro { steps: Vec::new() })
     }
 
     fn sample_menu(action_id: ActionId) -> PieMenu {
@@ -242,6 +263,7 @@ mod tests {
             activation_rules: vec![],
             root_menu: menu.id,
             hold_to_open: false,
+            priority: 0,
         };
 
         let result = validate_profile(&profile, &[menu.clone()], &[action.clone()]);
@@ -261,6 +283,7 @@ mod tests {
             activation_rules: vec![],
             root_menu: menu.id,
             hold_to_open: false,
+            priority: 0,
         };
 
         let broken_slice = PieSlice {
@@ -312,6 +335,7 @@ mod tests {
             activation_rules: vec![],
             root_menu: menu.id,
             hold_to_open: false,
+            priority: 0,
         };
 
         let result = validate_profile(&profile, &[menu.clone()], &[action.clone()]);
@@ -346,6 +370,7 @@ mod tests {
             activation_rules: vec![],
             root_menu: menu.id,
             hold_to_open: false,
+            priority: 0,
         };
 
         let result = validate_profile(&profile, &[menu.clone()], &[action.clone()]);
@@ -425,6 +450,7 @@ mod tests {
             activation_rules: vec![],
             root_menu: root.id,
             hold_to_open: false,
+            priority: 0,
         };
 
         let result = validate_profile(&profile, &menus, &[action]);
@@ -436,4 +462,38 @@ mod tests {
                 || *offending == level_four.id
         )));
     }
+
+    #[test]
+    fn validate_profile_checks_composite_references() {
+        let shared = sample_action();
+        let missing = ActionId::new();
+        let composite = Action::new(
+            "Chain",
+            ActionPayload::Composite {
+                actions: vec![shared.id.into(), missing.into()],
+            },
+        );
+        let menu = sample_menu(composite.id);
+        let profile = Profile {
+            id: ProfileId::new(),
+            name: "Default".to_string(),
+            description: None,
+            enabled: true,
+            global_hotkey: None,
+            activation_rules: vec![],
+            root_menu: menu.id,
+            hold_to_open: false,
+            priority: 0,
+        };
+
+        let result = validate_profile(&profile, &[menu], [&composite, &shared]);
+        let errs = result.expect_err("missing child should be reported");
+        assert_eq!(
+            errs,
+            vec![DomainValidationError::MissingReferencedAction {
+                action: composite.id,
+                child: missing,
+            }]
+        );
+    }
 }
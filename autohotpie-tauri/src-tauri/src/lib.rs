@@ -8,6 +8,7 @@ mod storage;
 pub fn run() {
     let app = tauri::Builder::<tauri::Wry>::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_autostart::init(
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             None,
@@ -31,9 +32,18 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             commands::actions::list_actions,
             commands::actions::recent_action_events,
+            commands::actions::get_action_output,
+            commands::actions::query_action_history,
             commands::actions::save_actions,
             commands::actions::run_action,
             commands::actions::test_action,
+            commands::actions::explain_action,
+            commands::actions::cancel_action,
+            commands::actions::list_running_actions,
+            commands::actions::list_custom_handlers,
+            commands::actions::available_script_languages,
+            commands::actions::check_action_policy,
+            commands::actions::mark_actions_reviewed,
             commands::import_export::export_profiles,
             commands::import_export::import_profiles,
             commands::import_export::save_export_bundle,
@@ -44,6 +54,10 @@ pub fn run() {
             commands::profiles::activate_profile,
             commands::profiles::create_profile,
             commands::profiles::open_profiles_backups,
+            commands::profiles::profile_ranking_order,
+            commands::triggers::list_triggers,
+            commands::triggers::save_triggers,
+            commands::triggers::set_trigger_enabled,
             commands::settings::load_settings,
             commands::settings::save_settings,
             commands::settings::add_profile,
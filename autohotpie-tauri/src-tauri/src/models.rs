@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
 
+/// Global action timeout used when settings do not configure one.
+pub const DEFAULT_ACTION_TIMEOUT_MS: u32 = 300_000;
+/// Actions allowed to run at once when settings do not say otherwise.
+pub const DEFAULT_MAX_CONCURRENT_ACTIONS: u32 = 4;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Settings {
@@ -48,6 +53,61 @@ impl Settings {
         app_obj.insert("version".to_string(), Value::String(version.to_string()));
         true
     }
+
+    /// Global cap for a single action run, read from `actions.timeoutMs`.
+    /// Falls back to [`DEFAULT_ACTION_TIMEOUT_MS`]; `0` disables the cap.
+    pub fn action_timeout_ms(&self) -> Option<u32> {
+        let configured = self
+            .global
+            .get("actions")
+            .and_then(|actions| actions.get("timeoutMs"))
+            .and_then(Value::as_u64);
+        match configured {
+            Some(0) => None,
+            Some(value) => Some(value.min(u64::from(u32::MAX)) as u32),
+            None => Some(DEFAULT_ACTION_TIMEOUT_MS),
+        }
+    }
+
+    /// Global limit on concurrently running actions from
+    /// `actions.maxConcurrency`, at least 1.
+    pub fn max_concurrent_actions(&self) -> u32 {
+        self.global
+            .get("actions")
+            .and_then(|actions| actions.get("maxConcurrency"))
+            .and_then(Value::as_u64)
+            .map(|value| value.clamp(1, 64) as u32)
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_ACTIONS)
+    }
+
+    /// Raw `actions.interpreters` entries; parsed by the interpreter registry.
+    pub fn script_interpreter_entries(&self) -> &[Value] {
+        self.global
+            .get("actions")
+            .and_then(|actions| actions.get("interpreters"))
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Raw `actions.security` object; parsed by the execution policy.
+    pub fn execution_policy_entry(&self) -> Option<&Value> {
+        self.global
+            .get("actions")
+            .and_then(|actions| actions.get("security"))
+    }
+
+    /// Replaces the whole `actions` block with the one from `other`, so
+    /// imported settings cannot loosen the execution policy, repoint
+    /// script interpreters or change run limits.
+    pub fn keep_action_settings_of(&mut self, other: &Settings) {
+        let actions = other
+            .global
+            .get("actions")
+            .cloned()
+            .unwrap_or_else(|| default_global()["actions"].clone());
+        self.global_map_mut().insert("actions".to_string(), actions);
+    }
 }
 
 fn default_global() -> Value {
@@ -78,6 +138,16 @@ fn default_global() -> Value {
             "safetyStrokeColor": [123, 123, 123, 255],
             "labelStrokeThickness": 1
         },
+        "actions": {
+            "timeoutMs": DEFAULT_ACTION_TIMEOUT_MS,
+            "maxConcurrency": DEFAULT_MAX_CONCURRENT_ACTIONS,
+            "interpreters": [],
+            "security": {
+                "payloads": {},
+                "allowedExecutables": null,
+                "allowedInterpreters": null
+            }
+        },
         "functionConfig": {
             "common": [],
             "custom": []
@@ -401,4 +471,30 @@ mod tests {
         assert!(settings.set_app_version("1.2.3"));
         assert!(!settings.set_app_version("1.2.3"));
     }
+
+    #[test]
+    fn imported_settings_keep_the_local_action_block() {
+        use crate::services::interpreters::InterpreterRegistry;
+
+        let mut local = Settings::default();
+        local.global["actions"]["timeoutMs"] = json!(60_000);
+        let mut imported = Settings::default();
+        imported.global["actions"]["interpreters"] = json!([
+            { "language": "python", "executable": "/tmp/evil", "args": ["-c"] }
+        ]);
+        imported.global["actions"]["security"] = json!({ "allowedExecutables": null });
+        imported.global["actions"]["timeoutMs"] = json!(0);
+
+        imported.keep_action_settings_of(&local);
+
+        let python = |settings: &Settings| {
+            InterpreterRegistry::from_settings(settings)
+                .get("python")
+                .map(|config| config.executable.clone())
+        };
+        assert_eq!(python(&imported), python(&local));
+        assert_ne!(python(&imported).as_deref(), Some("/tmp/evil"));
+        assert_eq!(imported.global["actions"], local.global["actions"]);
+        assert_eq!(imported.action_timeout_ms(), Some(60_000));
+    }
 }
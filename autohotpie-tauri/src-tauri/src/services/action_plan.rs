@@ -0,0 +1,188 @@
+//! Structured description of what an action would do, produced by
+//! [`ActionRunner::explain`](crate::services::action_runner::ActionRunner::explain)
+//! without launching, typing or running anything.
+
+use crate::domain::{
+    Action, ActionId, ClipboardOperation, JoinMode, LaunchMode, StepCondition, WindowOperation,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionPlan {
+    pub action_id: ActionId,
+    pub action_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u32>,
+    #[serde(flatten)]
+    pub node: PlanNode,
+}
+
+impl ActionPlan {
+    pub fn new(action: &Action, node: PlanNode) -> Self {
+        Self {
+            action_id: action.id,
+            action_name: action.name.clone(),
+            timeout_ms: action.timeout_ms,
+            node,
+        }
+    }
+}
+
+/// One planned payload or macro step, with the placeholders it resolved.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanNode {
+    #[serde(flatten)]
+    pub step: PlanStep,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub templates: Vec<TemplateResolution>,
+}
+
+/// A templated field before and after placeholder substitution. Step
+/// outputs are only known at run time and stay as written.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateResolution {
+    pub template: String,
+    pub rendered: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum PlanStep {
+    /// `env` lists the variables the launch changes, `None` for removed
+    /// ones; a prepended `PATH` shows its full value.
+    #[serde(rename_all = "camelCase")]
+    LaunchProgram {
+        executable: String,
+        resolved_path: String,
+        arguments: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        working_dir: Option<String>,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        env: BTreeMap<String, Option<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        stdin: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        stdout: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        stderr: Option<String>,
+        mode: LaunchMode,
+        single_instance: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    SendKeys {
+        sequence: String,
+        key_events: usize,
+        repeat: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        delay_ms: Option<u32>,
+    },
+    /// `interpreter_path` is `None` when the interpreter is not on `PATH`.
+    #[serde(rename_all = "camelCase")]
+    RunScript {
+        language: String,
+        interpreter: String,
+        interpreter_path: Option<String>,
+        arguments: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    SystemCommand {
+        shell: String,
+        shell_path: Option<String>,
+        arguments: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Delay {
+        duration_ms: u32,
+    },
+    Composite {
+        steps: Vec<CompositeStepPlan>,
+    },
+    Custom {
+        handler: String,
+        registered: bool,
+        params: Value,
+    },
+    Macro {
+        steps: Vec<MacroStepPlan>,
+    },
+    /// `backend` is the window backend that would carry it out.
+    Window {
+        operation: WindowOperation,
+        backend: String,
+    },
+    Clipboard {
+        operation: ClipboardOperation,
+    },
+    /// Credential headers show `***` instead of their value.
+    #[serde(rename_all = "camelCase")]
+    HttpRequest {
+        method: String,
+        url: String,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        body: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_ms: Option<u32>,
+    },
+    /// `resolved` is the URL, or the absolute path for files and folders.
+    Open {
+        target: String,
+        resolved: String,
+        exists: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    TypeText {
+        text: String,
+        paste_keys: String,
+        restore_clipboard: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum CompositeStepPlan {
+    Child(CompositeChildPlan),
+    #[serde(rename_all = "camelCase")]
+    Parallel {
+        join: JoinMode,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        when: Option<StepCondition>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        condition_holds: Option<bool>,
+        continue_on_error: bool,
+        branches: Vec<CompositeChildPlan>,
+    },
+}
+
+/// Every child is expanded, whether or not its condition holds.
+/// `condition_holds` is `None` when there is no condition or it depends on
+/// results that only exist at run time.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompositeChildPlan {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when: Option<StepCondition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition_holds: Option<bool>,
+    pub continue_on_error: bool,
+    pub plan: ActionPlan,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroStepPlan {
+    pub step_id: ActionId,
+    pub order: u32,
+    #[serde(flatten)]
+    pub node: PlanNode,
+}
@@ -1,51 +1,454 @@
 use crate::commands::AppError;
-use crate::domain::{Action, ActionEventPayload, ActionEventStatus, ActionId, ActionPayload};
+use crate::domain::action::{MacroStepDefinition, ParallelGroup};
+use crate::domain::{
+    Action, ActionEventPayload, ActionEventStatus, ActionId, ActionOutput, ActionPayload,
+    ClipboardOperation, CompositeChild, CompositeStep, ConcurrencyPolicy, JoinMode,
+    LaunchEnvironment, LaunchMode, LaunchOptions, MacroStepKind, RetryOn, RetryPolicy,
+    StatusMatcher, StepCondition, WindowOperation,
+};
+use crate::models::{Settings, DEFAULT_MAX_CONCURRENT_ACTIONS};
 use crate::services::action_events::ActionEventsChannel;
+use crate::services::action_plan::{
+    ActionPlan, CompositeChildPlan, CompositeStepPlan, MacroStepPlan, PlanNode, PlanStep,
+    TemplateResolution,
+};
 use crate::services::audit_log::AuditLogger;
-use std::collections::HashSet;
+use crate::services::clipboard::{self, ClipboardBackend, ClipboardError};
+use crate::services::custom_handlers::CustomHandlerRegistry;
+use crate::services::execution_history::ExecutionHistory;
+use crate::services::execution_policy::{ExecutionPolicy, PolicyDecision, PolicyVerdict};
+use crate::services::http_request::{self, HttpRequestError, PreparedRequest};
+use crate::services::interpreters::{InterpreterRegistry, ScriptLanguageInfo};
+use crate::services::keyboard::{self, KeyboardBackend, KeyboardError};
+use crate::services::process_tree::{self, ProcessOutput};
+use crate::services::templating::{Escaping, StepRecord, TemplateContext};
+use crate::services::window_control::{self, WindowBackend, WindowError, WindowResult};
+use futures::stream::{FuturesUnordered, StreamExt};
+use parking_lot::{Mutex, RwLock};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
 use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::{field, instrument, Span};
 use time::OffsetDateTime;
 use tokio::process::Command;
-use tokio::sync::Semaphore;
+use tokio::sync::{
+    Mutex as AsyncMutex, Notify, OwnedMutexGuard, OwnedRwLockReadGuard, OwnedRwLockWriteGuard,
+    OwnedSemaphorePermit, RwLock as AsyncRwLock, Semaphore,
+};
+use uuid::Uuid;
 
 pub const ACTION_EXECUTED_EVENT: &str = "actions://executed";
 pub const ACTION_FAILED_EVENT: &str = "actions://failed";
 
+/// Largest stdout/stderr tail kept per stream for events and the audit log.
+pub const MAX_CAPTURED_OUTPUT_BYTES: usize = 8 * 1024;
+
+/// Keys that paste in `TypeText` payloads unless they set their own.
+const DEFAULT_PASTE_KEYS: &str = "^v";
+/// Time the target app gets to read a pasted snippet before the previous
+/// clipboard text is restored.
+const PASTE_SETTLE_DELAY: Duration = Duration::from_millis(200);
+
 type RunnerFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
+/// Result of executing an action or macro step, before it becomes an event.
+#[derive(Debug)]
+struct Outcome {
+    status: ActionEventStatus,
+    message: Option<String>,
+    output: Option<ActionOutput>,
+    /// Set instead of running anything when the run is a dry run.
+    plan: Option<PlanNode>,
+    /// Attempt that produced this outcome; see [`with_retries`].
+    attempt: u32,
+}
+
+impl Outcome {
+    fn new(status: ActionEventStatus, message: Option<String>) -> Self {
+        Self {
+            status,
+            message,
+            output: None,
+            plan: None,
+            attempt: 1,
+        }
+    }
+
+    fn planned(step: PlanStep, templates: Vec<TemplateResolution>) -> Self {
+        let mut outcome = Self::success();
+        outcome.plan = Some(PlanNode { step, templates });
+        outcome
+    }
+
+    fn success() -> Self {
+        Self::new(ActionEventStatus::Success, None)
+    }
+
+    fn with_output(mut self, output: Option<ActionOutput>) -> Self {
+        self.output = output;
+        self
+    }
+
+    fn with_attempt(mut self, attempt: u32) -> Self {
+        self.attempt = attempt;
+        self
+    }
+}
+
+impl From<(ActionEventStatus, Option<String>)> for Outcome {
+    fn from((status, message): (ActionEventStatus, Option<String>)) -> Self {
+        Self::new(status, message)
+    }
+}
+
 #[derive(Clone)]
 pub struct ActionRunner {
     audit: AuditLogger,
     data_dir: PathBuf,
     events: ActionEventsChannel,
-    queue: Arc<Semaphore>,
+    history: ExecutionHistory,
+    slots: Arc<Slots>,
+    serial: Arc<Mutex<HashMap<ActionId, Arc<AsyncMutex<()>>>>>,
+    keyboard: Arc<dyn KeyboardBackend>,
+    windows: Arc<dyn WindowBackend>,
+    clipboard: Arc<dyn ClipboardBackend>,
+    http: reqwest::Client,
+    custom_handlers: CustomHandlerRegistry,
+    interpreters: Arc<RwLock<InterpreterRegistry>>,
+    policy: Arc<RwLock<ExecutionPolicy>>,
+    running: Arc<Mutex<HashMap<Uuid, RunningEntry>>>,
+    default_timeout_ms: Arc<AtomicU32>,
+}
+
+/// Global run slots. `Exclusive` runs hold the write side of `access`, so
+/// they wait for every other run and nothing starts beside them.
+struct Slots {
+    semaphore: Arc<Semaphore>,
+    access: Arc<AsyncRwLock<()>>,
+    count: Mutex<SlotCount>,
+}
+
+/// `owed` counts slots removed by lowering the limit while they were in use;
+/// they are forgotten instead of returned when their runs finish.
+struct SlotCount {
+    size: u32,
+    owed: u32,
+}
+
+impl Slots {
+    fn new(size: u32) -> Self {
+        let size = size.max(1);
+        Self {
+            semaphore: Arc::new(Semaphore::new(size as usize)),
+            access: Arc::new(AsyncRwLock::new(())),
+            count: Mutex::new(SlotCount { size, owed: 0 }),
+        }
+    }
+
+    /// Changes the number of slots in place, so queued runs keep their
+    /// place in line.
+    fn resize(&self, limit: u32) {
+        let limit = limit.max(1);
+        let mut count = self.count.lock();
+        if limit > count.size {
+            let added = limit - count.size;
+            let repaid = added.min(count.owed);
+            count.owed -= repaid;
+            self.semaphore.add_permits((added - repaid) as usize);
+        } else if limit < count.size {
+            let removed = (count.size - limit) as usize;
+            let forgotten = self.semaphore.forget_permits(removed);
+            count.owed += (removed - forgotten) as u32;
+        }
+        count.size = limit;
+    }
+
+    /// Returns a run's slot, unless lowering the limit removed it.
+    fn release(&self, permit: OwnedSemaphorePermit) {
+        let mut count = self.count.lock();
+        if count.owed > 0 {
+            count.owed -= 1;
+            permit.forget();
+        }
+    }
+}
+
+/// Held for the duration of a run; releases the slot(s) and the per-action
+/// queue position on drop.
+struct SlotGuard {
+    slots: Arc<Slots>,
+    permit: Option<OwnedSemaphorePermit>,
+    _shared: Option<OwnedRwLockReadGuard<()>>,
+    _exclusive: Option<OwnedRwLockWriteGuard<()>>,
+    _serial: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        if let Some(permit) = self.permit.take() {
+            self.slots.release(permit);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunState {
+    Running,
+    Queued,
+}
+
+/// An invocation that has been accepted but has not finished yet.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunningAction {
+    pub invocation_id: Uuid,
+    pub action_id: ActionId,
+    pub action_name: String,
+    pub concurrency: ConcurrencyPolicy,
+    pub state: RunState,
+    pub queued_at: OffsetDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<OffsetDateTime>,
+}
+
+struct RunningEntry {
+    info: RunningAction,
+    signal: Arc<Notify>,
+}
+
+/// Keeps an invocation listed and cancellable until the run finishes or its
+/// future is dropped.
+struct Registration {
+    running: Arc<Mutex<HashMap<Uuid, RunningEntry>>>,
+    invocation_id: Uuid,
+    signal: Arc<Notify>,
+}
+
+impl Registration {
+    fn mark_running(&self) {
+        if let Some(entry) = self.running.lock().get_mut(&self.invocation_id) {
+            entry.info.state = RunState::Running;
+            entry.info.started_at = Some(OffsetDateTime::now_utc());
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.running.lock().remove(&self.invocation_id);
+    }
 }
 
 pub trait ActionProvider: Send + Sync {
     fn get_action(&self, id: &ActionId) -> Option<Action>;
 }
 
+/// State shared by every action and step reached from one top-level run.
+struct RunContext {
+    invocation_id: Uuid,
+    template: TemplateContext,
+    /// Describe each payload instead of executing it; see
+    /// [`ActionRunner::explain`].
+    dry_run: bool,
+}
+
+impl RunContext {
+    /// Renders `template`, noting the substitution in `trace` when a
+    /// placeholder changed it.
+    fn render(
+        &self,
+        action_name: &str,
+        template: &str,
+        escaping: Escaping,
+        trace: &mut Vec<TemplateResolution>,
+    ) -> Result<String, AppError> {
+        let rendered = self.template.render(template, escaping).map_err(|err| {
+            AppError::Message(format!("invalid template in '{action_name}': {err}"))
+        })?;
+        if rendered != template {
+            trace.push(TemplateResolution {
+                template: template.to_string(),
+                rendered: rendered.clone(),
+            });
+        }
+        Ok(rendered)
+    }
+}
+
 impl ActionRunner {
     pub fn new(data_dir: PathBuf, audit: AuditLogger, events: ActionEventsChannel) -> Self {
+        let clipboard = clipboard::system_clipboard();
         Self {
             audit,
+            history: ExecutionHistory::new(&data_dir),
             data_dir,
             events,
-            queue: Arc::new(Semaphore::new(1)),
+            slots: Arc::new(Slots::new(DEFAULT_MAX_CONCURRENT_ACTIONS)),
+            serial: Arc::new(Mutex::new(HashMap::new())),
+            keyboard: keyboard::default_backend(),
+            windows: window_control::default_backend(),
+            custom_handlers: CustomHandlerRegistry::with_builtins(clipboard.clone()),
+            clipboard,
+            http: reqwest::Client::new(),
+            interpreters: Arc::new(RwLock::new(InterpreterRegistry::default())),
+            policy: Arc::new(RwLock::new(ExecutionPolicy::default())),
+            running: Arc::new(Mutex::new(HashMap::new())),
+            default_timeout_ms: Arc::new(AtomicU32::new(0)),
         }
     }
 
+    /// Replaces the keyboard backend used by SendKeys payloads and macro
+    /// `keys` steps.
+    #[allow(dead_code)]
+    pub fn with_keyboard(mut self, backend: Arc<dyn KeyboardBackend>) -> Self {
+        self.keyboard = backend;
+        self
+    }
+
+    /// Client used by `HttpRequest` payloads; the app passes the one the
+    /// update checker builds.
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.http = client;
+        self
+    }
+
+    /// Replaces the window backend used by `Window` payloads.
+    #[allow(dead_code)]
+    pub fn with_windows(mut self, backend: Arc<dyn WindowBackend>) -> Self {
+        self.windows = backend;
+        self
+    }
+
+    /// Handlers available to `Custom` payloads. The registry is shared, so
+    /// handlers registered through this reference apply to every clone.
+    pub fn custom_handlers(&self) -> &CustomHandlerRegistry {
+        &self.custom_handlers
+    }
+
+    /// Persistent record of every event this runner publishes.
+    pub fn history(&self) -> &ExecutionHistory {
+        &self.history
+    }
+
     #[allow(dead_code)]
     pub fn data_dir(&self) -> &Path {
         &self.data_dir
     }
 
-    #[instrument(skip_all, fields(action_id = %action.id, action_name = %action.name))]
+    pub fn apply_settings(&self, settings: &Settings) {
+        self.set_default_timeout(settings.action_timeout_ms());
+        self.set_max_concurrency(settings.max_concurrent_actions());
+        *self.interpreters.write() = InterpreterRegistry::from_settings(settings);
+        *self.policy.write() = ExecutionPolicy::from_settings(settings);
+    }
+
+    /// What the execution policy says about running `action`.
+    pub fn check_policy<P>(&self, action: &Action, provider: &P) -> PolicyDecision
+    where
+        P: ActionProvider,
+    {
+        self.policy
+            .read()
+            .evaluate(action, provider, &self.interpreters.read())
+    }
+
+    /// Gate in front of [`run_with_invocation`](Self::run_with_invocation):
+    /// fails when the policy denies `action`, or asks for confirmation and
+    /// `confirmed` is not set. Every decision is written to the audit log.
+    pub fn authorize<P>(
+        &self,
+        action: &Action,
+        provider: &P,
+        confirmed: bool,
+    ) -> Result<PolicyDecision, AppError>
+    where
+        P: ActionProvider,
+    {
+        let decision = self.check_policy(action, provider);
+        let reasons = decision.reasons.join("; ");
+        match decision.verdict {
+            PolicyVerdict::Allow => {
+                self.log_info(&format!("Policy allowed action '{}'", action.name))?;
+            }
+            PolicyVerdict::Confirm if confirmed => {
+                self.log_warn(&format!(
+                    "Policy allowed action '{}' after confirmation: {reasons}",
+                    action.name
+                ))?;
+            }
+            PolicyVerdict::Confirm => {
+                let message = format!("Action '{}' requires confirmation: {reasons}", action.name);
+                self.log_warn(&message)?;
+                return Err(AppError::Message(message));
+            }
+            PolicyVerdict::Deny => {
+                let message = format!(
+                    "Action '{}' blocked by execution policy: {reasons}",
+                    action.name
+                );
+                self.log_error(&message);
+                return Err(AppError::Message(message));
+            }
+        }
+        Ok(decision)
+    }
+
+    /// Script languages the runner knows and whether each interpreter is
+    /// installed.
+    pub fn script_languages(&self) -> Vec<ScriptLanguageInfo> {
+        self.interpreters.read().probe()
+    }
+
+    /// Number of runs allowed at once. Runs already holding a slot keep it,
+    /// and queued runs keep their place; lowering the limit takes effect as
+    /// running invocations finish.
+    pub fn set_max_concurrency(&self, limit: u32) {
+        self.slots.resize(limit);
+    }
+
+    /// Invocations in flight, running ones first, each group oldest first.
+    pub fn list_running(&self) -> Vec<RunningAction> {
+        let mut list: Vec<RunningAction> = self
+            .running
+            .lock()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect();
+        list.sort_by_key(|entry| (entry.state, entry.queued_at));
+        list
+    }
+
+    /// Limit applied to every top-level run on top of the action's own
+    /// `timeout_ms`. `None` or `Some(0)` disables it.
+    pub fn set_default_timeout(&self, timeout_ms: Option<u32>) {
+        self.default_timeout_ms
+            .store(timeout_ms.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// Requests cancellation of a queued or running invocation. Child
+    /// processes it started are killed. Returns `false` when no invocation
+    /// with that id is in flight.
+    pub fn cancel(&self, invocation_id: Uuid) -> bool {
+        match self.running.lock().get(&invocation_id) {
+            Some(entry) => {
+                entry.signal.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[allow(dead_code)]
     pub async fn run<P>(
         &self,
         action: &Action,
@@ -54,28 +457,112 @@ impl ActionRunner {
     where
         P: ActionProvider,
     {
-        let permit = self
-            .queue
-            .clone()
-            .acquire_owned()
+        self.run_with_invocation(action, provider, Uuid::new_v4(), TemplateContext::default())
             .await
-            .map_err(|_| AppError::StatePoisoned)?;
+    }
+
+    /// Runs `action` under a caller-chosen invocation id so the caller can
+    /// [`cancel`](Self::cancel) it before the outcome is known. `context`
+    /// supplies the values for `{{...}}` placeholders in the action's
+    /// arguments; the system clipboard is used unless it sets its own.
+    #[instrument(skip_all, fields(action_id = %action.id, action_name = %action.name))]
+    pub async fn run_with_invocation<P>(
+        &self,
+        action: &Action,
+        provider: &P,
+        invocation_id: Uuid,
+        context: TemplateContext,
+    ) -> Result<ActionEventPayload, AppError>
+    where
+        P: ActionProvider,
+    {
+        let context = if context.has_clipboard() {
+            context
+        } else {
+            context.with_clipboard(self.clipboard.clone())
+        };
+        let run = RunContext {
+            invocation_id,
+            template: context,
+            dry_run: false,
+        };
+        let Some(registration) = self.register_invocation(action, invocation_id)? else {
+            return self.handle_outcome(
+                action.id,
+                &action.name,
+                Outcome::new(
+                    ActionEventStatus::Skipped,
+                    Some(format!("Action '{}' skipped: already running", action.name)),
+                ),
+                0,
+                OffsetDateTime::now_utc(),
+                &run,
+            );
+        };
+        let cancelled = || {
+            Ok(Outcome::new(
+                ActionEventStatus::Cancelled,
+                Some(format!("Action '{}' was cancelled", action.name)),
+            ))
+        };
+
+        let slot = tokio::select! {
+            biased;
+            _ = registration.signal.notified() => None,
+            slot = self.acquire_slot(action) => Some(slot?),
+        };
+        if slot.is_some() {
+            registration.mark_running();
+        }
 
         let timer = Instant::now();
         let span = Span::current();
-        let result = self
-            .execute_internal(action, provider, HashSet::new(), span.clone())
-            .await;
+        let result = match slot {
+            None => cancelled(),
+            Some(_) => {
+                let execution = async {
+                    check_for_cycles(action, provider)?;
+                    self.execute_internal(action, provider, HashSet::new(), &run, span.clone())
+                        .await
+                };
+                let execution = async {
+                    match self.default_timeout_ms.load(Ordering::Relaxed) {
+                        0 => execution.await,
+                        limit => {
+                            tokio::time::timeout(Duration::from_millis(u64::from(limit)), execution)
+                                .await
+                                .unwrap_or_else(|_| {
+                                    Ok(Outcome::new(
+                                        ActionEventStatus::TimedOut,
+                                        Some(format!(
+                                            "Action '{}' exceeded the global timeout of {} ms",
+                                            action.name, limit
+                                        )),
+                                    ))
+                                })
+                        }
+                    }
+                };
+                tokio::select! {
+                    biased;
+                    _ = registration.signal.notified() => cancelled(),
+                    result = execution => result,
+                }
+            }
+        };
         let duration_ms = timer.elapsed().as_millis().min(u32::MAX as u128) as u32;
         let timestamp = OffsetDateTime::now_utc();
-        span.record("duration_ms", &field::display(duration_ms));
+        span.record("duration_ms", field::display(duration_ms));
 
-        match result {
-            Ok((status, message)) => {
-                let outcome = self.handle_outcome(action, status, message, duration_ms, timestamp);
-                drop(permit);
-                outcome
-            }
+        let outcome = match result {
+            Ok(outcome) => self.handle_outcome(
+                action.id,
+                &action.name,
+                outcome,
+                duration_ms,
+                timestamp,
+                &run,
+            ),
             Err(err) => {
                 let message = err.to_string();
                 self.log_error(&message);
@@ -86,423 +573,3217 @@ impl ActionRunner {
                     duration_ms,
                     Some(message),
                     timestamp,
-                    None,
-                );
+                    Some(invocation_id),
+                )
+                .with_profile(run.template.profile_id);
                 self.publish_payload(&payload)?;
-                drop(permit);
                 Err(err)
             }
-        }
+        };
+        drop(slot);
+        drop(registration);
+        outcome
     }
 
-    fn execute_internal<'a, P>(
-        &'a self,
-        action: &'a Action,
-        provider: &'a P,
-        mut visited: HashSet<ActionId>,
-        parent_span: Span,
-    ) -> RunnerFuture<'a, Result<(ActionEventStatus, Option<String>), AppError>>
+    /// Describes what `action` would do without doing it: the same path as a
+    /// run is walked, but programs, scripts, keys and custom handlers are
+    /// replaced by a plan of them. Nothing is queued, logged or emitted.
+    /// `{{steps.*}}` placeholders stay as written since no step runs.
+    pub async fn explain<P>(
+        &self,
+        action: &Action,
+        provider: &P,
+        context: TemplateContext,
+    ) -> Result<ActionPlan, AppError>
     where
-        P: ActionProvider + 'a,
+        P: ActionProvider,
     {
-        Box::pin(async move {
-            let _guard = parent_span.enter();
-            if !visited.insert(action.id) {
-                return Err(AppError::Message(format!(
-                    "cycle detected while executing action {}",
-                    action.id
-                )));
-            }
-
-            let outcome = match &action.payload {
-                ActionPayload::LaunchProgram {
-                    executable,
-                    arguments,
-                    working_dir,
-                } => {
-                    self.launch_program(executable, arguments, working_dir.as_deref())?;
-                    (ActionEventStatus::Success, None)
-                }
-                ActionPayload::SendKeys { .. } => (
-                    ActionEventStatus::Skipped,
-                    Some(format!(
-                        "Action '{}' skipped: SendKeys payload is not supported yet",
-                        action.name
-                    )),
-                ),
-                ActionPayload::RunScript { language, script } => {
-                    self.run_script(language, script).await?;
-                    (ActionEventStatus::Success, None)
-                }
-                ActionPayload::SystemCommand { command } => {
-                    self.run_system_command(command).await?;
-                    (ActionEventStatus::Success, None)
-                }
-                ActionPayload::Composite { actions } => {
-                    let mut skipped_messages: Vec<String> = Vec::new();
-                    for action_id in actions {
-                        let Some(next_action) = provider.get_action(action_id) else {
-                            return Err(AppError::Message(format!(
-                                "referenced action {action_id} not found"
-                            )));
-                        };
-                        let child_span = tracing::span!(
-                            tracing::Level::DEBUG,
-                            "action_runner.child",
-                            child_action_id = %next_action.id,
-                            child_action_name = %next_action.name
-                        );
-                        let (status, msg) = self
-                            .execute_internal(&next_action, provider, visited.clone(), child_span)
-                            .await?;
-                        match status {
-                            ActionEventStatus::Success => {}
-                            ActionEventStatus::Skipped => {
-                                if let Some(value) = msg {
-                                    skipped_messages.push(value);
-                                }
-                            }
-                            ActionEventStatus::Failure => {
-                                return Err(AppError::Message(msg.unwrap_or_else(|| {
-                                    format!(
-                                        "Composite action '{}' failed while executing child {}",
-                                        action.name, action_id
-                                    )
-                                })));
-                            }
-                        }
-                    }
-
-                    if skipped_messages.is_empty() {
-                        (ActionEventStatus::Success, None)
-                    } else {
-                        let summary = skipped_messages.join("; ");
-                        (
-                            ActionEventStatus::Skipped,
-                            Some(format!(
-                                "Composite action '{}' completed with skipped steps: {}",
-                                action.name, summary
-                            )),
-                        )
-                    }
-                }
-                ActionPayload::Custom { handler, .. } => (
-                    ActionEventStatus::Skipped,
-                    Some(format!(
-                        "Action '{}' skipped: custom payload '{}' is not supported",
-                        action.name, handler
-                    )),
-                ),
-            };
-
-            Ok(outcome)
-        })
-    }
-
-    fn launch_program(
-        &self,
-        executable: &str,
-        arguments: &[String],
-        working_dir: Option<&str>,
-    ) -> Result<(), AppError> {
-        let mut command = if executable.contains(':') || Path::new(executable).is_absolute() {
-            Command::new(executable)
+        let context = if context.has_clipboard() {
+            context
         } else {
-            let resolved = self.resolve_relative(executable);
-            Command::new(resolved)
+            context.with_clipboard(self.clipboard.clone())
+        };
+        let run = RunContext {
+            invocation_id: Uuid::nil(),
+            template: context.deferring_steps(),
+            dry_run: true,
         };
+        check_for_cycles(action, provider)?;
+        let outcome = self
+            .execute_internal(action, provider, HashSet::new(), &run, Span::current())
+            .await?;
+        planned(action, outcome)
+    }
 
-        if let Some(dir) = working_dir {
-            command.current_dir(self.resolve_relative(dir));
+    /// Lists the invocation as queued. Returns `None` when the action's
+    /// policy is `DropIfRunning` and another run of it is in flight.
+    fn register_invocation(
+        &self,
+        action: &Action,
+        invocation_id: Uuid,
+    ) -> Result<Option<Registration>, AppError> {
+        let mut running = self.running.lock();
+        if running.contains_key(&invocation_id) {
+            return Err(AppError::Message(format!(
+                "invocation {invocation_id} is already running"
+            )));
+        }
+        if action.concurrency == ConcurrencyPolicy::DropIfRunning
+            && running
+                .values()
+                .any(|entry| entry.info.action_id == action.id)
+        {
+            return Ok(None);
         }
+        let signal = Arc::new(Notify::new());
+        running.insert(
+            invocation_id,
+            RunningEntry {
+                info: RunningAction {
+                    invocation_id,
+                    action_id: action.id,
+                    action_name: action.name.clone(),
+                    concurrency: action.concurrency,
+                    state: RunState::Queued,
+                    queued_at: OffsetDateTime::now_utc(),
+                    started_at: None,
+                },
+                signal: signal.clone(),
+            },
+        );
+        Ok(Some(Registration {
+            running: self.running.clone(),
+            invocation_id,
+            signal,
+        }))
+    }
 
-        if !arguments.is_empty() {
-            command.args(arguments);
+    /// Waits until the action's policy allows it to start. `Queue` runs
+    /// first line up behind earlier runs of the same action so they do not
+    /// hold a global slot while waiting.
+    async fn acquire_slot(&self, action: &Action) -> Result<SlotGuard, AppError> {
+        let serial = match action.concurrency {
+            ConcurrencyPolicy::Queue => {
+                let lock = self.serial.lock().entry(action.id).or_default().clone();
+                Some(lock.lock_owned().await)
+            }
+            _ => None,
+        };
+        if action.concurrency == ConcurrencyPolicy::Exclusive {
+            let exclusive = self.slots.access.clone().write_owned().await;
+            return Ok(SlotGuard {
+                slots: self.slots.clone(),
+                permit: None,
+                _shared: None,
+                _exclusive: Some(exclusive),
+                _serial: serial,
+            });
         }
+        let shared = self.slots.access.clone().read_owned().await;
+        let permit = self
+            .slots
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| AppError::StatePoisoned)?;
+        Ok(SlotGuard {
+            slots: self.slots.clone(),
+            permit: Some(permit),
+            _shared: Some(shared),
+            _exclusive: None,
+            _serial: serial,
+        })
+    }
 
-        command.spawn().map_err(|err| {
-            AppError::Message(format!("failed to launch program '{executable}': {err}"))
-        })?;
+    /// Runs macro steps in order, emitting one event per step. Step events
+    /// share the invocation id of the run that reached the macro.
+    async fn execute_macro(
+        &self,
+        action: &Action,
+        steps: &[MacroStepDefinition],
+        run: &RunContext,
+    ) -> Result<Outcome, AppError> {
+        let mut steps: Vec<&MacroStepDefinition> = steps.iter().collect();
+        steps.sort_by_key(|step| step.order);
 
-        Ok(())
-    }
+        if run.dry_run {
+            let mut plans = Vec::with_capacity(steps.len());
+            for (index, step) in steps.into_iter().enumerate() {
+                let step_name = format!("{} (step {})", action.name, index + 1);
+                let outcome = self.run_macro_step(&step_name, &step.kind, run).await?;
+                plans.push(MacroStepPlan {
+                    step_id: step.id,
+                    order: step.order,
+                    node: outcome.plan.ok_or_else(|| no_plan(&step_name))?,
+                });
+            }
+            return Ok(Outcome::planned(
+                PlanStep::Macro { steps: plans },
+                Vec::new(),
+            ));
+        }
 
-    async fn run_system_command(&self, command: &str) -> Result<(), AppError> {
-        #[cfg(target_os = "windows")]
-        let status = Command::new("cmd")
-            .args(["/C", command])
-            .status()
-            .await
-            .map_err(|err| {
-                AppError::Message(format!("failed to run system command '{command}': {err}"))
-            })?;
+        let mut skipped_messages: Vec<String> = Vec::new();
+        for (index, step) in steps.into_iter().enumerate() {
+            let step_name = format!("{} (step {})", action.name, index + 1);
+            let (result, elapsed) = with_retries(
+                step.retry.as_ref(),
+                || self.run_macro_step(&step_name, &step.kind, run),
+                |attempt| self.publish_payload(&attempt.event(step.id, &step_name, run)),
+            )
+            .await;
+            let duration_ms = elapsed.as_millis().min(u32::MAX as u128) as u32;
+            let (status, message, output, attempt) = match &result {
+                Ok(outcome) => (
+                    outcome.status,
+                    outcome.message.clone(),
+                    outcome.output.clone(),
+                    outcome.attempt,
+                ),
+                Err(err) => (ActionEventStatus::Failure, Some(err.to_string()), None, 1),
+            };
 
-        #[cfg(not(target_os = "windows"))]
-        let status = Command::new("sh")
-            .args(["-c", command])
-            .status()
-            .await
-            .map_err(|err| {
-                AppError::Message(format!("failed to run system command '{command}': {err}"))
-            })?;
+            let payload = ActionEventPayload::new(
+                step.id,
+                step_name,
+                status,
+                duration_ms,
+                message,
+                OffsetDateTime::now_utc(),
+                Some(run.invocation_id),
+            )
+            .with_output(output)
+            .with_attempt(attempt)
+            .with_profile(run.template.profile_id);
+            self.publish_payload(&payload)?;
 
-        if status.success() {
-            Ok(())
-        } else {
-            Err(AppError::Message(format!(
-                "system command '{command}' exited with status {status}"
-            )))
+            let outcome = result?;
+            match outcome.status {
+                ActionEventStatus::Success => {}
+                ActionEventStatus::Skipped => {
+                    if let Some(value) = outcome.message {
+                        skipped_messages.push(value);
+                    }
+                }
+                ActionEventStatus::Failure => {
+                    let message = outcome.message.unwrap_or_else(|| {
+                        format!("Macro '{}' failed at step {}", action.name, index + 1)
+                    });
+                    return Ok(Outcome::new(ActionEventStatus::Failure, Some(message))
+                        .with_output(outcome.output));
+                }
+                ActionEventStatus::TimedOut | ActionEventStatus::Cancelled => {
+                    return Ok(outcome);
+                }
+            }
         }
-    }
 
-    fn resolve_relative(&self, value: &str) -> PathBuf {
-        let path = PathBuf::from(value);
-        if path.is_absolute() || !(value.contains('/') || value.contains('\\')) {
-            path
+        if skipped_messages.is_empty() {
+            Ok(Outcome::success())
         } else {
-            self.data_dir.join(path)
+            Ok(Outcome::new(
+                ActionEventStatus::Skipped,
+                Some(format!(
+                    "Macro '{}' completed with skipped steps: {}",
+                    action.name,
+                    skipped_messages.join("; ")
+                )),
+            ))
         }
     }
 
-    async fn run_script(&self, language: &str, script: &str) -> Result<(), AppError> {
-        let lang = language.trim().to_ascii_lowercase();
-        let (mut command, label) = match lang.as_str() {
-            "powershell" | "pwsh" => {
-                let shell = if cfg!(target_os = "windows") {
-                    "powershell"
-                } else {
-                    "pwsh"
-                };
-                let mut cmd = Command::new(shell);
-                cmd.args(["-NoLogo", "-NoProfile", "-Command", script]);
-                (cmd, shell.to_string())
-            }
-            "cmd" => {
-                if cfg!(not(target_os = "windows")) {
-                    return Err(AppError::Message(
-                        "cmd scripts are only supported on Windows".to_string(),
+    async fn run_macro_step(
+        &self,
+        step_name: &str,
+        kind: &MacroStepKind,
+        run: &RunContext,
+    ) -> Result<Outcome, AppError> {
+        let mut trace = Vec::new();
+        match kind {
+            MacroStepKind::Launch {
+                app_path,
+                arguments,
+            } => {
+                // Split before substituting so values with spaces stay one
+                // argument.
+                let arguments = arguments
+                    .as_deref()
+                    .map(split_arguments)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|argument| run.render(step_name, argument, Escaping::Raw, &mut trace))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let app_path = run.render(step_name, app_path, Escaping::Raw, &mut trace)?;
+                if run.dry_run {
+                    return Ok(self.plan_launch(
+                        &app_path,
+                        arguments,
+                        None,
+                        &LaunchOptions::default(),
+                        trace,
                     ));
                 }
-                let mut cmd = Command::new("cmd");
-                cmd.args(["/C", script]);
-                (cmd, "cmd".to_string())
+                self.launch_program(&app_path, &arguments, None)?;
+                Ok(Outcome::success())
             }
-            "bash" | "sh" => {
-                let shell = if lang == "bash" { "bash" } else { "sh" };
-                let mut cmd = Command::new(shell);
-                cmd.args(["-c", script]);
-                (cmd, shell.to_string())
-            }
-            "python" => {
-                let mut cmd = Command::new("python");
-                cmd.args(["-c", script]);
-                (cmd, "python".to_string())
+            MacroStepKind::Keys { keys, repeat } => {
+                let keys = run.render(step_name, keys, Escaping::SendKeys, &mut trace)?;
+                if run.dry_run {
+                    return plan_keys(step_name, &keys, (*repeat).max(1), None, trace);
+                }
+                let mut outcome = Outcome::success();
+                for _ in 0..(*repeat).max(1) {
+                    outcome = self.send_keys(step_name, &keys, None).await?;
+                    if outcome.status != ActionEventStatus::Success {
+                        break;
+                    }
+                }
+                Ok(outcome)
             }
-            "node" | "javascript" | "js" => {
-                let mut cmd = Command::new("node");
-                cmd.args(["-e", script]);
-                (cmd, "node".to_string())
+            MacroStepKind::Delay { duration_ms } => {
+                if run.dry_run {
+                    return Ok(Outcome::planned(
+                        PlanStep::Delay {
+                            duration_ms: *duration_ms,
+                        },
+                        trace,
+                    ));
+                }
+                tokio::time::sleep(Duration::from_millis(u64::from(*duration_ms))).await;
+                Ok(Outcome::success())
+            }
+            MacroStepKind::Script { language, script } => {
+                let escaping = self.interpreters.read().escaping(language);
+                let script = run.render(step_name, script, escaping, &mut trace)?;
+                if run.dry_run {
+                    return self.plan_script(language, &script, trace);
+                }
+                self.run_script(language, &script).await
+            }
+        }
+    }
+
+    fn execute_internal<'a, P>(
+        &'a self,
+        action: &'a Action,
+        provider: &'a P,
+        mut visited: HashSet<ActionId>,
+        run: &'a RunContext,
+        parent_span: Span,
+    ) -> RunnerFuture<'a, Result<Outcome, AppError>>
+    where
+        P: ActionProvider + 'a,
+    {
+        Box::pin(async move {
+            let _guard = parent_span.enter();
+            if !visited.insert(action.id) {
+                return Err(cycle_error(action.id));
+            }
+
+            // Each attempt gets the full `timeout_ms`. Dry runs plan once.
+            let retry = action.retry.as_ref().filter(|_| !run.dry_run);
+            let visited = &visited;
+            let attempt = move || async move {
+                let execution = self.execute_payload(action, provider, visited.clone(), run);
+                match action.timeout_ms.filter(|limit| *limit > 0) {
+                    Some(limit) => {
+                        tokio::time::timeout(Duration::from_millis(u64::from(limit)), execution)
+                            .await
+                            .unwrap_or_else(|_| {
+                                Ok(Outcome::new(
+                                    ActionEventStatus::TimedOut,
+                                    Some(format!(
+                                        "Action '{}' timed out after {} ms",
+                                        action.name, limit
+                                    )),
+                                ))
+                            })
+                    }
+                    None => execution.await,
+                }
+            };
+            let report = |attempt: RetriedAttempt<'_>| {
+                self.publish_payload(&attempt.event(action.id, &action.name, run))
+            };
+            with_retries(retry, attempt, report).await.0
+        })
+    }
+
+    async fn execute_payload<P>(
+        &self,
+        action: &Action,
+        provider: &P,
+        visited: HashSet<ActionId>,
+        run: &RunContext,
+    ) -> Result<Outcome, AppError>
+    where
+        P: ActionProvider,
+    {
+        let name = action.name.as_str();
+        let mut trace = Vec::new();
+        let outcome = match &action.payload {
+            ActionPayload::LaunchProgram {
+                executable,
+                arguments,
+                working_dir,
+                options,
+            } => {
+                let mut render = |value: &str| run.render(name, value, Escaping::Raw, &mut trace);
+                let executable = render(executable)?;
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| render(argument))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let working_dir = working_dir.as_deref().map(&mut render).transpose()?;
+                let options = LaunchOptions {
+                    env: LaunchEnvironment {
+                        set: options
+                            .env
+                            .set
+                            .iter()
+                            .map(|(variable, value)| Ok((variable.clone(), render(value)?)))
+                            .collect::<Result<_, AppError>>()?,
+                        unset: options.env.unset.clone(),
+                        path_prepend: options
+                            .env
+                            .path_prepend
+                            .iter()
+                            .map(|dir| render(dir))
+                            .collect::<Result<_, _>>()?,
+                    },
+                    stdin: options.stdin.as_deref().map(&mut render).transpose()?,
+                    stdout: options.stdout.as_deref().map(&mut render).transpose()?,
+                    stderr: options.stderr.as_deref().map(&mut render).transpose()?,
+                    mode: options.mode,
+                    single_instance: options.single_instance,
+                };
+                if run.dry_run {
+                    return Ok(self.plan_launch(
+                        &executable,
+                        arguments,
+                        working_dir.as_deref(),
+                        &options,
+                        trace,
+                    ));
+                }
+                self.run_launch(
+                    name,
+                    &executable,
+                    &arguments,
+                    working_dir.as_deref(),
+                    &options,
+                )
+                .await?
+            }
+            ActionPayload::SendKeys { sequence, delay_ms } => {
+                let sequence = run.render(name, sequence, Escaping::SendKeys, &mut trace)?;
+                if run.dry_run {
+                    return plan_keys(name, &sequence, 1, *delay_ms, trace);
+                }
+                self.send_keys(name, &sequence, *delay_ms).await?
+            }
+            ActionPayload::RunScript { language, script } => {
+                let escaping = self.interpreters.read().escaping(language);
+                let script = run.render(name, script, escaping, &mut trace)?;
+                if run.dry_run {
+                    return self.plan_script(language, &script, trace);
+                }
+                self.run_script(language, &script).await?
+            }
+            ActionPayload::SystemCommand { command } => {
+                let command = run.render(name, command, Escaping::system_shell(), &mut trace)?;
+                if run.dry_run {
+                    let shell = shell_command(&command);
+                    let (shell, shell_path, arguments) = describe_command(&shell);
+                    return Ok(Outcome::planned(
+                        PlanStep::SystemCommand {
+                            shell,
+                            shell_path,
+                            arguments,
+                        },
+                        trace,
+                    ));
+                }
+                self.run_system_command(&command).await?
+            }
+            ActionPayload::Composite { actions } if run.dry_run => {
+                self.explain_composite(actions, provider, visited, run)
+                    .await?
+            }
+            ActionPayload::Composite { actions } => {
+                self.execute_composite(action, actions, provider, visited, run)
+                    .await?
+            }
+            ActionPayload::Custom { handler, params } if run.dry_run => Outcome::planned(
+                PlanStep::Custom {
+                    handler: handler.clone(),
+                    registered: self.custom_handlers.get(handler).is_some(),
+                    params: params.clone(),
+                },
+                trace,
+            ),
+            ActionPayload::Custom { handler, params } => {
+                match self.custom_handlers.invoke(handler, params).await {
+                    Some(result) => result?.into(),
+                    None => Outcome::new(
+                        ActionEventStatus::Skipped,
+                        Some(format!(
+                            "Action '{}' skipped: custom handler '{}' is not registered",
+                            action.name, handler
+                        )),
+                    ),
+                }
+            }
+            ActionPayload::Macro { steps } => self.execute_macro(action, steps, run).await?,
+            ActionPayload::Window { operation } => {
+                let operation = match operation {
+                    WindowOperation::FocusOrLaunch {
+                        process_name,
+                        executable,
+                        arguments,
+                    } => WindowOperation::FocusOrLaunch {
+                        process_name: run.render(name, process_name, Escaping::Raw, &mut trace)?,
+                        executable: executable
+                            .as_deref()
+                            .map(|executable| {
+                                run.render(name, executable, Escaping::Raw, &mut trace)
+                            })
+                            .transpose()?,
+                        arguments: arguments
+                            .iter()
+                            .map(|argument| run.render(name, argument, Escaping::Raw, &mut trace))
+                            .collect::<Result<Vec<_>, _>>()?,
+                    },
+                    other => other.clone(),
+                };
+                if run.dry_run {
+                    return Ok(Outcome::planned(
+                        PlanStep::Window {
+                            operation,
+                            backend: self.windows.name().to_string(),
+                        },
+                        trace,
+                    ));
+                }
+                self.control_window(name, &operation)?
+            }
+            ActionPayload::Clipboard { operation } => {
+                let mut render = |text: &str| run.render(name, text, Escaping::Raw, &mut trace);
+                let operation = match operation {
+                    ClipboardOperation::Set { text } => ClipboardOperation::Set {
+                        text: render(text)?,
+                    },
+                    ClipboardOperation::Append { text } => ClipboardOperation::Append {
+                        text: render(text)?,
+                    },
+                    ClipboardOperation::RegexReplace {
+                        pattern,
+                        replacement,
+                    } => ClipboardOperation::RegexReplace {
+                        pattern: pattern.clone(),
+                        replacement: render(replacement)?,
+                    },
+                    other => other.clone(),
+                };
+                if run.dry_run {
+                    return Ok(Outcome::planned(PlanStep::Clipboard { operation }, trace));
+                }
+                self.edit_clipboard(name, &operation, run)?
+            }
+            ActionPayload::TypeText {
+                text,
+                paste_keys,
+                restore_clipboard,
+            } => {
+                let text = run.render(name, text, Escaping::Raw, &mut trace)?;
+                let paste_keys = paste_keys.as_deref().unwrap_or(DEFAULT_PASTE_KEYS);
+                if run.dry_run {
+                    return Ok(Outcome::planned(
+                        PlanStep::TypeText {
+                            text,
+                            paste_keys: paste_keys.to_string(),
+                            restore_clipboard: *restore_clipboard,
+                        },
+                        trace,
+                    ));
+                }
+                self.type_text(name, &text, paste_keys, *restore_clipboard, run)
+                    .await?
+            }
+            ActionPayload::HttpRequest {
+                method,
+                url,
+                headers,
+                body,
+                timeout_ms,
+                success_status,
+            } => {
+                let url = run.render(name, url, Escaping::Url, &mut trace)?;
+                // Credentials stay out of the plan's template trace.
+                let mut hidden = Vec::new();
+                let headers = headers
+                    .iter()
+                    .map(|(header, value)| -> Result<_, AppError> {
+                        let trace = if http_request::is_secret_header(header) {
+                            &mut hidden
+                        } else {
+                            &mut trace
+                        };
+                        Ok((
+                            header.clone(),
+                            run.render(name, value, Escaping::Raw, trace)?,
+                        ))
+                    })
+                    .collect::<Result<BTreeMap<_, _>, AppError>>()?;
+                let escaping = http_request::body_escaping(&headers);
+                let body = body
+                    .as_deref()
+                    .map(|body| run.render(name, body, escaping, &mut trace))
+                    .transpose()?;
+                let request = PreparedRequest {
+                    method: method.trim().to_ascii_uppercase(),
+                    url,
+                    headers,
+                    body,
+                    timeout: timeout_ms.map(|ms| Duration::from_millis(u64::from(ms))),
+                };
+                if run.dry_run {
+                    return Ok(Outcome::planned(
+                        PlanStep::HttpRequest {
+                            headers: http_request::redacted_headers(&request.headers),
+                            method: request.method,
+                            url: request.url,
+                            body: request.body,
+                            timeout_ms: *timeout_ms,
+                        },
+                        trace,
+                    ));
+                }
+                self.send_http_request(name, &request, success_status)
+                    .await?
+            }
+            ActionPayload::Open { target } => {
+                let target = run.render(name, target, Escaping::Raw, &mut trace)?;
+                let resolved = self.resolve_open_target(&target);
+                if run.dry_run {
+                    return Ok(Outcome::planned(
+                        PlanStep::Open {
+                            exists: resolved.exists(),
+                            resolved: resolved.to_string(),
+                            target,
+                        },
+                        trace,
+                    ));
+                }
+                if let OpenTarget::Path(path) = &resolved {
+                    if let Some(reason) = self.policy.read().check_open_path(path) {
+                        let message =
+                            format!("Action '{name}' blocked by execution policy: {reason}");
+                        self.log_error(&message);
+                        return Err(AppError::Message(message));
+                    }
+                }
+                open_target(&target, &resolved)?
+            }
+        };
+
+        Ok(outcome)
+    }
+
+    /// Runs composite steps in order. Named steps publish their result to
+    /// later templates and conditions; a failed step stops the composite
+    /// unless it sets `continue_on_error`.
+    async fn execute_composite<P>(
+        &self,
+        action: &Action,
+        steps: &[CompositeStep],
+        provider: &P,
+        visited: HashSet<ActionId>,
+        run: &RunContext,
+    ) -> Result<Outcome, AppError>
+    where
+        P: ActionProvider,
+    {
+        let mut previous: Option<ActionEventStatus> = None;
+        let mut skipped_messages: Vec<String> = Vec::new();
+        let mut ignored_failures: Vec<String> = Vec::new();
+        for (index, step) in steps.iter().enumerate() {
+            if let Some(condition) = step.condition() {
+                if !condition_holds(condition, previous, &run.template)? {
+                    continue;
+                }
+            }
+
+            let outcome = match step {
+                CompositeStep::Child(child) => {
+                    self.run_composite_child(child, provider, &visited, run)
+                        .await?
+                }
+                CompositeStep::Parallel(group) => {
+                    let (outcome, ignored) = self
+                        .run_parallel_group(group, previous, provider, &visited, run)
+                        .await?;
+                    ignored_failures.extend(ignored);
+                    outcome
+                }
+            };
+            if let Some(name) = step.name() {
+                run.template.record_step(
+                    name,
+                    StepRecord {
+                        status: outcome.status,
+                        message: outcome.message.clone(),
+                        output: outcome.output.clone(),
+                    },
+                );
+            }
+            previous = Some(outcome.status);
+
+            match outcome.status {
+                ActionEventStatus::Success => {}
+                ActionEventStatus::Skipped => {
+                    if let Some(value) = outcome.message {
+                        skipped_messages.push(value);
+                    }
+                }
+                ActionEventStatus::Cancelled => return Ok(outcome),
+                ActionEventStatus::Failure | ActionEventStatus::TimedOut
+                    if step.continue_on_error() =>
+                {
+                    ignored_failures.push(
+                        outcome
+                            .message
+                            .unwrap_or_else(|| format!("step {} failed", index + 1)),
+                    );
+                }
+                ActionEventStatus::Failure => {
+                    let message = outcome.message.unwrap_or_else(|| match step {
+                        CompositeStep::Child(child) => format!(
+                            "Composite action '{}' failed while executing child {}",
+                            action.name, child.action
+                        ),
+                        CompositeStep::Parallel(_) => format!(
+                            "Composite action '{}' failed in parallel group at step {}",
+                            action.name,
+                            index + 1
+                        ),
+                    });
+                    return Ok(Outcome::new(ActionEventStatus::Failure, Some(message))
+                        .with_output(outcome.output));
+                }
+                ActionEventStatus::TimedOut => return Ok(outcome),
+            }
+        }
+
+        let mut notes = Vec::new();
+        if !skipped_messages.is_empty() {
+            notes.push(format!("skipped steps: {}", skipped_messages.join("; ")));
+        }
+        if !ignored_failures.is_empty() {
+            notes.push(format!("ignored failures: {}", ignored_failures.join("; ")));
+        }
+        if notes.is_empty() {
+            return Ok(Outcome::success());
+        }
+        let status = if skipped_messages.is_empty() {
+            ActionEventStatus::Success
+        } else {
+            ActionEventStatus::Skipped
+        };
+        Ok(Outcome::new(
+            status,
+            Some(format!(
+                "Composite action '{}' completed with {}",
+                action.name,
+                notes.join(", ")
+            )),
+        ))
+    }
+
+    /// Runs one composite child. Errors raised while executing it become a
+    /// `Failure` outcome so `continue_on_error` and `previous_failed` can see
+    /// them; a missing child or a cycle still aborts the whole run.
+    async fn run_composite_child<P>(
+        &self,
+        child: &CompositeChild,
+        provider: &P,
+        visited: &HashSet<ActionId>,
+        run: &RunContext,
+    ) -> Result<Outcome, AppError>
+    where
+        P: ActionProvider,
+    {
+        let Some(next_action) = provider.get_action(&child.action) else {
+            return Err(AppError::Message(format!(
+                "referenced action {} not found",
+                child.action
+            )));
+        };
+        if visited.contains(&next_action.id) {
+            return Err(cycle_error(next_action.id));
+        }
+        let child_span = tracing::span!(
+            tracing::Level::DEBUG,
+            "action_runner.child",
+            child_action_id = %next_action.id,
+            child_action_name = %next_action.name
+        );
+        let result = self
+            .execute_internal(&next_action, provider, visited.clone(), run, child_span)
+            .await;
+        Ok(result
+            .unwrap_or_else(|err| Outcome::new(ActionEventStatus::Failure, Some(err.to_string()))))
+    }
+
+    /// Starts every branch whose condition holds and joins them according
+    /// to the group's [`JoinMode`]. Branches still running when an `any`
+    /// group finishes are dropped, which kills their processes. Returns the
+    /// group outcome plus the failures tolerated by `continue_on_error`.
+    async fn run_parallel_group<P>(
+        &self,
+        group: &ParallelGroup,
+        previous: Option<ActionEventStatus>,
+        provider: &P,
+        visited: &HashSet<ActionId>,
+        run: &RunContext,
+    ) -> Result<(Outcome, Vec<String>), AppError>
+    where
+        P: ActionProvider,
+    {
+        let mut branches = FuturesUnordered::new();
+        for child in &group.parallel {
+            if let Some(condition) = &child.when {
+                if !condition_holds(condition, previous, &run.template)? {
+                    continue;
+                }
+            }
+            branches.push(async move {
+                let result = self
+                    .run_composite_child(child, provider, visited, run)
+                    .await;
+                (child, result)
+            });
+        }
+
+        let mut skipped_messages: Vec<String> = Vec::new();
+        let mut ignored_failures: Vec<String> = Vec::new();
+        let mut failures: Vec<Outcome> = Vec::new();
+        while let Some((child, result)) = branches.next().await {
+            let outcome = result?;
+            if let Some(name) = &child.name {
+                run.template.record_step(
+                    name,
+                    StepRecord {
+                        status: outcome.status,
+                        message: outcome.message.clone(),
+                        output: outcome.output.clone(),
+                    },
+                );
+            }
+            match outcome.status {
+                ActionEventStatus::Cancelled => return Ok((outcome, ignored_failures)),
+                ActionEventStatus::Success | ActionEventStatus::Skipped => {
+                    if group.join == JoinMode::Any {
+                        return Ok((outcome, ignored_failures));
+                    }
+                    if let Some(value) = outcome
+                        .message
+                        .filter(|_| outcome.status == ActionEventStatus::Skipped)
+                    {
+                        skipped_messages.push(value);
+                    }
+                }
+                ActionEventStatus::Failure | ActionEventStatus::TimedOut => {
+                    if child.continue_on_error {
+                        ignored_failures.push(
+                            outcome.message.unwrap_or_else(|| {
+                                format!("parallel branch {} failed", child.action)
+                            }),
+                        );
+                    } else if group.join == JoinMode::All {
+                        // Stop the remaining branches; the group has failed.
+                        return Ok((outcome, ignored_failures));
+                    } else {
+                        failures.push(outcome);
+                    }
+                }
+            }
+        }
+
+        if let Some(first) = failures.into_iter().next() {
+            return Ok((first, ignored_failures));
+        }
+        let outcome = if skipped_messages.is_empty() {
+            Outcome::success()
+        } else {
+            Outcome::new(
+                ActionEventStatus::Skipped,
+                Some(skipped_messages.join("; ")),
+            )
+        };
+        Ok((outcome, ignored_failures))
+    }
+
+    /// Dry-run counterpart of [`execute_composite`](Self::execute_composite):
+    /// expands every step, including parallel branches and steps whose
+    /// condition does not hold, so the whole tree is visible.
+    async fn explain_composite<P>(
+        &self,
+        steps: &[CompositeStep],
+        provider: &P,
+        visited: HashSet<ActionId>,
+        run: &RunContext,
+    ) -> Result<Outcome, AppError>
+    where
+        P: ActionProvider,
+    {
+        let mut plans = Vec::with_capacity(steps.len());
+        for step in steps {
+            let plan = match step {
+                CompositeStep::Child(child) => CompositeStepPlan::Child(
+                    self.explain_child(child, provider, &visited, run).await?,
+                ),
+                CompositeStep::Parallel(group) => {
+                    let mut branches = Vec::with_capacity(group.parallel.len());
+                    for child in &group.parallel {
+                        branches.push(self.explain_child(child, provider, &visited, run).await?);
+                    }
+                    CompositeStepPlan::Parallel {
+                        join: group.join,
+                        name: group.name.clone(),
+                        when: group.when.clone(),
+                        condition_holds: planned_condition(group.when.as_ref(), &run.template)?,
+                        continue_on_error: group.continue_on_error,
+                        branches,
+                    }
+                }
+            };
+            plans.push(plan);
+        }
+        Ok(Outcome::planned(
+            PlanStep::Composite { steps: plans },
+            Vec::new(),
+        ))
+    }
+
+    async fn explain_child<P>(
+        &self,
+        child: &CompositeChild,
+        provider: &P,
+        visited: &HashSet<ActionId>,
+        run: &RunContext,
+    ) -> Result<CompositeChildPlan, AppError>
+    where
+        P: ActionProvider,
+    {
+        let Some(next_action) = provider.get_action(&child.action) else {
+            return Err(AppError::Message(format!(
+                "referenced action {} not found",
+                child.action
+            )));
+        };
+        if visited.contains(&next_action.id) {
+            return Err(cycle_error(next_action.id));
+        }
+        let outcome = self
+            .execute_internal(
+                &next_action,
+                provider,
+                visited.clone(),
+                run,
+                Span::current(),
+            )
+            .await?;
+        Ok(CompositeChildPlan {
+            name: child.name.clone(),
+            when: child.when.clone(),
+            condition_holds: planned_condition(child.when.as_ref(), &run.template)?,
+            continue_on_error: child.continue_on_error,
+            plan: planned(&next_action, outcome)?,
+        })
+    }
+
+    fn plan_launch(
+        &self,
+        executable: &str,
+        arguments: Vec<String>,
+        working_dir: Option<&str>,
+        options: &LaunchOptions,
+        trace: Vec<TemplateResolution>,
+    ) -> Outcome {
+        let resolved_path = self
+            .launch_target(executable, &options.env)
+            .unwrap_or_else(|_| self.resolve_executable(executable))
+            .display()
+            .to_string();
+        let mut env: BTreeMap<String, Option<String>> = options
+            .env
+            .unset
+            .iter()
+            .map(|variable| (variable.clone(), None))
+            .collect();
+        env.extend(
+            options
+                .env
+                .set
+                .iter()
+                .map(|(variable, value)| (variable.clone(), Some(value.clone()))),
+        );
+        if !options.env.path_prepend.is_empty() {
+            let path = self.child_path(&options.env);
+            env.insert(
+                "PATH".into(),
+                path.map(|path| path.to_string_lossy().into_owned()),
+            );
+        }
+        let resolve = |file: &Option<String>| {
+            file.as_deref()
+                .map(|file| self.resolve_data_path(file).display().to_string())
+        };
+        Outcome::planned(
+            PlanStep::LaunchProgram {
+                executable: executable.to_string(),
+                resolved_path,
+                arguments,
+                working_dir: working_dir
+                    .map(|dir| self.resolve_relative(dir).display().to_string()),
+                env,
+                stdin: resolve(&options.stdin),
+                stdout: resolve(&options.stdout),
+                stderr: resolve(&options.stderr),
+                mode: options.mode,
+                single_instance: options.single_instance,
+            },
+            trace,
+        )
+    }
+
+    fn plan_script(
+        &self,
+        language: &str,
+        script: &str,
+        trace: Vec<TemplateResolution>,
+    ) -> Result<Outcome, AppError> {
+        let prepared = self.interpreters.read().prepare(language, script, false)?;
+        let (interpreter, interpreter_path, arguments) = describe_command(&prepared.command);
+        Ok(Outcome::planned(
+            PlanStep::RunScript {
+                language: language.to_string(),
+                interpreter,
+                interpreter_path,
+                arguments,
+            },
+            trace,
+        ))
+    }
+
+    /// Starts `executable` with default options and returns the binary
+    /// that was run.
+    fn launch_program(
+        &self,
+        executable: &str,
+        arguments: &[String],
+        working_dir: Option<&str>,
+    ) -> Result<PathBuf, AppError> {
+        let options = LaunchOptions::default();
+        let program = self.launch_target(executable, &options.env)?;
+        let mut command = self.launch_command(&program, arguments, working_dir, &options)?;
+        process_tree::isolate(&mut command)
+            .spawn()
+            .map_err(|err| launch_error(executable, err))?;
+        Ok(program)
+    }
+
+    async fn run_launch(
+        &self,
+        action_name: &str,
+        executable: &str,
+        arguments: &[String],
+        working_dir: Option<&str>,
+        options: &LaunchOptions,
+    ) -> Result<Outcome, AppError> {
+        let program = self.launch_target(executable, &options.env)?;
+        if options.single_instance {
+            let focus = WindowOperation::FocusOrLaunch {
+                process_name: program.display().to_string(),
+                executable: None,
+                arguments: Vec::new(),
+            };
+            match window_control::apply(self.windows.as_ref(), &focus) {
+                Ok(WindowResult::Applied) => {
+                    return Ok(Outcome::new(
+                        ActionEventStatus::Success,
+                        Some(format!(
+                            "Action '{action_name}' focused the running '{}'",
+                            program.display()
+                        )),
+                    ))
+                }
+                // Without a window backend every launch starts a new copy.
+                Ok(WindowResult::NotRunning) | Err(WindowError::Unavailable(_)) => {}
+                Err(err) => {
+                    return Err(AppError::Message(format!(
+                        "failed to look for a running '{}': {err}",
+                        program.display()
+                    )))
+                }
+            }
+        }
+
+        let mut command = self.launch_command(&program, arguments, working_dir, options)?;
+        match options.mode {
+            LaunchMode::Detached => {
+                process_tree::isolate(&mut command)
+                    .spawn()
+                    .map_err(|err| launch_error(executable, err))?;
+                Ok(Outcome::new(
+                    ActionEventStatus::Success,
+                    Some(format!(
+                        "Action '{action_name}' launched '{}'",
+                        program.display()
+                    )),
+                ))
+            }
+            LaunchMode::Wait => {
+                let output = process_tree::collect(&mut command, MAX_CAPTURED_OUTPUT_BYTES)
+                    .await
+                    .map_err(|err| launch_error(executable, err))?;
+                Ok(finish_process(output, format!("'{}'", program.display())))
+            }
+        }
+    }
+
+    /// The binary a launch of `executable` runs. Bare names are searched
+    /// on the `PATH` the program will get.
+    fn launch_target(
+        &self,
+        executable: &str,
+        env: &LaunchEnvironment,
+    ) -> Result<PathBuf, AppError> {
+        let resolved = self.resolve_executable(executable);
+        let search_path = self.child_path(env);
+        if let Some(program) =
+            process_tree::find_program_in(&resolved.to_string_lossy(), search_path.as_deref())
+        {
+            return Ok(program);
+        }
+        if executable.contains(['/', '\\', ':']) {
+            // Left to the OS to resolve or reject, e.g. a `shell:` target.
+            Ok(resolved)
+        } else {
+            Err(AppError::Message(format!(
+                "program '{executable}' was not found on PATH"
+            )))
+        }
+    }
+
+    fn launch_command(
+        &self,
+        program: &Path,
+        arguments: &[String],
+        working_dir: Option<&str>,
+        options: &LaunchOptions,
+    ) -> Result<Command, AppError> {
+        let mut command = Command::new(program);
+        command.args(arguments);
+        if let Some(dir) = working_dir {
+            command.current_dir(self.resolve_relative(dir));
+        }
+
+        for variable in &options.env.unset {
+            command.env_remove(variable);
+        }
+        command.envs(&options.env.set);
+        if !options.env.path_prepend.is_empty() {
+            match self.child_path(&options.env) {
+                Some(path) => command.env("PATH", path),
+                None => command.env_remove("PATH"),
+            };
+        }
+
+        let stdin = match &options.stdin {
+            Some(file) => File::open(self.resolve_data_path(file))
+                .map(Stdio::from)
+                .map_err(|err| {
+                    AppError::Message(format!("failed to open '{file}' for input: {err}"))
+                })?,
+            None => Stdio::null(),
+        };
+        let capture = options.mode == LaunchMode::Wait;
+        command
+            .stdin(stdin)
+            .stdout(self.output_target(options.stdout.as_deref(), capture)?)
+            .stderr(self.output_target(options.stderr.as_deref(), capture)?);
+        Ok(command)
+    }
+
+    /// Appends to `file` when set; otherwise the stream is captured for
+    /// waiting launches and shared with this process for detached ones.
+    fn output_target(&self, file: Option<&str>, capture: bool) -> Result<Stdio, AppError> {
+        match file {
+            Some(file) => OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.resolve_data_path(file))
+                .map(Stdio::from)
+                .map_err(|err| {
+                    AppError::Message(format!("failed to open '{file}' for output: {err}"))
+                }),
+            None if capture => Ok(Stdio::piped()),
+            None => Ok(Stdio::inherit()),
+        }
+    }
+
+    /// `PATH` as a launch with `env` passes it on, `None` if it has none.
+    fn child_path(&self, env: &LaunchEnvironment) -> Option<OsString> {
+        let inherited = match env.set.get("PATH") {
+            Some(path) => Some(OsString::from(path)),
+            None if env.unset.iter().any(|variable| variable == "PATH") => None,
+            None => std::env::var_os("PATH"),
+        };
+        if env.path_prepend.is_empty() {
+            return inherited;
+        }
+        let prepended = env
+            .path_prepend
+            .iter()
+            .map(|dir| self.resolve_data_path(dir));
+        std::env::join_paths(prepended.chain(inherited.iter().flat_map(std::env::split_paths))).ok()
+    }
+
+    fn control_window(
+        &self,
+        action_name: &str,
+        operation: &WindowOperation,
+    ) -> Result<Outcome, AppError> {
+        match window_control::apply(self.windows.as_ref(), operation) {
+            Ok(WindowResult::Applied) => Ok(Outcome::success()),
+            Ok(WindowResult::NotRunning) => {
+                let WindowOperation::FocusOrLaunch {
+                    process_name,
+                    executable,
+                    arguments,
+                } = operation
+                else {
+                    return Ok(Outcome::success());
+                };
+                let executable = executable.as_deref().unwrap_or(process_name);
+                let program = self.launch_program(executable, arguments, None)?;
+                Ok(Outcome::new(
+                    ActionEventStatus::Success,
+                    Some(format!(
+                        "Action '{action_name}' launched '{}': no window was open",
+                        program.display()
+                    )),
+                ))
+            }
+            Err(WindowError::Unavailable(reason)) => Ok(Outcome::new(
+                ActionEventStatus::Skipped,
+                Some(format!("Action '{}' skipped: {}", action_name, reason)),
+            )),
+            Err(err @ WindowError::NoForegroundWindow) => Ok(Outcome::new(
+                ActionEventStatus::Failure,
+                Some(format!("Action '{action_name}' failed: {err}")),
+            )),
+            Err(err) => Err(AppError::Message(format!(
+                "failed to control window for '{action_name}': {err}"
+            ))),
+        }
+    }
+
+    async fn send_http_request(
+        &self,
+        action_name: &str,
+        request: &PreparedRequest,
+        success_status: &[StatusMatcher],
+    ) -> Result<Outcome, AppError> {
+        let response =
+            match http_request::send(&self.http, request, MAX_CAPTURED_OUTPUT_BYTES).await {
+                Ok(response) => response,
+                Err(HttpRequestError::TimedOut) => {
+                    return Ok(Outcome::new(
+                        ActionEventStatus::TimedOut,
+                        Some(format!(
+                            "Action '{action_name}' timed out waiting for {}",
+                            request.url
+                        )),
+                    ))
+                }
+                Err(err) => {
+                    return Err(AppError::Message(format!(
+                        "HTTP request for '{action_name}' failed: {err}"
+                    )))
+                }
+            };
+
+        let status = response.status;
+        let succeeded = if success_status.is_empty() {
+            (200..300).contains(&status)
+        } else {
+            success_status
+                .iter()
+                .map(|matcher| matcher.matches(status))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| AppError::Message(format!("{err} in '{action_name}'")))?
+                .contains(&true)
+        };
+        let output = ActionOutput {
+            http_status: Some(status),
+            stdout: String::from_utf8_lossy(&response.body).into_owned(),
+            stdout_truncated: response.truncated,
+            ..ActionOutput::default()
+        };
+        if succeeded {
+            return Ok(Outcome::success().with_output(Some(output)));
+        }
+        Ok(Outcome::new(
+            ActionEventStatus::Failure,
+            Some(format!(
+                "{} {} returned HTTP {status}",
+                request.method, request.url
+            )),
+        )
+        .with_output(Some(output)))
+    }
+
+    /// Clipboard shared with the run's `{{clipboard}}` placeholder.
+    fn run_clipboard(&self, run: &RunContext) -> Arc<dyn ClipboardBackend> {
+        run.template
+            .clipboard()
+            .unwrap_or_else(|| self.clipboard.clone())
+    }
+
+    fn edit_clipboard(
+        &self,
+        action_name: &str,
+        operation: &ClipboardOperation,
+        run: &RunContext,
+    ) -> Result<Outcome, AppError> {
+        let text = match clipboard::apply(self.run_clipboard(run).as_ref(), operation) {
+            Ok(text) => text,
+            Err(err) => return clipboard_failure(action_name, err),
+        };
+        if *operation == ClipboardOperation::Read {
+            run.template.note_clipboard(text.clone());
+            return Ok(Outcome::success().with_output(Some(ActionOutput {
+                stdout: text.unwrap_or_default(),
+                ..ActionOutput::default()
+            })));
+        }
+        match text {
+            Some(text) => {
+                run.template.note_clipboard(Some(text));
+                Ok(Outcome::success())
+            }
+            None => Ok(Outcome::new(
+                ActionEventStatus::Skipped,
+                Some(format!(
+                    "Action '{action_name}' skipped: the clipboard holds no text"
+                )),
+            )),
+        }
+    }
+
+    /// Puts `text` on the clipboard and presses `paste_keys`. With
+    /// `restore`, the previous clipboard text is put back afterwards.
+    async fn type_text(
+        &self,
+        action_name: &str,
+        text: &str,
+        paste_keys: &str,
+        restore: bool,
+        run: &RunContext,
+    ) -> Result<Outcome, AppError> {
+        let events = keyboard::parse_sequence(paste_keys).map_err(|err| {
+            AppError::Message(format!("invalid paste keys for '{action_name}': {err}"))
+        })?;
+        let clipboard = self.run_clipboard(run);
+        let previous = if restore {
+            match clipboard.read_text() {
+                Ok(previous) => previous,
+                Err(err) => return clipboard_failure(action_name, err),
+            }
+        } else {
+            None
+        };
+        if let Err(err) = clipboard.write_text(text) {
+            return clipboard_failure(action_name, err);
+        }
+
+        let pasted = keyboard::send_events(self.keyboard.as_ref(), &events, None).await;
+        let mut clipboard_text = Some(text.to_string());
+        if let Some(previous) = previous {
+            tokio::time::sleep(PASTE_SETTLE_DELAY).await;
+            if let Err(err) = clipboard.write_text(&previous) {
+                return clipboard_failure(action_name, err);
+            }
+            clipboard_text = Some(previous);
+        }
+        run.template.note_clipboard(clipboard_text);
+
+        match pasted {
+            Ok(()) => Ok(Outcome::success()),
+            Err(KeyboardError::Unavailable(reason)) => Ok(Outcome::new(
+                ActionEventStatus::Skipped,
+                Some(format!("Action '{}' skipped: {}", action_name, reason)),
+            )),
+            Err(err) => Err(AppError::Message(format!(
+                "failed to paste text for '{action_name}': {err}"
+            ))),
+        }
+    }
+
+    async fn send_keys(
+        &self,
+        action_name: &str,
+        sequence: &str,
+        delay_ms: Option<u32>,
+    ) -> Result<Outcome, AppError> {
+        let events = keyboard::parse_sequence(sequence).map_err(|err| {
+            AppError::Message(format!("invalid key sequence for '{action_name}': {err}"))
+        })?;
+        let delay = delay_ms
+            .filter(|ms| *ms > 0)
+            .map(|ms| Duration::from_millis(u64::from(ms)));
+
+        match keyboard::send_events(self.keyboard.as_ref(), &events, delay).await {
+            Ok(()) => Ok(Outcome::success()),
+            Err(KeyboardError::Unavailable(reason)) => Ok(Outcome::new(
+                ActionEventStatus::Skipped,
+                Some(format!("Action '{}' skipped: {}", action_name, reason)),
+            )),
+            Err(err) => Err(AppError::Message(format!(
+                "failed to send keys for '{action_name}': {err}"
+            ))),
+        }
+    }
+
+    async fn run_system_command(&self, command: &str) -> Result<Outcome, AppError> {
+        let mut shell = shell_command(command);
+        let output = process_tree::output(&mut shell, MAX_CAPTURED_OUTPUT_BYTES)
+            .await
+            .map_err(|err| {
+                AppError::Message(format!("failed to run system command '{command}': {err}"))
+            })?;
+
+        Ok(finish_process(
+            output,
+            format!("system command '{command}'"),
+        ))
+    }
+
+    /// Drive-qualified and absolute paths are used as-is; anything else goes
+    /// through [`resolve_relative`](Self::resolve_relative).
+    fn resolve_executable(&self, executable: &str) -> PathBuf {
+        if executable.contains(':') || Path::new(executable).is_absolute() {
+            PathBuf::from(executable)
+        } else {
+            self.resolve_relative(executable)
+        }
+    }
+
+    fn resolve_relative(&self, value: &str) -> PathBuf {
+        let path = PathBuf::from(value);
+        if path.is_absolute() || !(value.contains('/') || value.contains('\\')) {
+            path
+        } else {
+            self.data_dir.join(path)
+        }
+    }
+
+    /// URLs are kept as they are; `file://` URLs and relative paths become
+    /// absolute paths, relative ones under the data directory.
+    fn resolve_open_target(&self, target: &str) -> OpenTarget {
+        let target = target.trim();
+        if let Ok(url) = reqwest::Url::parse(target) {
+            // A one-letter scheme is a Windows drive letter.
+            if url.scheme().len() > 1 {
+                return match url.to_file_path() {
+                    Ok(path) if url.scheme() == "file" => OpenTarget::Path(path),
+                    _ => OpenTarget::Url(target.to_string()),
+                };
+            }
+        }
+        OpenTarget::Path(self.resolve_data_path(target))
+    }
+
+    /// Unlike [`Self::resolve_relative`], bare names are under the data
+    /// directory too.
+    fn resolve_data_path(&self, value: &str) -> PathBuf {
+        let path = PathBuf::from(value);
+        if path.is_absolute() {
+            path
+        } else {
+            self.data_dir.join(path)
+        }
+    }
+
+    async fn run_script(&self, language: &str, script: &str) -> Result<Outcome, AppError> {
+        // `prepared` owns the temporary file in `file` mode; it is removed
+        // when this returns.
+        let mut prepared = self.interpreters.read().prepare(language, script, true)?;
+        let label = prepared.label.clone();
+        let output = process_tree::output(&mut prepared.command, MAX_CAPTURED_OUTPUT_BYTES)
+            .await
+            .map_err(|err| AppError::Message(format!("failed to run {label} script: {err}")))?;
+
+        Ok(finish_process(output, format!("{label} script")))
+    }
+
+    fn handle_outcome(
+        &self,
+        action_id: ActionId,
+        action_name: &str,
+        outcome: Outcome,
+        duration_ms: u32,
+        timestamp: OffsetDateTime,
+        run: &RunContext,
+    ) -> Result<ActionEventPayload, AppError> {
+        let Outcome {
+            status,
+            message,
+            output,
+            attempt,
+            ..
+        } = outcome;
+        let default_message = match status {
+            ActionEventStatus::Success => {
+                format!("Action '{}' executed", action_name)
+            }
+            ActionEventStatus::Skipped => {
+                format!("Action '{}' skipped (not supported)", action_name)
+            }
+            ActionEventStatus::Failure => format!("Action '{}' failed", action_name),
+            ActionEventStatus::TimedOut => format!("Action '{}' timed out", action_name),
+            ActionEventStatus::Cancelled => format!("Action '{}' was cancelled", action_name),
+        };
+
+        let final_message = message.unwrap_or(default_message);
+        match status {
+            ActionEventStatus::Success => self.log_info(&final_message)?,
+            ActionEventStatus::Skipped | ActionEventStatus::Cancelled => {
+                self.log_warn(&final_message)?
+            }
+            ActionEventStatus::Failure | ActionEventStatus::TimedOut => {
+                self.log_error(&final_message)
+            }
+        }
+
+        let payload = ActionEventPayload::new(
+            action_id,
+            action_name,
+            status,
+            duration_ms,
+            Some(final_message.clone()),
+            timestamp,
+            Some(run.invocation_id),
+        )
+        .with_output(output)
+        .with_attempt(attempt)
+        .with_profile(run.template.profile_id);
+
+        self.publish_payload(&payload)?;
+
+        if matches!(
+            status,
+            ActionEventStatus::Failure | ActionEventStatus::TimedOut
+        ) {
+            return Err(AppError::Message(final_message));
+        }
+
+        Ok(payload)
+    }
+
+    fn log_info(&self, message: &str) -> Result<(), AppError> {
+        self.audit.log("INFO", message).map_err(AppError::from)
+    }
+
+    fn log_warn(&self, message: &str) -> Result<(), AppError> {
+        self.audit.log("WARN", message).map_err(AppError::from)
+    }
+
+    fn log_error(&self, message: &str) {
+        self.audit.log_err("ERROR", message);
+    }
+
+    fn publish_payload(&self, payload: &ActionEventPayload) -> Result<(), AppError> {
+        self.audit.log_action_outcome(payload)?;
+        if let Err(err) = self.history.append(payload) {
+            self.log_error(&format!("failed to record action history: {err}"));
+        }
+        self.events.emit(payload.clone());
+        Ok(())
+    }
+}
+
+/// A failed attempt that is about to be retried.
+struct RetriedAttempt<'a> {
+    number: u32,
+    of: u32,
+    result: &'a Result<Outcome, AppError>,
+    elapsed: Duration,
+    next_delay: Duration,
+}
+
+impl RetriedAttempt<'_> {
+    fn event(&self, id: ActionId, name: &str, run: &RunContext) -> ActionEventPayload {
+        let (status, message, output) = match self.result {
+            Ok(outcome) => (
+                outcome.status,
+                outcome.message.clone(),
+                outcome.output.clone(),
+            ),
+            Err(err) => (ActionEventStatus::Failure, Some(err.to_string()), None),
+        };
+        let message = format!(
+            "{} (attempt {} of {}, retrying in {} ms)",
+            message.unwrap_or_else(|| format!("'{name}' failed")),
+            self.number,
+            self.of,
+            self.next_delay.as_millis()
+        );
+        ActionEventPayload::new(
+            id,
+            name,
+            status,
+            self.elapsed.as_millis().min(u32::MAX as u128) as u32,
+            Some(message),
+            OffsetDateTime::now_utc(),
+            Some(run.invocation_id),
+        )
+        .with_output(output)
+        .with_attempt(self.number)
+        .with_profile(run.template.profile_id)
+    }
+}
+
+/// Calls `attempt` until it succeeds, fails in a way `retry` does not
+/// cover, or runs out of attempts, sleeping the policy's backoff in
+/// between. `report` sees each attempt that is followed by another one.
+/// Returns the last result, tagged with its attempt number, and how long
+/// that attempt took. An error on the last of several attempts becomes a
+/// `Failure` outcome so the attempt number survives.
+async fn with_retries<F, Fut, R>(
+    retry: Option<&RetryPolicy>,
+    mut attempt: F,
+    mut report: R,
+) -> (Result<Outcome, AppError>, Duration)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Outcome, AppError>>,
+    R: FnMut(RetriedAttempt<'_>) -> Result<(), AppError>,
+{
+    let attempts = retry.map_or(1, RetryPolicy::attempts);
+    let mut number = 1;
+    loop {
+        let timer = Instant::now();
+        let result = attempt().await;
+        let elapsed = timer.elapsed();
+        let kind = match &result {
+            Err(_) => Some(RetryOn::Error),
+            Ok(outcome) if outcome.status == ActionEventStatus::Failure => Some(RetryOn::Failure),
+            Ok(outcome) if outcome.status == ActionEventStatus::TimedOut => Some(RetryOn::TimedOut),
+            Ok(_) => None,
+        };
+        let retry =
+            retry.filter(|retry| number < attempts && kind.is_some_and(|kind| retry.retries(kind)));
+        let Some(retry) = retry else {
+            let result = match result {
+                Ok(outcome) => Ok(outcome.with_attempt(number)),
+                Err(err) if number > 1 => Ok(Outcome::new(
+                    ActionEventStatus::Failure,
+                    Some(err.to_string()),
+                )
+                .with_attempt(number)),
+                Err(err) => Err(err),
+            };
+            return (result, elapsed);
+        };
+
+        let next_delay = retry.delay_before(number + 1);
+        if let Err(err) = report(RetriedAttempt {
+            number,
+            of: attempts,
+            result: &result,
+            elapsed,
+            next_delay,
+        }) {
+            return (Err(err), elapsed);
+        }
+        tokio::time::sleep(next_delay).await;
+        number += 1;
+    }
+}
+
+/// Runs `command` through `cmd /C` on Windows and `sh -c` elsewhere.
+fn shell_command(command: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    let shell = {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let shell = {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    };
+
+    shell
+}
+
+/// Program name, its location on `PATH` and the arguments of `command`.
+fn describe_command(command: &Command) -> (String, Option<String>, Vec<String>) {
+    let command = command.as_std();
+    let program = command.get_program().to_string_lossy().into_owned();
+    let path = process_tree::find_program(&program).map(|path| path.display().to_string());
+    let arguments = command
+        .get_args()
+        .map(|argument| argument.to_string_lossy().into_owned())
+        .collect();
+    (program, path, arguments)
+}
+
+fn plan_keys(
+    action_name: &str,
+    sequence: &str,
+    repeat: u32,
+    delay_ms: Option<u32>,
+    trace: Vec<TemplateResolution>,
+) -> Result<Outcome, AppError> {
+    let events = keyboard::parse_sequence(sequence).map_err(|err| {
+        AppError::Message(format!("invalid key sequence for '{action_name}': {err}"))
+    })?;
+    Ok(Outcome::planned(
+        PlanStep::SendKeys {
+            sequence: sequence.to_string(),
+            key_events: events.len(),
+            repeat,
+            delay_ms,
+        },
+        trace,
+    ))
+}
+
+/// Wraps the plan a dry run produced for `action`.
+fn planned(action: &Action, outcome: Outcome) -> Result<ActionPlan, AppError> {
+    let node = outcome.plan.ok_or_else(|| no_plan(&action.name))?;
+    Ok(ActionPlan::new(action, node))
+}
+
+fn no_plan(name: &str) -> AppError {
+    AppError::Message(format!("'{name}' did not produce a plan"))
+}
+
+/// Conditions on the foreground window can be checked ahead of time; those
+/// on step results cannot and give `None`.
+fn planned_condition(
+    condition: Option<&StepCondition>,
+    context: &TemplateContext,
+) -> Result<Option<bool>, AppError> {
+    match condition {
+        Some(
+            condition @ (StepCondition::ProcessMatches { .. }
+            | StepCondition::WindowTitleMatches { .. }),
+        ) => condition_holds(condition, None, context).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn cycle_error(id: ActionId) -> AppError {
+    AppError::Message(format!("cycle detected while executing action {id}"))
+}
+
+/// Walks the composite graph reachable from `action` before anything runs,
+/// so a cycle is reported even where `continue_on_error` would otherwise
+/// turn the runtime check into an ignored failure. Missing children are
+/// left for the runner to report.
+fn check_for_cycles<P>(action: &Action, provider: &P) -> Result<(), AppError>
+where
+    P: ActionProvider,
+{
+    fn visit<P: ActionProvider>(
+        action: &Action,
+        provider: &P,
+        path: &mut Vec<ActionId>,
+        cleared: &mut HashSet<ActionId>,
+    ) -> Result<(), AppError> {
+        if path.contains(&action.id) {
+            return Err(cycle_error(action.id));
+        }
+        if cleared.contains(&action.id) {
+            return Ok(());
+        }
+        path.push(action.id);
+        for child in action.referenced_actions() {
+            if let Some(next) = provider.get_action(&child) {
+                visit(&next, provider, path, cleared)?;
+            }
+        }
+        path.pop();
+        cleared.insert(action.id);
+        Ok(())
+    }
+
+    visit(action, provider, &mut Vec::new(), &mut HashSet::new())
+}
+
+/// A failed step is one that ended with `failure` or `timed_out`.
+fn is_failure(status: ActionEventStatus) -> bool {
+    matches!(
+        status,
+        ActionEventStatus::Failure | ActionEventStatus::TimedOut
+    )
+}
+
+fn condition_holds(
+    condition: &StepCondition,
+    previous: Option<ActionEventStatus>,
+    context: &TemplateContext,
+) -> Result<bool, AppError> {
+    let step_status = |step: &str| context.step(step).map(|record| record.status);
+    Ok(match condition {
+        StepCondition::ProcessMatches { value } => {
+            text_matches(value, context.window.process_name.as_deref())?
+        }
+        StepCondition::WindowTitleMatches { value } => {
+            text_matches(value, context.window.window_title.as_deref())?
+        }
+        StepCondition::PreviousSucceeded => previous.is_some_and(|status| !is_failure(status)),
+        StepCondition::PreviousFailed => previous.is_some_and(is_failure),
+        StepCondition::StepSucceeded { step } => {
+            step_status(step).is_some_and(|status| !is_failure(status))
+        }
+        StepCondition::StepFailed { step } => step_status(step).is_some_and(is_failure),
+    })
+}
+
+/// Same matching as activation rules: case-insensitive equality, or a
+/// regular expression after a `regex:` prefix.
+fn text_matches(pattern: &str, candidate: Option<&str>) -> Result<bool, AppError> {
+    let Some(candidate) = candidate else {
+        return Ok(false);
+    };
+    let pattern = pattern.trim();
+    match pattern.strip_prefix("regex:") {
+        Some(expression) => Regex::new(expression.trim())
+            .map(|regex| regex.is_match(candidate))
+            .map_err(|err| {
+                AppError::Message(format!("invalid condition pattern '{pattern}': {err}"))
+            }),
+        None => Ok(candidate.eq_ignore_ascii_case(pattern)),
+    }
+}
+
+/// What an `open` payload points at once resolved.
+enum OpenTarget {
+    Url(String),
+    Path(PathBuf),
+}
+
+impl OpenTarget {
+    fn exists(&self) -> bool {
+        match self {
+            OpenTarget::Url(_) => true,
+            OpenTarget::Path(path) => path.exists(),
+        }
+    }
+}
+
+impl std::fmt::Display for OpenTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpenTarget::Url(url) => f.write_str(url),
+            OpenTarget::Path(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+fn open_target(target: &str, resolved: &OpenTarget) -> Result<Outcome, AppError> {
+    let result = match resolved {
+        OpenTarget::Url(url) => tauri_plugin_opener::open_url(url, None::<&str>),
+        OpenTarget::Path(path) if !path.exists() => {
+            return Ok(Outcome::new(
+                ActionEventStatus::Failure,
+                Some(format!("'{}' does not exist", path.display())),
+            ))
+        }
+        OpenTarget::Path(path) => tauri_plugin_opener::open_path(path, None::<&str>),
+    };
+    result.map_err(|err| AppError::Message(format!("failed to open '{target}': {err}")))?;
+    Ok(Outcome::success())
+}
+
+fn launch_error(executable: &str, err: std::io::Error) -> AppError {
+    AppError::Message(format!("failed to launch program '{executable}': {err}"))
+}
+
+fn clipboard_failure(action_name: &str, err: ClipboardError) -> Result<Outcome, AppError> {
+    match err {
+        ClipboardError::Unavailable(reason) => Ok(Outcome::new(
+            ActionEventStatus::Skipped,
+            Some(format!("Action '{}' skipped: {}", action_name, reason)),
+        )),
+        err => Err(AppError::Message(format!(
+            "clipboard action '{action_name}' failed: {err}"
+        ))),
+    }
+}
+
+/// Turns a finished process into an outcome: non-zero exit codes fail the
+/// action, and the captured output travels with the event either way.
+fn finish_process(output: ProcessOutput, label: String) -> Outcome {
+    let status = output.status;
+    let captured = ActionOutput {
+        exit_code: status.code(),
+        http_status: None,
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        stdout_truncated: output.stdout_truncated,
+        stderr_truncated: output.stderr_truncated,
+    };
+    if status.success() {
+        return Outcome::success().with_output(Some(captured));
+    }
+
+    let mut message = format!("{label} exited with status {status}");
+    if let Some(line) = captured
+        .stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+    {
+        message.push_str(": ");
+        message.push_str(line.trim());
+    }
+    Outcome::new(ActionEventStatus::Failure, Some(message)).with_output(Some(captured))
+}
+
+/// Splits a macro step argument string into argv entries, honouring double
+/// and single quotes so paths with spaces survive.
+fn split_arguments(raw: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut has_token = false;
+
+    for ch in raw.chars() {
+        match quote {
+            Some(open) if ch == open => quote = None,
+            Some(_) => current.push(ch),
+            None if ch == '"' || ch == '\'' => {
+                quote = Some(ch);
+                has_token = true;
+            }
+            None if ch.is_whitespace() => {
+                if has_token {
+                    arguments.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            None => {
+                current.push(ch);
+                has_token = true;
             }
-            other => {
-                return Err(AppError::Message(format!(
-                    "unsupported script language '{}'",
-                    other
-                )))
+        }
+    }
+
+    if has_token {
+        arguments.push(current);
+    }
+
+    arguments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::action::Backoff;
+    use crate::services::clipboard::MemoryClipboard;
+    use crate::services::keyboard::{Key, KeyEvent, NamedKey, RecordingBackend};
+    use crate::services::system_status::WindowSnapshot;
+    use crate::services::window_control::{RecordingWindows, Rect, WindowCall};
+    use crate::storage::StorageManager;
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use tauri::async_runtime::block_on;
+    use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+    #[derive(Default)]
+    struct MapProvider {
+        actions: HashMap<ActionId, Action>,
+    }
+
+    impl MapProvider {
+        fn insert(&mut self, action: Action) {
+            self.actions.insert(action.id, action);
+        }
+    }
+
+    impl ActionProvider for MapProvider {
+        fn get_action(&self, id: &ActionId) -> Option<Action> {
+            self.actions.get(id).cloned()
+        }
+    }
+
+    fn build_runner(app: &tauri::App<MockRuntime>) -> (ActionRunner, ActionEventsChannel) {
+        let handle = app.handle();
+        let storage = StorageManager::new(handle.clone()).expect("storage");
+        let audit = AuditLogger::from_storage(&storage).expect("audit");
+        let events = ActionEventsChannel::new(16);
+        let data_dir = storage.base_dir().to_path_buf();
+        let runner = ActionRunner::new(data_dir, audit, events.clone());
+        (runner, events)
+    }
+
+    fn create_app() -> tauri::App<MockRuntime> {
+        mock_builder()
+            .build(mock_context(noop_assets()))
+            .expect("build app")
+    }
+
+    fn composite_action(children: Vec<ActionId>, name: &str) -> Action {
+        Action {
+            id: ActionId::new(),
+            name: name.to_string(),
+            description: None,
+            payload: ActionPayload::Composite {
+                actions: children.into_iter().map(CompositeStep::from).collect(),
+            },
+            enabled: true,
+            timeout_ms: None,
+            concurrency: ConcurrencyPolicy::default(),
+            retry: None,
+            untrusted: false,
+            last_validated_at: None,
+        }
+    }
+
+    fn skip_action(name: &str) -> Action {
+        Action {
+            id: ActionId::new(),
+            name: name.to_string(),
+            description: None,
+            payload: ActionPayload::Custom {
+                handler: "noop".to_string(),
+                params: Value::Null,
+            },
+            enabled: true,
+            timeout_ms: None,
+            concurrency: ConcurrencyPolicy::default(),
+            retry: None,
+            untrusted: false,
+            last_validated_at: None,
+        }
+    }
+
+    fn delay_macro(name: &str, delays: &[u32], timeout_ms: u32) -> Action {
+        let steps = delays
+            .iter()
+            .enumerate()
+            .map(|(index, duration_ms)| MacroStepDefinition {
+                id: ActionId::new(),
+                order: index as u32,
+                kind: MacroStepKind::Delay {
+                    duration_ms: *duration_ms,
+                },
+                retry: None,
+                note: None,
+            })
+            .collect();
+        let mut action = Action::new(name, ActionPayload::Macro { steps });
+        action.timeout_ms = Some(timeout_ms);
+        action
+    }
+
+    fn macro_steps(action: &Action) -> &[MacroStepDefinition] {
+        match &action.payload {
+            ActionPayload::Macro { steps } => steps,
+            _ => &[],
+        }
+    }
+
+    #[test]
+    fn emits_skipped_payload_for_composite_sequence() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        let mut provider = MapProvider::default();
+
+        let child_success = composite_action(Vec::new(), "child-success");
+        let child_skip = skip_action("child-skip");
+        let parent = composite_action(vec![child_success.id, child_skip.id], "parent");
+
+        provider.insert(child_success.clone());
+        provider.insert(child_skip.clone());
+        provider.insert(parent.clone());
+
+        let payload = block_on(runner.run(&parent, &provider)).expect("run should succeed");
+        assert_eq!(payload.id, parent.id);
+        assert_eq!(payload.status, ActionEventStatus::Skipped);
+        assert!(payload
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .contains("child-skip"));
+
+        let history = events.recent();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].status, ActionEventStatus::Skipped);
+        assert_eq!(history[0].id, parent.id);
+    }
+
+    #[test]
+    fn emits_failure_payload_when_child_missing() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        let mut provider = MapProvider::default();
+
+        let missing_child = ActionId::new();
+        let parent = composite_action(vec![missing_child], "parent");
+        provider.insert(parent.clone());
+
+        let result = block_on(runner.run(&parent, &provider));
+        assert!(result.is_err());
+
+        let history = events.recent();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].status, ActionEventStatus::Failure);
+        assert_eq!(history[0].id, parent.id);
+        assert!(history[0]
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .contains("not found"));
+    }
+
+    #[test]
+    fn macro_emits_step_and_macro_events() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        let definition = delay_macro("macro", &[1, 1], 3000);
+        let provider = MapProvider::default();
+
+        let payload = block_on(runner.run(&definition, &provider)).expect("macro should succeed");
+        assert_eq!(payload.id, definition.id);
+        assert_eq!(payload.status, ActionEventStatus::Success);
+
+        let steps = macro_steps(&definition);
+        let history = events.recent();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].id, steps[0].id);
+        assert_eq!(history[1].id, steps[1].id);
+        assert_eq!(history[2].id, definition.id);
+        let invocation = payload.invocation_id.expect("invocation id");
+        assert!(history
+            .iter()
+            .all(|event| event.invocation_id == Some(invocation)));
+    }
+
+    #[test]
+    fn macro_fails_when_timeout_exceeded() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        let definition = delay_macro("slow", &[1, 500], 50);
+        let provider = MapProvider::default();
+
+        let result = block_on(runner.run(&definition, &provider));
+        assert!(result.is_err());
+
+        let history = events.recent();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].status, ActionEventStatus::Success);
+        assert_eq!(history[1].id, definition.id);
+        assert_eq!(history[1].status, ActionEventStatus::TimedOut);
+        assert!(history[1]
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .contains("timed out"));
+    }
+
+    #[test]
+    fn zero_action_timeout_means_no_limit() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        let definition = delay_macro("unbounded", &[1, 20], 0);
+        let provider = MapProvider::default();
+
+        let payload = block_on(runner.run(&definition, &provider)).expect("macro should succeed");
+        assert_eq!(payload.status, ActionEventStatus::Success);
+        assert_eq!(
+            events.recent().pop().expect("event").status,
+            ActionEventStatus::Success
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn global_timeout_kills_hung_script_and_frees_queue() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        runner.set_default_timeout(Some(200));
+        let provider = MapProvider::default();
+        let hung = Action::new(
+            "hung",
+            ActionPayload::RunScript {
+                language: "sh".into(),
+                script: "sleep 30".into(),
+            },
+        );
+
+        let started = Instant::now();
+        assert!(block_on(runner.run(&hung, &provider)).is_err());
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let last = events.recent().pop().expect("event");
+        assert_eq!(last.status, ActionEventStatus::TimedOut);
+        assert!(last
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .contains("global timeout"));
+
+        let next = composite_action(Vec::new(), "next");
+        let payload = block_on(runner.run(&next, &provider)).expect("queue released");
+        assert_eq!(payload.status, ActionEventStatus::Success);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancel_stops_running_and_queued_invocations() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        runner.set_max_concurrency(1);
+        let provider = MapProvider::default();
+        let hung = Action::new(
+            "hung",
+            ActionPayload::SystemCommand {
+                command: "sleep 30".into(),
+            },
+        );
+        let queued = composite_action(Vec::new(), "queued");
+        let running_id = Uuid::new_v4();
+        let queued_id = Uuid::new_v4();
+        assert!(!runner.cancel(running_id));
+
+        let started = Instant::now();
+        let (running, waiting, _) = block_on(async {
+            tokio::join!(
+                runner.run_with_invocation(
+                    &hung,
+                    &provider,
+                    running_id,
+                    TemplateContext::default()
+                ),
+                runner.run_with_invocation(
+                    &queued,
+                    &provider,
+                    queued_id,
+                    TemplateContext::default()
+                ),
+                async {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    assert!(runner.cancel(queued_id));
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    assert!(runner.cancel(running_id));
+                }
+            )
+        });
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        let running = running.expect("cancelled runs are not errors");
+        assert_eq!(running.status, ActionEventStatus::Cancelled);
+        assert_eq!(running.invocation_id, Some(running_id));
+        let waiting = waiting.expect("cancelled runs are not errors");
+        assert_eq!(waiting.status, ActionEventStatus::Cancelled);
+        assert_eq!(events.recent().len(), 2);
+        assert!(!runner.cancel(running_id));
+    }
+
+    #[test]
+    fn composite_runs_macro_children_under_one_invocation() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        let mut provider = MapProvider::default();
+
+        let child = delay_macro("child", &[1], 3000);
+        let parent = composite_action(vec![child.id], "parent");
+        provider.insert(child.clone());
+
+        let payload = block_on(runner.run(&parent, &provider)).expect("composite should succeed");
+        let invocation = payload.invocation_id.expect("invocation id");
+
+        let history = events.recent();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].id, macro_steps(&child)[0].id);
+        assert_eq!(history[1].id, parent.id);
+        assert!(history
+            .iter()
+            .all(|event| event.invocation_id == Some(invocation)));
+    }
+
+    fn script_action(name: &str, script: &str) -> Action {
+        Action::new(
+            name,
+            ActionPayload::RunScript {
+                language: "sh".into(),
+                script: script.into(),
+            },
+        )
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn composite_passes_outputs_and_honours_conditions() {
+        let app = create_app();
+        let (runner, _events) = build_runner(&app);
+        let dir = tempfile::tempdir().expect("tempdir");
+        let result_file = dir.path().join("result.txt");
+        let mut provider = MapProvider::default();
+
+        let greet = script_action("greet", "echo hello");
+        let broken = script_action("broken", "exit 4");
+        let shout = script_action(
+            "shout",
+            &format!(
+                "printf '%s!' {{{{steps.greet.stdout}}}} > '{}'",
+                result_file.display()
+            ),
+        );
+        let never = script_action("never", "exit 9");
+        for action in [&greet, &broken, &shout, &never] {
+            provider.insert(action.clone());
+        }
+
+        let composite: Action = serde_json::from_value(serde_json::json!({
+            "id": ActionId::new(),
+            "name": "flow",
+            "payload": {
+                "kind": "composite",
+                "actions": [
+                    { "action": greet.id, "name": "greet" },
+                    { "action": broken.id, "continueOnError": true },
+                    { "action": shout.id, "when": { "kind": "previous_failed" } },
+                    {
+                        "action": never.id,
+                        "when": { "kind": "process_matches", "value": "regex:^term" }
+                    },
+                    never.id
+                ]
             }
+        }))
+        .expect("composite json");
+        let ActionPayload::Composite { actions } = &composite.payload else {
+            panic!("composite payload");
+        };
+        assert_eq!(
+            serde_json::to_value(&actions[4]).expect("serialize"),
+            serde_json::json!(never.id)
+        );
+
+        let context = TemplateContext::new(
+            WindowSnapshot {
+                process_name: Some("code".into()),
+                ..WindowSnapshot::default()
+            },
+            None,
+        );
+        let err =
+            block_on(runner.run_with_invocation(&composite, &provider, Uuid::new_v4(), context))
+                .expect_err("unconditional failing child stops the composite");
+        assert!(err.to_string().contains("exit status: 9"), "{err}");
+        assert_eq!(
+            std::fs::read_to_string(&result_file).expect("shout ran"),
+            "hello!"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parallel_groups_join_all_or_first_success() {
+        let app = create_app();
+        let (runner, _events) = build_runner(&app);
+        let mut provider = MapProvider::default();
+        let short = script_action("short", "sleep 0.3");
+        let hung = script_action("hung", "sleep 30");
+        let failing = script_action("failing", "exit 2");
+        for action in [&short, &hung, &failing] {
+            provider.insert(action.clone());
+        }
+        let group = |children: Vec<ActionId>, join: JoinMode| {
+            let mut action = composite_action(Vec::new(), "group");
+            action.payload = ActionPayload::Composite {
+                actions: vec![CompositeStep::Parallel(ParallelGroup {
+                    parallel: children.into_iter().map(CompositeChild::from).collect(),
+                    join,
+                    name: None,
+                    when: None,
+                    continue_on_error: false,
+                })],
+            };
+            action
+        };
+
+        let started = Instant::now();
+        block_on(runner.run(&group(vec![short.id, short.id], JoinMode::All), &provider))
+            .expect("all branches succeed");
+        assert!(started.elapsed() < Duration::from_millis(550));
+
+        let started = Instant::now();
+        block_on(runner.run(
+            &group(vec![hung.id, failing.id, short.id], JoinMode::Any),
+            &provider,
+        ))
+        .expect("first success wins");
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        let started = Instant::now();
+        let err = block_on(runner.run(&group(vec![hung.id, failing.id], JoinMode::All), &provider))
+            .expect_err("failing branch fails the group");
+        assert!(err.to_string().contains("exit status: 2"), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn cycles_are_reported_through_continue_on_error() {
+        let app = create_app();
+        let (runner, _events) = build_runner(&app);
+        let mut provider = MapProvider::default();
+        let mut first = composite_action(Vec::new(), "first");
+        let second = composite_action(vec![first.id], "second");
+        first.payload = ActionPayload::Composite {
+            actions: vec![CompositeStep::Child(CompositeChild {
+                continue_on_error: true,
+                ..second.id.into()
+            })],
         };
+        provider.insert(first.clone());
+        provider.insert(second);
+
+        let err = block_on(runner.run(&first, &provider)).expect_err("cycle");
+        assert!(err.to_string().contains("cycle detected"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn explain_plans_the_tree_without_side_effects() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        let dir = tempfile::tempdir().expect("tempdir");
+        let marker = dir.path().join("ran");
+        let mut provider = MapProvider::default();
+
+        let launch = Action::new(
+            "launch",
+            ActionPayload::LaunchProgram {
+                executable: "tools/run.sh".into(),
+                arguments: vec!["--profile={{profile.name}}".into()],
+                working_dir: None,
+                options: LaunchOptions::default(),
+            },
+        );
+        let script = script_action(
+            "touch",
+            &format!("touch '{}' {{{{steps.first.stdout}}}}", marker.display()),
+        );
+        let keys = delay_macro("keys", &[5], 1000);
+        for action in [&launch, &script, &keys] {
+            provider.insert(action.clone());
+        }
+        let composite: Action = serde_json::from_value(serde_json::json!({
+            "id": ActionId::new(),
+            "name": "plan",
+            "payload": {
+                "kind": "composite",
+                "actions": [
+                    { "action": launch.id, "name": "first" },
+                    {
+                        "parallel": [
+                            script.id,
+                            {
+                                "action": keys.id,
+                                "when": { "kind": "process_matches", "value": "code" }
+                            }
+                        ],
+                        "join": "any"
+                    }
+                ]
+            }
+        }))
+        .expect("composite json");
+
+        let context = TemplateContext::new(WindowSnapshot::default(), Some("Work".into()));
+        let plan = block_on(runner.explain(&composite, &provider, context)).expect("plan");
+        let plan = serde_json::to_value(&plan).expect("serialize plan");
+
+        let first = &plan["steps"][0]["plan"];
+        assert_eq!(first["kind"], "launch_program");
+        assert_eq!(
+            first["resolvedPath"],
+            runner.data_dir().join("tools/run.sh").display().to_string()
+        );
+        assert_eq!(first["arguments"], serde_json::json!(["--profile=Work"]));
+        assert_eq!(first["templates"][0]["rendered"], "--profile=Work");
+
+        let group = &plan["steps"][1];
+        assert_eq!(group["kind"], "parallel");
+        assert_eq!(group["join"], "any");
+        let script_plan = &group["branches"][0]["plan"];
+        assert_eq!(script_plan["interpreter"], "sh");
+        assert_eq!(
+            script_plan["arguments"][1],
+            format!("touch '{}' {{{{steps.first.stdout}}}}", marker.display())
+        );
+        assert_eq!(group["branches"][1]["conditionHolds"], false);
+        assert_eq!(group["branches"][1]["plan"]["steps"][0]["kind"], "delay");
 
-        let status = command
-            .status()
-            .await
-            .map_err(|err| AppError::Message(format!("failed to run {label} script: {err}")))?;
+        assert!(!marker.exists());
+        assert!(events.recent().is_empty());
+    }
 
-        if status.success() {
-            Ok(())
-        } else {
-            Err(AppError::Message(format!(
-                "{label} script exited with status {status}"
-            )))
-        }
+    #[test]
+    fn window_payloads_act_on_the_window_backend() {
+        let app = create_app();
+        let (runner, _events) = build_runner(&app);
+        let windows = Arc::new(RecordingWindows::new(vec![Rect::new(0, 0, 1920, 1080)]));
+        windows.open(7, "editor", Rect::new(0, 0, 800, 600));
+        let runner = runner.with_windows(windows.clone());
+        let provider = MapProvider::default();
+
+        let maximize = Action::new(
+            "maximize",
+            ActionPayload::Window {
+                operation: WindowOperation::Maximize,
+            },
+        );
+        let payload = block_on(runner.run(&maximize, &provider)).expect("maximize");
+        assert_eq!(payload.status, ActionEventStatus::Success);
+
+        let focus = Action::new(
+            "editor",
+            ActionPayload::Window {
+                operation: WindowOperation::FocusOrLaunch {
+                    process_name: "editor".into(),
+                    executable: Some("/nonexistent/editor".into()),
+                    arguments: Vec::new(),
+                },
+            },
+        );
+        let payload = block_on(runner.run(&focus, &provider)).expect("focus");
+        assert_eq!(payload.status, ActionEventStatus::Success);
+        assert_eq!(
+            windows.calls(),
+            vec![WindowCall::Maximize(7), WindowCall::Focus(7)]
+        );
+
+        let plan =
+            block_on(runner.explain(&focus, &provider, TemplateContext::default())).expect("plan");
+        let plan = serde_json::to_value(plan).expect("serialize plan");
+        assert_eq!(plan["kind"], "window");
+        assert_eq!(plan["operation"]["type"], "focus_or_launch");
+        assert_eq!(plan["backend"], "recording");
     }
 
-    fn handle_outcome(
-        &self,
-        action: &Action,
-        status: ActionEventStatus,
-        message: Option<String>,
-        duration_ms: u32,
-        timestamp: OffsetDateTime,
-    ) -> Result<ActionEventPayload, AppError> {
-        let default_message = match status {
-            ActionEventStatus::Success => {
-                format!("Action '{}' executed", action.name)
-            }
-            ActionEventStatus::Skipped => {
-                format!("Action '{}' skipped (not supported)", action.name)
-            }
-            ActionEventStatus::Failure => format!("Action '{}' failed", action.name),
+    #[test]
+    fn clipboard_actions_feed_later_templates_and_restore_after_paste() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        let keyboard = Arc::new(RecordingBackend::default());
+        let runner = runner.with_keyboard(keyboard.clone());
+        let clipboard = Arc::new(MemoryClipboard::default());
+        clipboard.write_text("previous").expect("seed clipboard");
+        let context = || {
+            TemplateContext::new(WindowSnapshot::default(), Some("Work".into()))
+                .with_clipboard(clipboard.clone())
+        };
+        let clipboard_action =
+            |name: &str, operation| Action::new(name, ActionPayload::Clipboard { operation });
+        let paste = |text: &str, restore_clipboard| {
+            Action::new(
+                "paste",
+                ActionPayload::TypeText {
+                    text: text.into(),
+                    paste_keys: None,
+                    restore_clipboard,
+                },
+            )
         };
 
-        let final_message = message.unwrap_or(default_message);
-        match status {
-            ActionEventStatus::Success => self.log_info(&final_message)?,
-            ActionEventStatus::Skipped => self.log_warn(&final_message)?,
-            ActionEventStatus::Failure => self.log_error(&final_message),
+        let set = clipboard_action(
+            "set",
+            ClipboardOperation::Set {
+                text: "{{clipboard}} for {{profile.name}}".into(),
+            },
+        );
+        let shout = clipboard_action("shout", ClipboardOperation::Uppercase);
+        let paste_clipboard = paste("{{clipboard}}!", false);
+        let mut provider = MapProvider::default();
+        let snippet = composite_action(vec![set.id, shout.id, paste_clipboard.id], "snippet");
+        for action in [set, shout, paste_clipboard] {
+            provider.insert(action);
         }
-
-        let payload = ActionEventPayload::new(
-            action.id,
-            action.name.clone(),
-            status,
-            duration_ms,
-            Some(final_message.clone()),
-            timestamp,
-            None,
+        let payload =
+            block_on(runner.run_with_invocation(&snippet, &provider, Uuid::new_v4(), context()))
+                .expect("snippet");
+        assert_eq!(payload.status, ActionEventStatus::Success);
+        assert_eq!(
+            clipboard.read_text().unwrap().as_deref(),
+            Some("PREVIOUS FOR WORK!")
         );
 
-        self.publish_payload(&payload)?;
+        let payload = block_on(runner.run_with_invocation(
+            &paste("snippet", true),
+            &provider,
+            Uuid::new_v4(),
+            context(),
+        ))
+        .expect("paste with restore");
+        assert_eq!(payload.status, ActionEventStatus::Success);
 
-        if status == ActionEventStatus::Failure {
-            return Err(AppError::Message(final_message));
-        }
+        let read = clipboard_action("read", ClipboardOperation::Read);
+        block_on(runner.run_with_invocation(&read, &provider, Uuid::new_v4(), context()))
+            .expect("read");
+        let output = events.recent().pop().and_then(|event| event.output);
+        assert_eq!(output.expect("output").stdout, "PREVIOUS FOR WORK!");
 
-        Ok(payload)
+        let ctrl = Key::Named(NamedKey::Ctrl);
+        let paste_keys = [
+            KeyEvent::Press(ctrl),
+            KeyEvent::Press(Key::Char('v')),
+            KeyEvent::Release(Key::Char('v')),
+            KeyEvent::Release(ctrl),
+        ];
+        assert_eq!(keyboard.events(), paste_keys.repeat(2));
     }
 
-    fn log_info(&self, message: &str) -> Result<(), AppError> {
-        self.audit.log("INFO", message).map_err(AppError::from)
-    }
+    #[cfg(unix)]
+    #[test]
+    fn launch_options_shape_the_environment_and_wait_for_exit() {
+        use std::os::unix::fs::PermissionsExt;
 
-    fn log_warn(&self, message: &str) -> Result<(), AppError> {
-        self.audit.log("WARN", message).map_err(AppError::from)
-    }
+        let app = create_app();
+        let (runner, _events) = build_runner(&app);
+        let provider = MapProvider::default();
+        let bin = runner.data_dir().join("bin");
+        std::fs::create_dir_all(&bin).expect("bin dir");
+        let greet = bin.join("greet");
+        std::fs::write(
+            &greet,
+            "#!/bin/sh\necho \"$GREETING ${HOME:-no home}\"\ncat\necho oops >&2\n",
+        )
+        .expect("script");
+        std::fs::set_permissions(&greet, std::fs::Permissions::from_mode(0o755)).expect("chmod");
+        std::fs::write(runner.data_dir().join("input.txt"), "from stdin\n").expect("input");
+        let log = runner.data_dir().join("greet.log");
+        let _ = std::fs::remove_file(&log);
 
-    fn log_error(&self, message: &str) {
-        self.audit.log_err("ERROR", message);
+        let launch = |options| {
+            Action::new(
+                "greet",
+                ActionPayload::LaunchProgram {
+                    executable: "greet".into(),
+                    arguments: Vec::new(),
+                    working_dir: None,
+                    options,
+                },
+            )
+        };
+        let greeting = launch(LaunchOptions {
+            env: LaunchEnvironment {
+                set: BTreeMap::from([("GREETING".to_string(), "hi {{profile.name}}".to_string())]),
+                unset: vec!["HOME".into()],
+                path_prepend: vec!["bin".into()],
+            },
+            stdin: Some("input.txt".into()),
+            stderr: Some("greet.log".into()),
+            mode: LaunchMode::Wait,
+            ..LaunchOptions::default()
+        });
+        let context = || TemplateContext::new(WindowSnapshot::default(), Some("Work".into()));
+
+        let plan = block_on(runner.explain(&greeting, &provider, context())).expect("plan");
+        let plan = serde_json::to_value(plan).expect("serialize plan");
+        assert_eq!(plan["resolvedPath"], greet.display().to_string());
+        assert_eq!(plan["env"]["GREETING"], "hi Work");
+        assert_eq!(plan["env"]["HOME"], Value::Null);
+        assert_eq!(plan["mode"], "wait");
+
+        let payload =
+            block_on(runner.run_with_invocation(&greeting, &provider, Uuid::new_v4(), context()))
+                .expect("launch");
+        assert_eq!(payload.status, ActionEventStatus::Success);
+        assert_eq!(
+            payload.output.expect("captured output").stdout,
+            "hi Work no home\nfrom stdin\n"
+        );
+        assert_eq!(std::fs::read_to_string(&log).expect("stderr log"), "oops\n");
+
+        // Without the prepended directory the bare name is not found.
+        let err = block_on(runner.run(&launch(LaunchOptions::default()), &provider))
+            .expect_err("not on PATH");
+        assert!(err.to_string().contains("not found on PATH"), "{err}");
     }
 
-    fn publish_payload(&self, payload: &ActionEventPayload) -> Result<(), AppError> {
-        self.audit.log_action_outcome(payload)?;
-        self.events.emit(payload.clone());
-        Ok(())
+    #[test]
+    fn open_targets_resolve_against_the_data_dir() {
+        let app = create_app();
+        let (runner, _events) = build_runner(&app);
+        let provider = MapProvider::default();
+        std::fs::create_dir_all(runner.data_dir().join("notes")).expect("notes dir");
+        std::fs::write(runner.data_dir().join("notes/todo.md"), "- ship").expect("note");
+        let open = |target: &str| {
+            Action::new(
+                "open",
+                ActionPayload::Open {
+                    target: target.into(),
+                },
+            )
+        };
+        let explain = |action: &Action| {
+            let plan = block_on(runner.explain(action, &provider, TemplateContext::default()))
+                .expect("plan");
+            serde_json::to_value(plan).expect("serialize plan")
+        };
+
+        let plan = explain(&open("notes/todo.md"));
+        assert_eq!(plan["kind"], "open");
+        assert_eq!(
+            plan["resolved"],
+            runner
+                .data_dir()
+                .join("notes/todo.md")
+                .display()
+                .to_string()
+        );
+        assert_eq!(plan["exists"], true);
+
+        let plan = explain(&open("https://example.com/docs?q=1"));
+        assert_eq!(plan["resolved"], "https://example.com/docs?q=1");
+        assert_eq!(plan["exists"], true);
+
+        let file_url =
+            reqwest::Url::from_file_path(runner.data_dir().join("notes")).expect("file url");
+        let plan = explain(&open(file_url.as_str()));
+        assert_eq!(
+            plan["resolved"],
+            runner.data_dir().join("notes").display().to_string()
+        );
+
+        // Missing paths fail before anything is handed to the opener.
+        let payload = block_on(runner.run(&open("notes/missing.md"), &provider)).expect("run");
+        assert_eq!(payload.status, ActionEventStatus::Failure);
+        assert!(payload
+            .message
+            .unwrap_or_default()
+            .contains("does not exist"));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Answers one request on a local port with `response` and hands the
+    /// raw request back.
+    fn http_stand_in(response: &'static str) -> (String, std::sync::mpsc::Receiver<String>) {
+        use std::io::{Read, Write};
 
-    use crate::storage::StorageManager;
-    use serde_json::Value;
-    use std::collections::HashMap;
-    use tauri::async_runtime::block_on;
-    use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
-    #[derive(Default)]
-    struct MapProvider {
-        actions: HashMap<ActionId, Action>,
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}", listener.local_addr().expect("local addr"));
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            loop {
+                let read = stream.read(&mut buffer).expect("read request");
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text[..end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            stream
+                .write_all(response.as_bytes())
+                .expect("write response");
+            let _ = sender.send(String::from_utf8_lossy(&request).into_owned());
+        });
+        (url, receiver)
     }
 
-    impl MapProvider {
-        fn insert(&mut self, action: Action) {
-            self.actions.insert(action.id, action);
-        }
+    #[test]
+    fn http_requests_capture_the_response_and_check_the_status() {
+        let app = create_app();
+        let (runner, _events) = build_runner(&app);
+        let provider = MapProvider::default();
+        let hook = |url: String, success_status| {
+            Action::new(
+                "hook",
+                ActionPayload::HttpRequest {
+                    method: "post".into(),
+                    url: format!("{url}/hooks/{{{{profile.name}}}}"),
+                    headers: BTreeMap::from([(
+                        "Content-Type".to_string(),
+                        "application/json".to_string(),
+                    )]),
+                    body: Some(r#"{"profile": {{profile.name}}}"#.into()),
+                    timeout_ms: Some(5_000),
+                    success_status,
+                },
+            )
+        };
+        let run = |action: Action| {
+            let context = TemplateContext::new(WindowSnapshot::default(), Some("Home Lab".into()));
+            block_on(runner.run_with_invocation(&action, &provider, Uuid::new_v4(), context))
+                .expect("request")
+        };
+
+        let (url, requests) = http_stand_in(
+            "HTTP/1.1 201 Created\r\nContent-Length: 11\r\nConnection: close\r\n\r\n{\"ok\":true}",
+        );
+        let payload = run(hook(url, Vec::new()));
+        assert_eq!(payload.status, ActionEventStatus::Success);
+        let output = payload.output.expect("response output");
+        assert_eq!(output.http_status, Some(201));
+        assert_eq!(output.exit_code, None);
+        assert_eq!(output.stdout, r#"{"ok":true}"#);
+        let request = requests.recv().expect("recorded request");
+        assert!(request.starts_with("POST /hooks/Home%20Lab HTTP/1.1\r\n"));
+        assert!(request
+            .to_ascii_lowercase()
+            .contains("content-type: application/json"));
+        assert!(request.ends_with(r#"{"profile": "Home Lab"}"#));
+
+        let (url, _requests) = http_stand_in(
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+        let payload = run(hook(
+            url,
+            vec![
+                StatusMatcher::Pattern("2xx".into()),
+                StatusMatcher::Code(404),
+            ],
+        ));
+        assert_eq!(payload.status, ActionEventStatus::Success);
+
+        let (url, _requests) = http_stand_in(
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+        let payload = run(hook(url, vec![StatusMatcher::Pattern("200-299".into())]));
+        assert_eq!(payload.status, ActionEventStatus::Failure);
+        assert_eq!(
+            payload.output.and_then(|output| output.http_status),
+            Some(404)
+        );
     }
 
-    impl ActionProvider for MapProvider {
-        fn get_action(&self, id: &ActionId) -> Option<Action> {
-            self.actions.get(id).cloned()
-        }
+    #[test]
+    fn send_keys_and_macro_keys_share_keyboard_backend() {
+        let app = create_app();
+        let (runner, _events) = build_runner(&app);
+        let keyboard = Arc::new(RecordingBackend::default());
+        let runner = runner.with_keyboard(keyboard.clone());
+        let provider = MapProvider::default();
+
+        let send_keys = Action::new(
+            "copy",
+            ActionPayload::SendKeys {
+                sequence: "^c".to_string(),
+                delay_ms: None,
+            },
+        );
+        let payload = block_on(runner.run(&send_keys, &provider)).expect("send keys");
+        assert_eq!(payload.status, ActionEventStatus::Success);
+
+        let keys_macro = Action::new(
+            "confirm",
+            ActionPayload::Macro {
+                steps: vec![MacroStepDefinition {
+                    id: ActionId::new(),
+                    order: 0,
+                    kind: MacroStepKind::Keys {
+                        keys: "{Enter}".to_string(),
+                        repeat: 2,
+                    },
+                    retry: None,
+                    note: None,
+                }],
+            },
+        );
+        block_on(runner.run(&keys_macro, &provider)).expect("keys macro");
+
+        let ctrl = Key::Named(NamedKey::Ctrl);
+        let enter = Key::Named(NamedKey::Enter);
+        assert_eq!(
+            keyboard.events(),
+            vec![
+                KeyEvent::Press(ctrl),
+                KeyEvent::Press(Key::Char('c')),
+                KeyEvent::Release(Key::Char('c')),
+                KeyEvent::Release(ctrl),
+                KeyEvent::Press(enter),
+                KeyEvent::Release(enter),
+                KeyEvent::Press(enter),
+                KeyEvent::Release(enter),
+            ]
+        );
     }
 
-    fn build_runner(app: &tauri::App<MockRuntime>) -> (ActionRunner, ActionEventsChannel) {
-        let handle = app.handle();
-        let storage = StorageManager::new(handle.clone()).expect("storage");
+    #[cfg(unix)]
+    #[test]
+    fn command_output_is_captured_and_logged() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        let provider = MapProvider::default();
+        let failing = Action::new(
+            "noisy",
+            ActionPayload::SystemCommand {
+                command: "echo out; echo oops >&2; exit 3".into(),
+            },
+        );
+
+        let err = block_on(runner.run(&failing, &provider)).expect_err("exit 3 fails");
+        assert!(err.to_string().contains("oops"));
+
+        let event = events.recent().pop().expect("event");
+        assert_eq!(event.status, ActionEventStatus::Failure);
+        let output = event.output.clone().expect("captured output");
+        assert_eq!(output.exit_code, Some(3));
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "oops\n");
+        assert!(!output.stdout_truncated);
+
+        let storage = StorageManager::new(app.handle().clone()).expect("storage");
         let audit = AuditLogger::from_storage(&storage).expect("audit");
-        let events = ActionEventsChannel::new(16);
-        let data_dir = storage.base_dir().to_path_buf();
-        let runner = ActionRunner::new(data_dir, audit, events.clone());
-        (runner, events)
+        let logged = audit
+            .find_action_event(event.event_id)
+            .expect("read audit log")
+            .expect("event logged");
+        assert_eq!(logged.output, Some(output));
     }
 
-    fn create_app() -> tauri::App<MockRuntime> {
-        mock_builder()
-            .build(mock_context(noop_assets()))
-            .expect("build app")
+    #[cfg(unix)]
+    #[test]
+    fn retried_actions_emit_one_event_per_attempt() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        let provider = MapProvider::default();
+        let marker = std::env::temp_dir().join(format!("autohotpie-retry-{}", Uuid::new_v4()));
+        let mut flaky = Action::new(
+            "flaky",
+            ActionPayload::SystemCommand {
+                command: format!(
+                    "test -f '{0}' || {{ touch '{0}'; exit 1; }}",
+                    marker.display()
+                ),
+            },
+        );
+        flaky.retry = Some(RetryPolicy {
+            max_attempts: 3,
+            backoff: Backoff::Exponential,
+            delay_ms: 10,
+            max_delay_ms: None,
+            retry_on: vec![RetryOn::Failure],
+        });
+
+        let payload = block_on(runner.run(&flaky, &provider)).expect("second attempt succeeds");
+        let _ = std::fs::remove_file(&marker);
+        assert_eq!(payload.status, ActionEventStatus::Success);
+        assert_eq!(payload.attempt, 2);
+
+        let attempts: Vec<(ActionEventStatus, u32)> = events
+            .recent()
+            .iter()
+            .map(|event| (event.status, event.attempt))
+            .collect();
+        assert_eq!(
+            attempts,
+            [
+                (ActionEventStatus::Failure, 1),
+                (ActionEventStatus::Success, 2)
+            ]
+        );
+
+        // Failures outside `retry_on` are reported right away.
+        flaky.retry = Some(RetryPolicy {
+            retry_on: vec![RetryOn::TimedOut],
+            ..flaky.retry.clone().unwrap()
+        });
+        let err = block_on(runner.run(&flaky, &provider)).expect_err("not retried");
+        let _ = std::fs::remove_file(&marker);
+        assert!(!err.to_string().contains("attempt"));
+        assert_eq!(events.recent().pop().map(|event| event.attempt), Some(1));
     }
 
-    fn composite_action(children: Vec<ActionId>, name: &str) -> Action {
-        Action {
-            id: ActionId::new(),
-            name: name.to_string(),
-            description: None,
-            payload: ActionPayload::Composite { actions: children },
-            enabled: true,
-        }
+    #[test]
+    fn retry_backoff_grows_and_is_capped() {
+        let mut retry = RetryPolicy {
+            max_attempts: 0,
+            backoff: Backoff::Fixed,
+            delay_ms: 100,
+            max_delay_ms: Some(350),
+            retry_on: vec![RetryOn::Error],
+        };
+        assert_eq!(retry.attempts(), 1);
+        assert_eq!(retry.delay_before(4), Duration::from_millis(100));
+        retry.backoff = Backoff::Exponential;
+        assert_eq!(retry.delay_before(2), Duration::from_millis(100));
+        assert_eq!(retry.delay_before(3), Duration::from_millis(200));
+        assert_eq!(retry.delay_before(4), Duration::from_millis(350));
     }
 
-    fn skip_action(name: &str) -> Action {
-        Action {
-            id: ActionId::new(),
-            name: name.to_string(),
-            description: None,
-            payload: ActionPayload::Custom {
-                handler: "noop".to_string(),
-                params: Value::Null,
+    #[cfg(unix)]
+    #[test]
+    fn templates_resolve_from_window_and_profile() {
+        let app = create_app();
+        let (runner, events) = build_runner(&app);
+        let provider = MapProvider::default();
+        let window = WindowSnapshot {
+            window_title: Some("don't; rm -rf x".into()),
+            ..WindowSnapshot::default()
+        };
+        let context = || {
+            TemplateContext::new(window.clone(), Some("Work".into()))
+                .with_clipboard(Arc::new(MemoryClipboard::default()))
+        };
+        let script = Action::new(
+            "templated",
+            ActionPayload::RunScript {
+                language: "sh".into(),
+                script: "printf '%s|%s|%s' {{window.title}} {{profile.name}} {{clipboard}}".into(),
             },
-            enabled: true,
-        }
+        );
+
+        block_on(runner.run_with_invocation(&script, &provider, Uuid::new_v4(), context()))
+            .expect("templated script");
+        let output = events.recent().pop().and_then(|event| event.output);
+        assert_eq!(output.expect("output").stdout, "don't; rm -rf x|Work|");
+
+        let invalid = Action::new(
+            "invalid",
+            ActionPayload::SystemCommand {
+                command: "echo {{window.size}}".into(),
+            },
+        );
+        let err =
+            block_on(runner.run_with_invocation(&invalid, &provider, Uuid::new_v4(), context()))
+                .expect_err("unknown variable");
+        assert!(err.to_string().contains("window.size"));
+    }
+
+    fn with_policy(mut action: Action, concurrency: ConcurrencyPolicy) -> Action {
+        action.concurrency = concurrency;
+        action
     }
 
     #[test]
-    fn emits_skipped_payload_for_composite_sequence() {
+    fn parallel_runs_overlap_and_queue_runs_serialize() {
         let app = create_app();
-        let (runner, events) = build_runner(&app);
-        let mut provider = MapProvider::default();
+        let (runner, _) = build_runner(&app);
+        let provider = MapProvider::default();
 
-        let child_success = composite_action(Vec::new(), "child-success");
-        let child_skip = skip_action("child-skip");
-        let parent = composite_action(vec![child_success.id, child_skip.id], "parent");
+        let parallel = with_policy(
+            delay_macro("parallel", &[300], 5_000),
+            ConcurrencyPolicy::Parallel,
+        );
+        let started = Instant::now();
+        let (first, second) = block_on(async {
+            tokio::join!(
+                runner.run(&parallel, &provider),
+                runner.run(&parallel, &provider)
+            )
+        });
+        assert!(first.is_ok() && second.is_ok());
+        assert!(started.elapsed() < Duration::from_millis(550));
 
-        provider.insert(child_success.clone());
-        provider.insert(child_skip.clone());
-        provider.insert(parent.clone());
+        let queued = with_policy(
+            delay_macro("queued", &[300], 5_000),
+            ConcurrencyPolicy::Queue,
+        );
+        let started = Instant::now();
+        let (first, second) = block_on(async {
+            tokio::join!(
+                runner.run(&queued, &provider),
+                runner.run(&queued, &provider)
+            )
+        });
+        assert!(first.is_ok() && second.is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(600));
+    }
 
-        let payload = block_on(runner.run(&parent, &provider)).expect("run should succeed");
-        assert_eq!(payload.id, parent.id);
-        assert_eq!(payload.status, ActionEventStatus::Skipped);
-        assert!(payload
+    #[test]
+    fn drop_if_running_skips_and_exclusive_runs_alone() {
+        let app = create_app();
+        let (runner, _) = build_runner(&app);
+        let provider = MapProvider::default();
+
+        let single = with_policy(
+            delay_macro("single", &[200], 5_000),
+            ConcurrencyPolicy::DropIfRunning,
+        );
+        let (first, second) = block_on(async {
+            tokio::join!(runner.run(&single, &provider), async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                runner.run(&single, &provider).await
+            })
+        });
+        assert_eq!(first.expect("first").status, ActionEventStatus::Success);
+        let second = second.expect("dropped runs are not errors");
+        assert_eq!(second.status, ActionEventStatus::Skipped);
+        assert!(second
             .message
-            .as_deref()
             .unwrap_or_default()
-            .contains("child-skip"));
+            .contains("already running"));
 
-        let history = events.recent();
-        assert_eq!(history.len(), 1);
-        assert_eq!(history[0].status, ActionEventStatus::Skipped);
-        assert_eq!(history[0].id, parent.id);
+        let exclusive = with_policy(
+            delay_macro("exclusive", &[300], 5_000),
+            ConcurrencyPolicy::Exclusive,
+        );
+        let other = with_policy(
+            delay_macro("other", &[300], 5_000),
+            ConcurrencyPolicy::Parallel,
+        );
+        let started = Instant::now();
+        let (first, second) = block_on(async {
+            tokio::join!(runner.run(&exclusive, &provider), async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                runner.run(&other, &provider).await
+            })
+        });
+        assert!(first.is_ok() && second.is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(600));
     }
 
     #[test]
-    fn emits_failure_payload_when_child_missing() {
+    fn raising_the_limit_releases_runs_already_waiting() {
         let app = create_app();
-        let (runner, events) = build_runner(&app);
-        let mut provider = MapProvider::default();
+        let (runner, _) = build_runner(&app);
+        runner.set_max_concurrency(1);
+        let provider = MapProvider::default();
+        let slow = with_policy(
+            delay_macro("slow", &[400], 5_000),
+            ConcurrencyPolicy::Parallel,
+        );
+        let waiting = with_policy(
+            delay_macro("waiting", &[10], 5_000),
+            ConcurrencyPolicy::Parallel,
+        );
 
-        let missing_child = ActionId::new();
-        let parent = composite_action(vec![missing_child], "parent");
-        provider.insert(parent.clone());
+        let started = Instant::now();
+        let (_, waited, _) = block_on(async {
+            tokio::join!(
+                runner.run(&slow, &provider),
+                async {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    runner.run(&waiting, &provider).await?;
+                    Ok::<_, AppError>(started.elapsed())
+                },
+                async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    runner.set_max_concurrency(2);
+                }
+            )
+        });
+        assert!(waited.expect("waiting run") < Duration::from_millis(300));
+    }
 
-        let result = block_on(runner.run(&parent, &provider));
-        assert!(result.is_err());
+    #[test]
+    fn lowering_the_limit_waits_for_running_invocations() {
+        let app = create_app();
+        let (runner, _) = build_runner(&app);
+        runner.set_max_concurrency(2);
+        let provider = MapProvider::default();
+        let slow = with_policy(
+            delay_macro("slow", &[300], 5_000),
+            ConcurrencyPolicy::Parallel,
+        );
+        let exclusive = with_policy(
+            delay_macro("exclusive", &[10], 5_000),
+            ConcurrencyPolicy::Exclusive,
+        );
 
-        let history = events.recent();
-        assert_eq!(history.len(), 1);
-        assert_eq!(history[0].status, ActionEventStatus::Failure);
-        assert_eq!(history[0].id, parent.id);
-        assert!(history[0]
-            .message
-            .as_deref()
-            .unwrap_or_default()
-            .contains("not found"));
+        let started = Instant::now();
+        let (first, second, exclusive) = block_on(async {
+            tokio::join!(
+                runner.run(&slow, &provider),
+                runner.run(&slow, &provider),
+                async {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    runner.set_max_concurrency(1);
+                    runner.run(&exclusive, &provider).await
+                }
+            )
+        });
+        assert!(first.is_ok() && second.is_ok());
+        assert!(exclusive.is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(300));
+
+        let (a, b) = block_on(async {
+            tokio::join!(runner.run(&slow, &provider), async {
+                let started = Instant::now();
+                runner.run(&slow, &provider).await?;
+                Ok::<_, AppError>(started.elapsed())
+            })
+        });
+        assert!(a.is_ok());
+        assert!(b.expect("second run") >= Duration::from_millis(550));
+    }
+
+    #[test]
+    fn list_running_reports_running_and_queued_invocations() {
+        let app = create_app();
+        let (runner, _) = build_runner(&app);
+        runner.set_max_concurrency(1);
+        let provider = MapProvider::default();
+        let slow = with_policy(
+            delay_macro("slow", &[300], 5_000),
+            ConcurrencyPolicy::Parallel,
+        );
+        let next = with_policy(
+            delay_macro("next", &[10], 5_000),
+            ConcurrencyPolicy::Parallel,
+        );
+
+        let (_, _, listed) = block_on(async {
+            tokio::join!(
+                runner.run(&slow, &provider),
+                async {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    runner.run(&next, &provider).await
+                },
+                async {
+                    tokio::time::sleep(Duration::from_millis(150)).await;
+                    runner.list_running()
+                }
+            )
+        });
+        let summary: Vec<(&str, RunState)> = listed
+            .iter()
+            .map(|entry| (entry.action_name.as_str(), entry.state))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("slow", RunState::Running), ("next", RunState::Queued)]
+        );
+        assert!(listed[0].started_at.is_some());
+        assert!(runner.list_running().is_empty());
+    }
+
+    #[test]
+    fn split_arguments_honours_quotes() {
+        assert_eq!(
+            split_arguments(r#"--path "C:\Program Files\App" -v ''"#),
+            vec!["--path", r"C:\Program Files\App", "-v", ""]
+        );
     }
 }
@@ -1,6 +1,6 @@
 use crate::domain::ActionEventPayload;
 use crate::storage::StorageManager;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::cmp::Ordering;
 use std::fs::{self, File, OpenOptions};
@@ -10,6 +10,7 @@ use std::sync::{Arc, Mutex};
 use time::format_description::FormatItem;
 use time::macros::format_description;
 use time::OffsetDateTime;
+use uuid::Uuid;
 
 const LOG_DIR: &str = "logs";
 const LOG_FILE_PREFIX: &str = "AHP-Audit";
@@ -34,6 +35,11 @@ pub struct AuditLogSnapshot {
     pub truncated: bool,
 }
 
+#[derive(Deserialize)]
+struct LoggedActionEvent {
+    event: ActionEventPayload,
+}
+
 #[derive(Clone)]
 pub struct AuditLogger {
     inner: Arc<Mutex<AuditLoggerInner>>,
@@ -95,6 +101,43 @@ impl AuditLogger {
         self.log("ACTION", &serialized)
     }
 
+    /// Finds a logged action event by id, newest log file first.
+    pub fn find_action_event(&self, event_id: Uuid) -> io::Result<Option<ActionEventPayload>> {
+        let log_dir = {
+            let guard = self
+                .inner
+                .lock()
+                .map_err(|_| io::Error::other("audit logger poisoned"))?;
+            guard.log_dir.clone()
+        };
+
+        let needle = event_id.to_string();
+        for path in collect_log_files(&log_dir)?.iter().rev() {
+            let file = match File::open(path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            for line in BufReader::new(file).lines() {
+                let Ok(line) = line else { continue };
+                if !line.contains(&needle) {
+                    continue;
+                }
+                let record = parse_log_line(&line);
+                if record.level != "ACTION" {
+                    continue;
+                }
+                let Ok(entry) = serde_json::from_str::<LoggedActionEvent>(&record.message) else {
+                    continue;
+                };
+                if entry.event.event_id == event_id {
+                    return Ok(Some(entry.event));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     pub fn current_log_path(&self) -> io::Result<PathBuf> {
         let guard = self
             .inner
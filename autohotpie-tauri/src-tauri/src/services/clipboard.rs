@@ -0,0 +1,178 @@
+#![allow(dead_code)]
+
+use crate::domain::ClipboardOperation;
+use parking_lot::Mutex;
+use regex::Regex;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClipboardError {
+    #[error("clipboard is not available: {0}")]
+    Unavailable(String),
+    #[error("clipboard operation failed: {0}")]
+    Backend(String),
+    #[error("invalid pattern: {0}")]
+    InvalidPattern(String),
+}
+
+/// Text access to the system clipboard. `read_text` returns `None` when the
+/// clipboard is empty or holds non-text data.
+pub trait ClipboardBackend: Send + Sync {
+    fn read_text(&self) -> Result<Option<String>, ClipboardError>;
+    fn write_text(&self, text: &str) -> Result<(), ClipboardError>;
+}
+
+/// System clipboard through `arboard`. The handle is kept open because on X11
+/// the contents we set disappear once the owning handle is dropped.
+#[derive(Default)]
+pub struct SystemClipboard {
+    handle: Mutex<Option<arboard::Clipboard>>,
+}
+
+impl SystemClipboard {
+    fn with_handle<T>(
+        &self,
+        f: impl FnOnce(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+    ) -> Result<T, ClipboardError> {
+        let mut guard = self.handle.lock();
+        if guard.is_none() {
+            let handle = arboard::Clipboard::new()
+                .map_err(|err| ClipboardError::Unavailable(err.to_string()))?;
+            *guard = Some(handle);
+        }
+        f(guard.as_mut().expect("clipboard initialised")).map_err(map_error)
+    }
+}
+
+impl ClipboardBackend for SystemClipboard {
+    fn read_text(&self) -> Result<Option<String>, ClipboardError> {
+        self.with_handle(|clipboard| match clipboard.get_text() {
+            Ok(text) => Ok(Some(text)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(err) => Err(err),
+        })
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), ClipboardError> {
+        self.with_handle(|clipboard| clipboard.set_text(text))
+    }
+}
+
+fn map_error(err: arboard::Error) -> ClipboardError {
+    match err {
+        arboard::Error::ClipboardNotSupported | arboard::Error::ClipboardOccupied => {
+            ClipboardError::Unavailable(err.to_string())
+        }
+        other => ClipboardError::Backend(other.to_string()),
+    }
+}
+
+/// In-process clipboard for tests and headless runs.
+#[derive(Default)]
+pub struct MemoryClipboard {
+    contents: Mutex<Option<String>>,
+}
+
+impl ClipboardBackend for MemoryClipboard {
+    fn read_text(&self) -> Result<Option<String>, ClipboardError> {
+        Ok(self.contents.lock().clone())
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), ClipboardError> {
+        *self.contents.lock() = Some(text.to_string());
+        Ok(())
+    }
+}
+
+pub fn system_clipboard() -> Arc<dyn ClipboardBackend> {
+    Arc::new(SystemClipboard::default())
+}
+
+/// Runs `operation` and returns the clipboard text afterwards, or `None`
+/// when there is no text to read or transform.
+pub fn apply(
+    clipboard: &dyn ClipboardBackend,
+    operation: &ClipboardOperation,
+) -> Result<Option<String>, ClipboardError> {
+    let updated = match operation {
+        ClipboardOperation::Read => return clipboard.read_text(),
+        ClipboardOperation::Set { text } => text.clone(),
+        ClipboardOperation::Append { text } => {
+            let mut current = clipboard.read_text()?.unwrap_or_default();
+            current.push_str(text);
+            current
+        }
+        ClipboardOperation::Uppercase
+        | ClipboardOperation::Lowercase
+        | ClipboardOperation::Trim
+        | ClipboardOperation::RegexReplace { .. } => {
+            let Some(current) = clipboard.read_text()? else {
+                return Ok(None);
+            };
+            transform(operation, current)?
+        }
+    };
+    clipboard.write_text(&updated)?;
+    Ok(Some(updated))
+}
+
+fn transform(operation: &ClipboardOperation, text: String) -> Result<String, ClipboardError> {
+    Ok(match operation {
+        ClipboardOperation::Uppercase => text.to_uppercase(),
+        ClipboardOperation::Lowercase => text.to_lowercase(),
+        ClipboardOperation::Trim => text.trim().to_string(),
+        ClipboardOperation::RegexReplace {
+            pattern,
+            replacement,
+        } => Regex::new(pattern)
+            .map_err(|err| ClipboardError::InvalidPattern(err.to_string()))?
+            .replace_all(&text, replacement.as_str())
+            .into_owned(),
+        ClipboardOperation::Read
+        | ClipboardOperation::Set { .. }
+        | ClipboardOperation::Append { .. } => text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operations_transform_clipboard_text() {
+        let clipboard = MemoryClipboard::default();
+        let run = |operation: ClipboardOperation| apply(&clipboard, &operation).expect("apply");
+
+        assert_eq!(run(ClipboardOperation::Trim), None);
+        run(ClipboardOperation::Set {
+            text: "  order 42 ".into(),
+        });
+        run(ClipboardOperation::Append {
+            text: "shipped".into(),
+        });
+        run(ClipboardOperation::Trim);
+        run(ClipboardOperation::RegexReplace {
+            pattern: r"(\d+)\s+".into(),
+            replacement: "#$1 ".into(),
+        });
+        assert_eq!(
+            run(ClipboardOperation::Uppercase).as_deref(),
+            Some("ORDER #42 SHIPPED")
+        );
+        assert_eq!(
+            clipboard.read_text().unwrap().as_deref(),
+            Some("ORDER #42 SHIPPED")
+        );
+
+        let err = apply(
+            &clipboard,
+            &ClipboardOperation::RegexReplace {
+                pattern: "(".into(),
+                replacement: String::new(),
+            },
+        )
+        .expect_err("invalid pattern");
+        assert!(matches!(err, ClipboardError::InvalidPattern(_)));
+    }
+}
@@ -0,0 +1,579 @@
+use crate::commands::AppError;
+use crate::domain::ActionEventStatus;
+use crate::services::clipboard::ClipboardBackend;
+use crate::services::process_tree;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_notification::NotificationExt;
+use tokio::process::Command;
+
+pub const CUSTOM_HANDLERS_FILE_NAME: &str = "custom-handlers.json";
+
+const DEFAULT_EXTERNAL_TIMEOUT_MS: u64 = 30_000;
+/// Only the tail of a handler's stdout and stderr is kept.
+const MAX_HANDLER_OUTPUT_BYTES: usize = 64 * 1024;
+
+pub type HandlerResult = Result<(ActionEventStatus, Option<String>), AppError>;
+pub type HandlerFuture<'a> = Pin<Box<dyn Future<Output = HandlerResult> + Send + 'a>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandlerSource {
+    Builtin,
+    External,
+}
+
+/// What the action editor needs to offer a handler: its name, a short
+/// description and a JSON schema describing `params`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomHandlerInfo {
+    pub name: String,
+    pub description: String,
+    pub params_schema: Value,
+    pub source: HandlerSource,
+}
+
+/// Executes `ActionPayload::Custom` payloads whose `handler` matches
+/// [`CustomHandlerInfo::name`]. `params` has already been checked against
+/// the declared schema when `invoke` is called.
+pub trait CustomHandler: Send + Sync {
+    fn info(&self) -> CustomHandlerInfo;
+    fn invoke<'a>(&'a self, params: &'a Value) -> HandlerFuture<'a>;
+}
+
+#[derive(Clone, Default)]
+pub struct CustomHandlerRegistry {
+    handlers: Arc<RwLock<HashMap<String, Arc<dyn CustomHandler>>>>,
+}
+
+impl CustomHandlerRegistry {
+    /// Registry holding the handlers that need no app handle.
+    pub fn with_builtins(clipboard: Arc<dyn ClipboardBackend>) -> Self {
+        let registry = Self::default();
+        registry.register(Arc::new(OpenUrlHandler));
+        registry.register(Arc::new(ClipboardSetHandler { clipboard }));
+        registry
+    }
+
+    /// Adds `handler`, replacing any handler registered under the same name.
+    pub fn register(&self, handler: Arc<dyn CustomHandler>) {
+        let name = handler.info().name;
+        self.handlers.write().insert(name, handler);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn CustomHandler>> {
+        self.handlers.read().get(name).cloned()
+    }
+
+    pub fn list(&self) -> Vec<CustomHandlerInfo> {
+        let mut infos: Vec<CustomHandlerInfo> = self
+            .handlers
+            .read()
+            .values()
+            .map(|handler| handler.info())
+            .collect();
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
+    }
+
+    /// Validates `params` against the handler's schema and runs it. Returns
+    /// `None` when no handler is registered under `name`.
+    pub async fn invoke(&self, name: &str, params: &Value) -> Option<HandlerResult> {
+        let handler = self.get(name)?;
+        let info = handler.info();
+        if let Err(reason) = check_params(&info.params_schema, params) {
+            return Some(Err(AppError::Message(format!(
+                "invalid params for custom handler '{name}': {reason}"
+            ))));
+        }
+        Some(handler.invoke(params).await)
+    }
+
+    /// Registers the external handlers declared in `custom-handlers.json`
+    /// under `base_dir`. Entries that clash with a built-in handler are
+    /// ignored. Returns the number of handlers added.
+    pub fn load_external(&self, base_dir: &Path) -> Result<usize, AppError> {
+        let path = base_dir.join(CUSTOM_HANDLERS_FILE_NAME);
+        if !path.exists() {
+            return Ok(0);
+        }
+        let raw = std::fs::read_to_string(&path)?;
+        let config: ExternalHandlersFile = serde_json::from_str(&raw)
+            .map_err(|err| AppError::Message(format!("invalid {}: {err}", path.display())))?;
+
+        let mut added = 0;
+        for definition in config.handlers {
+            let name = definition.name.trim().to_string();
+            if name.is_empty() || definition.command.trim().is_empty() {
+                eprintln!("custom handler entry without name or command ignored");
+                continue;
+            }
+            if let Some(existing) = self.get(&name) {
+                if existing.info().source == HandlerSource::Builtin {
+                    eprintln!("custom handler '{name}' shadows a built-in handler; ignored");
+                    continue;
+                }
+            }
+            self.register(Arc::new(ExternalHandler {
+                name,
+                definition,
+                base_dir: base_dir.to_path_buf(),
+            }));
+            added += 1;
+        }
+        Ok(added)
+    }
+}
+
+struct OpenUrlHandler;
+
+impl CustomHandler for OpenUrlHandler {
+    fn info(&self) -> CustomHandlerInfo {
+        CustomHandlerInfo {
+            name: "open_url".into(),
+            description: "Opens a web or mail link in the default application.".into(),
+            params_schema: json!({
+                "type": "object",
+                "required": ["url"],
+                "properties": {
+                    "url": { "type": "string", "description": "http, https or mailto URL" }
+                }
+            }),
+            source: HandlerSource::Builtin,
+        }
+    }
+
+    fn invoke<'a>(&'a self, params: &'a Value) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            let url = params["url"].as_str().unwrap_or_default().trim();
+            let scheme = url
+                .split_once(':')
+                .map(|(scheme, _)| scheme.to_ascii_lowercase());
+            if !matches!(scheme.as_deref(), Some("http" | "https" | "mailto")) {
+                return Err(AppError::Message(format!(
+                    "open_url only accepts http, https or mailto links, got '{url}'"
+                )));
+            }
+            tauri_plugin_opener::open_url(url, None::<&str>)
+                .map_err(|err| AppError::Message(format!("failed to open '{url}': {err}")))?;
+            Ok((ActionEventStatus::Success, None))
+        })
+    }
+}
+
+struct ClipboardSetHandler {
+    clipboard: Arc<dyn ClipboardBackend>,
+}
+
+impl CustomHandler for ClipboardSetHandler {
+    fn info(&self) -> CustomHandlerInfo {
+        CustomHandlerInfo {
+            name: "clipboard_set".into(),
+            description: "Replaces the clipboard contents with the given text.".into(),
+            params_schema: json!({
+                "type": "object",
+                "required": ["text"],
+                "properties": {
+                    "text": { "type": "string" }
+                }
+            }),
+            source: HandlerSource::Builtin,
+        }
+    }
+
+    fn invoke<'a>(&'a self, params: &'a Value) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            let text = params["text"].as_str().unwrap_or_default();
+            self.clipboard
+                .write_text(text)
+                .map_err(|err| AppError::Message(err.to_string()))?;
+            Ok((ActionEventStatus::Success, None))
+        })
+    }
+}
+
+/// Shows a desktop notification. Needs the app handle, so it is registered
+/// from `commands::init` rather than [`CustomHandlerRegistry::with_builtins`].
+pub struct NotifyHandler<R: Runtime> {
+    app: AppHandle<R>,
+}
+
+impl<R: Runtime> NotifyHandler<R> {
+    pub fn new(app: AppHandle<R>) -> Self {
+        Self { app }
+    }
+}
+
+impl<R: Runtime> CustomHandler for NotifyHandler<R> {
+    fn info(&self) -> CustomHandlerInfo {
+        CustomHandlerInfo {
+            name: "notify".into(),
+            description: "Shows a desktop notification.".into(),
+            params_schema: json!({
+                "type": "object",
+                "required": ["title"],
+                "properties": {
+                    "title": { "type": "string" },
+                    "body": { "type": "string" }
+                }
+            }),
+            source: HandlerSource::Builtin,
+        }
+    }
+
+    fn invoke<'a>(&'a self, params: &'a Value) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            let mut builder = self
+                .app
+                .notification()
+                .builder()
+                .title(params["title"].as_str().unwrap_or_default());
+            if let Some(body) = params["body"].as_str() {
+                builder = builder.body(body);
+            }
+            builder
+                .show()
+                .map_err(|err| AppError::Message(format!("failed to show notification: {err}")))?;
+            Ok((ActionEventStatus::Success, None))
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ExternalHandlersFile {
+    #[serde(default)]
+    handlers: Vec<ExternalHandlerDefinition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExternalHandlerDefinition {
+    name: String,
+    #[serde(default)]
+    description: String,
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    params_schema: Option<Value>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExternalResponse {
+    #[serde(default)]
+    status: Option<ActionEventStatus>,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+/// Runs a configured executable with `params` as JSON on stdin.
+///
+/// Exit code 0 means success. The handler may print
+/// `{"status": "skipped", "message": "..."}` to report another status;
+/// any other stdout is used as the event message. A non-zero exit fails
+/// the action with stderr as the reason.
+struct ExternalHandler {
+    name: String,
+    definition: ExternalHandlerDefinition,
+    base_dir: PathBuf,
+}
+
+impl ExternalHandler {
+    fn command_path(&self) -> PathBuf {
+        let command = self.definition.command.trim();
+        let path = PathBuf::from(command);
+        if path.is_absolute() || !(command.contains('/') || command.contains('\\')) {
+            path
+        } else {
+            self.base_dir.join(path)
+        }
+    }
+
+    async fn run(&self, params: &Value) -> HandlerResult {
+        let name = &self.name;
+        let mut command = Command::new(self.command_path());
+        command
+            .args(&self.definition.args)
+            .current_dir(&self.base_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let input = serde_json::to_vec(params).map_err(|err| AppError::Message(err.to_string()))?;
+
+        // Stdin is written while the output is read, so a handler that
+        // prints before reading cannot deadlock, and the timeout covers both.
+        // Timing out drops the run, which kills the handler's process tree.
+        let limit = Duration::from_millis(
+            self.definition
+                .timeout_ms
+                .unwrap_or(DEFAULT_EXTERNAL_TIMEOUT_MS),
+        );
+        let run = process_tree::collect_with_input(&mut command, &input, MAX_HANDLER_OUTPUT_BYTES);
+        let output = tokio::time::timeout(limit, run)
+            .await
+            .map_err(|_| {
+                AppError::Message(format!(
+                    "custom handler '{name}' timed out after {} ms",
+                    limit.as_millis()
+                ))
+            })?
+            .map_err(|err| AppError::Message(format!("custom handler '{name}' failed: {err}")))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            let reason = if stderr.is_empty() { stdout } else { stderr };
+            return Err(AppError::Message(format!(
+                "custom handler '{name}' exited with status {}: {reason}",
+                output.status
+            )));
+        }
+
+        if let Ok(response) = serde_json::from_str::<ExternalResponse>(&stdout) {
+            return Ok((
+                response.status.unwrap_or(ActionEventStatus::Success),
+                response.message,
+            ));
+        }
+        Ok((
+            ActionEventStatus::Success,
+            (!stdout.is_empty()).then_some(stdout),
+        ))
+    }
+}
+
+impl CustomHandler for ExternalHandler {
+    fn info(&self) -> CustomHandlerInfo {
+        CustomHandlerInfo {
+            name: self.name.clone(),
+            description: self.definition.description.clone(),
+            params_schema: self
+                .definition
+                .params_schema
+                .clone()
+                .unwrap_or_else(|| json!({ "type": "object" })),
+            source: HandlerSource::External,
+        }
+    }
+
+    fn invoke<'a>(&'a self, params: &'a Value) -> HandlerFuture<'a> {
+        Box::pin(self.run(params))
+    }
+}
+
+/// Checks the subset of JSON schema used by handler declarations: the
+/// top-level `type`, `required` keys and primitive property types.
+fn check_params(schema: &Value, params: &Value) -> Result<(), String> {
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        if !matches_type(expected, params) {
+            return Err(format!("expected {expected}"));
+        }
+    }
+    let Some(object) = params.as_object() else {
+        return Ok(());
+    };
+    if let Some(required) = schema.get("required").and_then(Value::as_array) {
+        for key in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(key) {
+                return Err(format!("missing required field '{key}'"));
+            }
+        }
+    }
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (key, property) in properties {
+            let (Some(value), Some(expected)) = (
+                object.get(key),
+                property.get("type").and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+            if !matches_type(expected, value) {
+                return Err(format!("field '{key}' must be a {expected}"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::clipboard::MemoryClipboard;
+
+    fn registry() -> (CustomHandlerRegistry, Arc<MemoryClipboard>) {
+        let clipboard = Arc::new(MemoryClipboard::default());
+        (
+            CustomHandlerRegistry::with_builtins(clipboard.clone()),
+            clipboard,
+        )
+    }
+
+    #[test]
+    fn lists_builtins_with_param_schemas() {
+        let (registry, _) = registry();
+        let infos = registry.list();
+        let names: Vec<&str> = infos.iter().map(|info| info.name.as_str()).collect();
+        assert_eq!(names, vec!["clipboard_set", "open_url"]);
+        assert_eq!(infos[1].params_schema["required"], json!(["url"]));
+        assert_eq!(infos[1].source, HandlerSource::Builtin);
+    }
+
+    #[tokio::test]
+    async fn clipboard_set_validates_and_writes_text() {
+        let (registry, clipboard) = registry();
+
+        let result = registry
+            .invoke("clipboard_set", &json!({ "text": 5 }))
+            .await
+            .expect("registered");
+        let err = result.expect_err("text must be a string");
+        assert!(err.to_string().contains("field 'text' must be a string"));
+        assert_eq!(clipboard.read_text().unwrap(), None);
+
+        let (status, _) = registry
+            .invoke("clipboard_set", &json!({ "text": "hello" }))
+            .await
+            .expect("registered")
+            .expect("clipboard write");
+        assert_eq!(status, ActionEventStatus::Success);
+        assert_eq!(clipboard.read_text().unwrap().as_deref(), Some("hello"));
+
+        assert!(registry.invoke("missing", &Value::Null).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn open_url_rejects_non_web_schemes() {
+        let (registry, _) = registry();
+        let err = registry
+            .invoke("open_url", &json!({ "url": "file:///etc/passwd" }))
+            .await
+            .expect("registered")
+            .expect_err("file urls are rejected");
+        assert!(err.to_string().contains("only accepts"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn loads_and_runs_external_handlers_from_config() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            dir.path().join(CUSTOM_HANDLERS_FILE_NAME),
+            r#"{
+                "handlers": [
+                    {
+                        "name": "echo_name",
+                        "description": "Echoes the name field",
+                        "command": "sh",
+                        "args": ["-c", "sed -n 's/.*\"name\":\"\\([^\"]*\\)\".*/\\1/p'"],
+                        "paramsSchema": { "type": "object", "required": ["name"] }
+                    },
+                    {
+                        "name": "skip",
+                        "command": "sh",
+                        "args": ["-c", "echo '{\"status\":\"skipped\",\"message\":\"nothing to do\"}'"]
+                    },
+                    { "name": "fail", "command": "sh", "args": ["-c", "echo broken >&2; exit 3"] },
+                    { "name": "clipboard_set", "command": "true" }
+                ]
+            }"#,
+        )
+        .expect("write config");
+
+        let (registry, _) = registry();
+        assert_eq!(registry.load_external(dir.path()).expect("load"), 3);
+        let clipboard_info = registry.get("clipboard_set").expect("builtin").info();
+        assert_eq!(clipboard_info.source, HandlerSource::Builtin);
+
+        let (status, message) = registry
+            .invoke("echo_name", &json!({ "name": "pie" }))
+            .await
+            .expect("registered")
+            .expect("run");
+        assert_eq!(status, ActionEventStatus::Success);
+        assert_eq!(message.as_deref(), Some("pie"));
+
+        let (status, message) = registry
+            .invoke("skip", &json!({}))
+            .await
+            .expect("registered")
+            .expect("run");
+        assert_eq!(status, ActionEventStatus::Skipped);
+        assert_eq!(message.as_deref(), Some("nothing to do"));
+
+        let err = registry
+            .invoke("fail", &json!({}))
+            .await
+            .expect("registered")
+            .expect_err("non-zero exit");
+        assert!(err.to_string().contains("broken"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn large_params_cannot_block_handlers_past_their_timeout() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            dir.path().join(CUSTOM_HANDLERS_FILE_NAME),
+            r#"{
+                "handlers": [
+                    { "name": "deaf", "command": "sh", "args": ["-c", "sleep 30"], "timeoutMs": 300 },
+                    {
+                        "name": "chatty",
+                        "command": "sh",
+                        "args": ["-c", "head -c 1000000 /dev/zero | tr '\\0' x; cat > /dev/null"]
+                    }
+                ]
+            }"#,
+        )
+        .expect("write config");
+        let (registry, _) = registry();
+        registry.load_external(dir.path()).expect("load");
+        // Well past the size of a pipe buffer.
+        let params = json!({ "text": "p".repeat(1 << 20) });
+
+        let started = std::time::Instant::now();
+        let err = registry
+            .invoke("deaf", &params)
+            .await
+            .expect("registered")
+            .expect_err("never reads stdin");
+        assert!(err.to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let (status, message) = registry
+            .invoke("chatty", &params)
+            .await
+            .expect("registered")
+            .expect("writes before reading");
+        assert_eq!(status, ActionEventStatus::Success);
+        assert_eq!(
+            message.map(|message| message.len()),
+            Some(MAX_HANDLER_OUTPUT_BYTES)
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! Append-only record of every action event, kept across restarts.
+//!
+//! Events are stored one JSON object per line in
+//! `<data dir>/history/executions.jsonl`. Unlike the `ACTION` lines of the
+//! audit log they are never rotated, so queries see the full history.
+
+use crate::domain::profile::ProfileId;
+use crate::domain::{ActionEventPayload, ActionEventStatus, ActionId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+const HISTORY_DIR: &str = "history";
+const HISTORY_FILE: &str = "executions.jsonl";
+const DEFAULT_QUERY_LIMIT: usize = 200;
+
+/// Filters for [`ExecutionHistory::query`]. Unset fields match everything;
+/// times are Unix milliseconds, `from_ms` inclusive and `to_ms` exclusive.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryQuery {
+    #[serde(default)]
+    pub action_id: Option<ActionId>,
+    #[serde(default)]
+    pub statuses: Option<Vec<ActionEventStatus>>,
+    #[serde(default)]
+    pub profile_id: Option<ProfileId>,
+    #[serde(default)]
+    pub invocation_id: Option<Uuid>,
+    #[serde(default)]
+    pub from_ms: Option<i64>,
+    #[serde(default)]
+    pub to_ms: Option<i64>,
+    /// Most events returned, newest first. Aggregates cover every match.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    fn matches(&self, event: &ActionEventPayload) -> bool {
+        let timestamp_ms = (event.timestamp.unix_timestamp_nanos() / 1_000_000) as i64;
+        self.action_id.is_none_or(|id| event.id == id)
+            && self
+                .statuses
+                .as_ref()
+                .is_none_or(|statuses| statuses.contains(&event.status))
+            && self
+                .profile_id
+                .is_none_or(|id| event.profile_id == Some(id))
+            && self
+                .invocation_id
+                .is_none_or(|id| event.invocation_id == Some(id))
+            && self.from_ms.is_none_or(|from| timestamp_ms >= from)
+            && self.to_ms.is_none_or(|to| timestamp_ms < to)
+    }
+}
+
+/// Aggregates for one action (or macro step) over the matching events.
+/// Every event counts as a run, including the attempts of retried runs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionStats {
+    pub action_id: ActionId,
+    /// Name from the newest matching event.
+    pub name: String,
+    pub runs: usize,
+    /// Runs that ended with `failure` or `timed_out`.
+    pub failures: usize,
+    pub failure_rate: f64,
+    pub p50_duration_ms: u32,
+    pub p95_duration_ms: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryReport {
+    pub events: Vec<ActionEventPayload>,
+    /// Matching events beyond `limit` that were left out of `events`.
+    pub truncated: bool,
+    /// One entry per action, slowest p95 first.
+    pub stats: Vec<ActionStats>,
+}
+
+#[derive(Clone)]
+pub struct ExecutionHistory {
+    path: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl ExecutionHistory {
+    /// History stored under `data_dir`. The file is created on the first
+    /// append.
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join(HISTORY_DIR).join(HISTORY_FILE),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, event: &ActionEventPayload) -> io::Result<()> {
+        let mut line = serde_json::to_string(event)
+            .map_err(|err| io::Error::other(format!("failed to serialize action event: {err}")))?;
+        line.push('\n');
+
+        let _guard = self
+            .lock
+            .lock()
+            .map_err(|_| io::Error::other("execution history poisoned"))?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+        // Start on a fresh line if a crash left the previous one unfinished.
+        if !ends_with_newline(&mut file)? {
+            line.insert(0, '\n');
+        }
+        file.write_all(line.as_bytes())
+    }
+
+    /// Matching events, newest first, with per-action aggregates. Lines that
+    /// do not parse, e.g. one cut short by a crash, are skipped.
+    pub fn query(&self, query: &HistoryQuery) -> io::Result<HistoryReport> {
+        let file = {
+            let _guard = self
+                .lock
+                .lock()
+                .map_err(|_| io::Error::other("execution history poisoned"))?;
+            match File::open(&self.path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    return Ok(HistoryReport {
+                        events: Vec::new(),
+                        truncated: false,
+                        stats: Vec::new(),
+                    })
+                }
+                Err(err) => return Err(err),
+            }
+        };
+
+        let mut matched: Vec<ActionEventPayload> = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<ActionEventPayload>(&line).ok())
+            .filter(|event| query.matches(event))
+            .collect();
+        let stats = aggregate(&matched);
+
+        matched.reverse();
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+        let truncated = matched.len() > limit;
+        matched.truncate(limit);
+        Ok(HistoryReport {
+            events: matched,
+            truncated,
+            stats,
+        })
+    }
+}
+
+fn ends_with_newline(file: &mut File) -> io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+/// `events` must be oldest first.
+fn aggregate(events: &[ActionEventPayload]) -> Vec<ActionStats> {
+    let mut grouped: HashMap<ActionId, (String, usize, Vec<u32>)> = HashMap::new();
+    for event in events {
+        let entry = grouped
+            .entry(event.id)
+            .or_insert_with(|| (String::new(), 0, Vec::new()));
+        entry.0.clone_from(&event.name);
+        if matches!(
+            event.status,
+            ActionEventStatus::Failure | ActionEventStatus::TimedOut
+        ) {
+            entry.1 += 1;
+        }
+        entry.2.push(event.duration_ms);
+    }
+
+    let mut stats: Vec<ActionStats> = grouped
+        .into_iter()
+        .map(|(action_id, (name, failures, mut durations))| {
+            durations.sort_unstable();
+            let runs = durations.len();
+            ActionStats {
+                action_id,
+                name,
+                runs,
+                failures,
+                failure_rate: failures as f64 / runs as f64,
+                p50_duration_ms: percentile(&durations, 50),
+                p95_duration_ms: percentile(&durations, 95),
+            }
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.p95_duration_ms
+            .cmp(&a.p95_duration_ms)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    stats
+}
+
+/// Nearest-rank percentile of sorted, non-empty `values`.
+fn percentile(values: &[u32], percent: usize) -> u32 {
+    let rank = (values.len() * percent).div_ceil(100).max(1);
+    values[rank - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use time::OffsetDateTime;
+
+    fn event(
+        id: ActionId,
+        status: ActionEventStatus,
+        duration_ms: u32,
+        profile_id: Option<ProfileId>,
+    ) -> ActionEventPayload {
+        ActionEventPayload::new(
+            id,
+            "Slice",
+            status,
+            duration_ms,
+            None,
+            OffsetDateTime::now_utc(),
+            Some(Uuid::new_v4()),
+        )
+        .with_profile(profile_id)
+    }
+
+    #[test]
+    fn query_filters_and_aggregates_persisted_events() {
+        let dir = tempdir().expect("tempdir");
+        let history = ExecutionHistory::new(dir.path());
+        let slow = ActionId::new();
+        let fast = ActionId::new();
+        let profile = ProfileId::new();
+
+        for duration in [100, 200, 300, 400] {
+            history
+                .append(&event(
+                    slow,
+                    ActionEventStatus::Success,
+                    duration,
+                    Some(profile),
+                ))
+                .expect("append");
+        }
+        history
+            .append(&event(slow, ActionEventStatus::Failure, 5000, None))
+            .expect("append");
+        fs::OpenOptions::new()
+            .append(true)
+            .open(history.path())
+            .and_then(|mut file| file.write_all(b"{\"truncated"))
+            .expect("write partial line");
+        history
+            .append(&event(fast, ActionEventStatus::Success, 5, Some(profile)))
+            .expect("append after partial line");
+
+        // A fresh handle reads what the previous one wrote.
+        let history = ExecutionHistory::new(dir.path());
+        let report = history.query(&HistoryQuery::default()).expect("query");
+        assert_eq!(report.events.len(), 6);
+        assert_eq!(report.events[0].id, fast);
+        assert_eq!(report.stats[0].action_id, slow);
+        assert_eq!(report.stats[0].runs, 5);
+        assert_eq!(report.stats[0].failures, 1);
+        assert!((report.stats[0].failure_rate - 0.2).abs() < f64::EPSILON);
+        assert_eq!(report.stats[0].p50_duration_ms, 300);
+        assert_eq!(report.stats[0].p95_duration_ms, 5000);
+
+        let report = history
+            .query(&HistoryQuery {
+                profile_id: Some(profile),
+                statuses: Some(vec![ActionEventStatus::Success]),
+                limit: Some(2),
+                ..HistoryQuery::default()
+            })
+            .expect("filtered query");
+        assert_eq!(report.events.len(), 2);
+        assert!(report.truncated);
+        assert_eq!(
+            report.stats.iter().map(|stats| stats.runs).sum::<usize>(),
+            5
+        );
+
+        let report = history
+            .query(&HistoryQuery {
+                action_id: Some(fast),
+                to_ms: Some(0),
+                ..HistoryQuery::default()
+            })
+            .expect("time range query");
+        assert!(report.events.is_empty());
+    }
+}
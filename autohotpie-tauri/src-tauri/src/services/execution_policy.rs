@@ -0,0 +1,633 @@
+//! Checks applied to an action before it is handed to the runner.
+//!
+//! The policy is read from `actions.security` in the settings:
+//!
+//! ```json
+//! { "payloads": { "system_command": { "enabled": false },
+//!                 "run_script": { "requireConfirmation": true } },
+//!   "allowedExecutables": ["notepad", "C:\\Tools\\sync.exe"],
+//!   "allowedInterpreters": ["python", "powershell"] }
+//! ```
+//!
+//! Payload kinds without an entry are enabled and run without asking. A
+//! `null` allowlist allows everything. Executable entries without a path
+//! separator match programs launched by bare name (with or without their
+//! extension); entries with one match that exact path. While an allowlist
+//! is set, launches by bare name may not change `PATH`. Interpreter entries
+//! are language ids or aliases from the interpreter registry.
+//!
+//! Composite children and macro steps are checked as well: a macro `launch`
+//! step counts as `launch_program` and a `script` step as `run_script`, as
+//! does a `focus_or_launch` window payload, which may launch its program.
+//! `type_text` payloads press keys and also count as `send_keys`.
+//! Opening an executable file launches it, so an `open` payload whose
+//! target is one counts as `launch_program` and is held to the executable
+//! allowlist: before the run when the target is a fixed absolute path, and
+//! again once the runner has resolved it.
+//! Actions marked `untrusted` always need confirmation.
+
+use crate::domain::{
+    Action, ActionId, ActionPayload, LaunchEnvironment, MacroStepKind, PayloadKind, WindowOperation,
+};
+use crate::models::Settings;
+use crate::services::action_runner::ActionProvider;
+use crate::services::interpreters::InterpreterRegistry;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadRule {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub require_confirmation: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for PayloadRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            require_confirmation: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionPolicy {
+    #[serde(default)]
+    pub payloads: HashMap<PayloadKind, PayloadRule>,
+    #[serde(default)]
+    pub allowed_executables: Option<Vec<String>>,
+    #[serde(default)]
+    pub allowed_interpreters: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyVerdict {
+    Allow,
+    /// Runs once the user confirms it.
+    Confirm,
+    Deny,
+}
+
+/// Outcome of checking an action. `reasons` explains a `deny` or `confirm`
+/// verdict and is empty for `allow`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyDecision {
+    pub verdict: PolicyVerdict,
+    pub reasons: Vec<String>,
+}
+
+impl ExecutionPolicy {
+    /// Policy from `actions.security`; an invalid entry is reported and the
+    /// permissive default used.
+    pub fn from_settings(settings: &Settings) -> Self {
+        match settings.execution_policy_entry() {
+            Some(entry) => serde_json::from_value(entry.clone()).unwrap_or_else(|err| {
+                eprintln!("ignoring invalid execution policy: {err}");
+                Self::default()
+            }),
+            None => Self::default(),
+        }
+    }
+
+    /// Checks `action` and everything it reaches through `provider`.
+    pub fn evaluate<P>(
+        &self,
+        action: &Action,
+        provider: &P,
+        interpreters: &InterpreterRegistry,
+    ) -> PolicyDecision
+    where
+        P: ActionProvider + ?Sized,
+    {
+        let mut check = Check {
+            policy: self,
+            interpreters,
+            denied: Vec::new(),
+            confirm: Vec::new(),
+        };
+        let mut visited = HashSet::new();
+        check.action(action, provider, &mut visited);
+
+        if !check.denied.is_empty() {
+            PolicyDecision {
+                verdict: PolicyVerdict::Deny,
+                reasons: check.denied,
+            }
+        } else if !check.confirm.is_empty() {
+            PolicyDecision {
+                verdict: PolicyVerdict::Confirm,
+                reasons: check.confirm,
+            }
+        } else {
+            PolicyDecision {
+                verdict: PolicyVerdict::Allow,
+                reasons: Vec::new(),
+            }
+        }
+    }
+
+    /// Why `path`, the resolved target of an `open` payload, must not be
+    /// opened, or `None` when it may. Only executable files are refused.
+    pub fn check_open_path(&self, path: &Path) -> Option<String> {
+        if !is_executable_file(path) {
+            return None;
+        }
+        let shown = path.display().to_string();
+        if !self.rule(PayloadKind::LaunchProgram).enabled {
+            return Some(format!(
+                "'{shown}' is a program and {} payloads are disabled",
+                PayloadKind::LaunchProgram
+            ));
+        }
+        let allowed = self.allowed_executables.as_ref()?;
+        (!allowed
+            .iter()
+            .any(|entry| executable_matches(entry, &shown)))
+        .then(|| format!("executable '{shown}' is not allowed"))
+    }
+
+    fn rule(&self, kind: PayloadKind) -> PayloadRule {
+        self.payloads.get(&kind).copied().unwrap_or_default()
+    }
+}
+
+struct Check<'a> {
+    policy: &'a ExecutionPolicy,
+    interpreters: &'a InterpreterRegistry,
+    denied: Vec<String>,
+    confirm: Vec<String>,
+}
+
+impl Check<'_> {
+    fn action<P>(&mut self, action: &Action, provider: &P, visited: &mut HashSet<ActionId>)
+    where
+        P: ActionProvider + ?Sized,
+    {
+        if !visited.insert(action.id) {
+            return;
+        }
+        let name = action.name.as_str();
+        if action.untrusted {
+            push_unique(
+                &mut self.confirm,
+                format!("'{name}' was imported and has not been reviewed"),
+            );
+        }
+        self.kind(name, action.payload.kind());
+
+        match &action.payload {
+            ActionPayload::LaunchProgram {
+                executable,
+                options,
+                ..
+            } => {
+                self.executable(name, executable);
+                self.search_path(name, executable, &options.env);
+            }
+            ActionPayload::RunScript { language, .. } => self.interpreter(name, language),
+            ActionPayload::TypeText { .. } => self.kind(name, PayloadKind::SendKeys),
+            ActionPayload::Open { target } => match fixed_open_path(target) {
+                Some(path) if is_executable_file(&path) => {
+                    self.kind(name, PayloadKind::LaunchProgram);
+                    self.executable(name, &path.to_string_lossy());
+                }
+                _ => {}
+            },
+            ActionPayload::Window {
+                operation:
+                    WindowOperation::FocusOrLaunch {
+                        process_name,
+                        executable,
+                        ..
+                    },
+            } => {
+                self.kind(name, PayloadKind::LaunchProgram);
+                self.executable(name, executable.as_deref().unwrap_or(process_name));
+            }
+            ActionPayload::Macro { steps } => {
+                for step in steps {
+                    match &step.kind {
+                        MacroStepKind::Launch { app_path, .. } => {
+                            self.kind(name, PayloadKind::LaunchProgram);
+                            self.executable(name, app_path);
+                        }
+                        MacroStepKind::Script { language, .. } => {
+                            self.kind(name, PayloadKind::RunScript);
+                            self.interpreter(name, language);
+                        }
+                        MacroStepKind::Keys { .. } => self.kind(name, PayloadKind::SendKeys),
+                        MacroStepKind::Delay { .. } => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // Missing children are left for the runner to report.
+        for child in action.referenced_actions() {
+            if let Some(child) = provider.get_action(&child) {
+                self.action(&child, provider, visited);
+            }
+        }
+    }
+
+    fn kind(&mut self, name: &str, kind: PayloadKind) {
+        let rule = self.policy.rule(kind);
+        if !rule.enabled {
+            push_unique(
+                &mut self.denied,
+                format!("{kind} payloads are disabled ('{name}')"),
+            );
+        } else if rule.require_confirmation {
+            push_unique(
+                &mut self.confirm,
+                format!("{kind} payloads require confirmation ('{name}')"),
+            );
+        }
+    }
+
+    fn executable(&mut self, name: &str, executable: &str) {
+        let Some(allowed) = &self.policy.allowed_executables else {
+            return;
+        };
+        if !allowed
+            .iter()
+            .any(|entry| executable_matches(entry, executable))
+        {
+            push_unique(
+                &mut self.denied,
+                format!("executable '{executable}' is not allowed ('{name}')"),
+            );
+        }
+    }
+
+    /// Bare names are looked up on the `PATH` the program gets, so a launch
+    /// that changes it could run any binary with an allowed name.
+    fn search_path(&mut self, name: &str, executable: &str, env: &LaunchEnvironment) {
+        if self.policy.allowed_executables.is_none()
+            || executable.contains(['/', '\\'])
+            || !changes_path(env)
+        {
+            return;
+        }
+        push_unique(
+            &mut self.denied,
+            format!(
+                "executable '{}' is looked up on a changed PATH ('{name}')",
+                executable.trim()
+            ),
+        );
+    }
+
+    fn interpreter(&mut self, name: &str, language: &str) {
+        let Some(allowed) = &self.policy.allowed_interpreters else {
+            return;
+        };
+        let canonical = self
+            .interpreters
+            .get(language)
+            .map(|config| config.language.as_str());
+        let permitted = allowed.iter().any(|entry| {
+            let entry = entry.trim();
+            entry.eq_ignore_ascii_case(language.trim())
+                || canonical.is_some_and(|canonical| entry.eq_ignore_ascii_case(canonical))
+        });
+        if !permitted {
+            push_unique(
+                &mut self.denied,
+                format!(
+                    "script language '{}' is not allowed ('{name}')",
+                    language.trim()
+                ),
+            );
+        }
+    }
+}
+
+fn executable_matches(entry: &str, executable: &str) -> bool {
+    let entry = entry.trim();
+    let executable = executable.trim();
+    if entry.is_empty() {
+        return false;
+    }
+    let has_separator = |value: &str| value.contains('/') || value.contains('\\');
+    if has_separator(entry) || has_separator(executable) {
+        let normalize = |value: &str| value.replace('\\', "/");
+        return if cfg!(windows) {
+            normalize(entry).eq_ignore_ascii_case(&normalize(executable))
+        } else {
+            normalize(entry) == normalize(executable)
+        };
+    }
+    entry.eq_ignore_ascii_case(executable)
+        || Path::new(executable)
+            .file_stem()
+            .is_some_and(|stem| entry.eq_ignore_ascii_case(&stem.to_string_lossy()))
+}
+
+fn changes_path(env: &LaunchEnvironment) -> bool {
+    let is_path = |variable: &String| variable.eq_ignore_ascii_case("PATH");
+    !env.path_prepend.is_empty() || env.set.keys().any(is_path) || env.unset.iter().any(is_path)
+}
+
+/// The file an `open` target points at when that is known before the run:
+/// an absolute path or `file://` URL without placeholders. Relative targets
+/// depend on the data directory and are checked by the runner.
+fn fixed_open_path(target: &str) -> Option<PathBuf> {
+    let target = target.trim();
+    if target.contains('{') {
+        return None;
+    }
+    if let Ok(url) = reqwest::Url::parse(target) {
+        // A one-letter scheme is a Windows drive letter.
+        if url.scheme().len() > 1 {
+            return url.to_file_path().ok();
+        }
+    }
+    Some(PathBuf::from(target)).filter(|path| path.is_absolute())
+}
+
+/// Files the system opener would run rather than show: programs and
+/// scripts by extension on Windows; `.desktop` entries and files with an
+/// execute bit elsewhere.
+fn is_executable_file(path: &Path) -> bool {
+    let Ok(metadata) = path.metadata() else {
+        return false;
+    };
+    if !metadata.is_file() {
+        return false;
+    }
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    #[cfg(windows)]
+    {
+        matches!(
+            extension.as_str(),
+            "exe" | "com" | "bat" | "cmd" | "msi" | "lnk" | "scr" | "ps1" | "vbs" | "js" | "wsf"
+        )
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        extension == "desktop" || metadata.permissions().mode() & 0o111 != 0
+    }
+}
+
+fn push_unique(reasons: &mut Vec<String>, reason: String) {
+    if !reasons.contains(&reason) {
+        reasons.push(reason);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::action::MacroStepDefinition;
+    use crate::domain::{CompositeStep, LaunchOptions};
+    use serde_json::json;
+
+    struct Library(Vec<Action>);
+
+    impl ActionProvider for Library {
+        fn get_action(&self, id: &ActionId) -> Option<Action> {
+            self.0.iter().find(|action| action.id == *id).cloned()
+        }
+    }
+
+    fn policy(value: serde_json::Value) -> ExecutionPolicy {
+        let mut settings = Settings::default();
+        settings.global["actions"]["security"] = value;
+        ExecutionPolicy::from_settings(&settings)
+    }
+
+    fn launch(executable: &str) -> Action {
+        Action::new(
+            "Launch",
+            ActionPayload::LaunchProgram {
+                executable: executable.into(),
+                arguments: Vec::new(),
+                working_dir: None,
+                options: LaunchOptions::default(),
+            },
+        )
+    }
+
+    #[test]
+    fn default_policy_allows_everything() {
+        let decision = ExecutionPolicy::from_settings(&Settings::default()).evaluate(
+            &launch("anything"),
+            &Library(Vec::new()),
+            &InterpreterRegistry::default(),
+        );
+        assert_eq!(decision.verdict, PolicyVerdict::Allow);
+        assert!(decision.reasons.is_empty());
+    }
+
+    #[test]
+    fn composite_children_and_macro_steps_are_checked() {
+        let policy = policy(json!({
+            "payloads": { "system_command": { "enabled": false },
+                          "send_keys": { "requireConfirmation": true } },
+            "allowedExecutables": ["notepad", "/usr/bin/gedit"],
+            "allowedInterpreters": ["node"]
+        }));
+        let interpreters = InterpreterRegistry::default();
+        let library = Library(Vec::new());
+
+        assert_eq!(
+            policy
+                .evaluate(&launch("notepad.exe"), &library, &interpreters)
+                .verdict,
+            PolicyVerdict::Allow
+        );
+        assert_eq!(
+            policy
+                .evaluate(&launch("/usr/bin/gedit"), &library, &interpreters)
+                .verdict,
+            PolicyVerdict::Allow
+        );
+        assert_eq!(
+            policy
+                .evaluate(&launch("/tmp/notepad"), &library, &interpreters)
+                .verdict,
+            PolicyVerdict::Deny
+        );
+
+        let script = Action::new(
+            "Script",
+            ActionPayload::RunScript {
+                language: "js".into(),
+                script: "1".into(),
+            },
+        );
+        assert_eq!(
+            policy.evaluate(&script, &library, &interpreters).verdict,
+            PolicyVerdict::Allow
+        );
+
+        let keys_macro = Action::new(
+            "Macro",
+            ActionPayload::Macro {
+                steps: vec![MacroStepDefinition {
+                    id: ActionId::new(),
+                    order: 0,
+                    kind: MacroStepKind::Keys {
+                        keys: "a".into(),
+                        repeat: 1,
+                    },
+                    retry: None,
+                    note: None,
+                }],
+            },
+        );
+        let decision = policy.evaluate(&keys_macro, &library, &interpreters);
+        assert_eq!(decision.verdict, PolicyVerdict::Confirm);
+
+        let command = Action::new(
+            "Command",
+            ActionPayload::SystemCommand {
+                command: "true".into(),
+            },
+        );
+        let composite = Action::new(
+            "Composite",
+            ActionPayload::Composite {
+                actions: vec![
+                    CompositeStep::from(keys_macro.id),
+                    CompositeStep::from(command.id),
+                ],
+            },
+        );
+        let library = Library(vec![keys_macro, command]);
+        let decision = policy.evaluate(&composite, &library, &interpreters);
+        assert_eq!(decision.verdict, PolicyVerdict::Deny);
+        assert_eq!(
+            decision.reasons,
+            ["system_command payloads are disabled ('Command')"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn open_targets_that_are_programs_follow_the_executable_rules() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let script = dir.path().join("payload.sh");
+        std::fs::write(&script, "#!/bin/sh\n").expect("write script");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod script");
+        let notes = dir.path().join("notes.txt");
+        std::fs::write(&notes, "hello").expect("write notes");
+        let open = |target: String| Action::new("Open", ActionPayload::Open { target });
+
+        let restricted = policy(json!({ "allowedExecutables": ["notepad"] }));
+        let interpreters = InterpreterRegistry::default();
+        let library = Library(Vec::new());
+        let decision = restricted.evaluate(
+            &open(format!("file://{}", script.display())),
+            &library,
+            &interpreters,
+        );
+        assert_eq!(decision.verdict, PolicyVerdict::Deny);
+        assert!(restricted.check_open_path(&script).is_some());
+        assert_eq!(
+            restricted
+                .evaluate(&open(notes.display().to_string()), &library, &interpreters)
+                .verdict,
+            PolicyVerdict::Allow
+        );
+        assert!(restricted.check_open_path(&notes).is_none());
+
+        let allowed = policy(json!({
+            "allowedExecutables": [script.display().to_string()]
+        }));
+        assert!(allowed.check_open_path(&script).is_none());
+        let disabled = policy(json!({
+            "payloads": { "launch_program": { "enabled": false } }
+        }));
+        assert!(disabled.check_open_path(&script).is_some());
+    }
+
+    #[test]
+    fn bare_names_may_not_change_path_under_an_allowlist() {
+        let with_env = |executable: &str, env: LaunchEnvironment| {
+            let mut action = launch(executable);
+            if let ActionPayload::LaunchProgram { options, .. } = &mut action.payload {
+                options.env = env;
+            }
+            action
+        };
+        let prepend = LaunchEnvironment {
+            path_prepend: vec!["/tmp/evil".into()],
+            ..LaunchEnvironment::default()
+        };
+        let set_path = LaunchEnvironment {
+            set: [("PATH".to_string(), "/tmp/evil".to_string())].into(),
+            ..LaunchEnvironment::default()
+        };
+        let other_variable = LaunchEnvironment {
+            set: [("LANG".to_string(), "C".to_string())].into(),
+            ..LaunchEnvironment::default()
+        };
+        let restricted = policy(json!({ "allowedExecutables": ["notepad", "/usr/bin/gedit"] }));
+        let interpreters = InterpreterRegistry::default();
+        let library = Library(Vec::new());
+        let verdict = |policy: &ExecutionPolicy, action: &Action| {
+            policy.evaluate(action, &library, &interpreters).verdict
+        };
+
+        let decision = restricted.evaluate(
+            &with_env("notepad", prepend.clone()),
+            &library,
+            &interpreters,
+        );
+        assert_eq!(decision.verdict, PolicyVerdict::Deny);
+        assert_eq!(
+            decision.reasons,
+            ["executable 'notepad' is looked up on a changed PATH ('Launch')"]
+        );
+        assert_eq!(
+            verdict(&restricted, &with_env("notepad", set_path)),
+            PolicyVerdict::Deny
+        );
+        assert_eq!(
+            verdict(&restricted, &with_env("notepad", other_variable)),
+            PolicyVerdict::Allow
+        );
+        assert_eq!(
+            verdict(&restricted, &with_env("/usr/bin/gedit", prepend.clone())),
+            PolicyVerdict::Allow
+        );
+        assert_eq!(
+            verdict(&policy(json!({})), &with_env("notepad", prepend)),
+            PolicyVerdict::Allow
+        );
+    }
+
+    #[test]
+    fn untrusted_actions_need_confirmation() {
+        let mut action = launch("notepad");
+        action.untrusted = true;
+        let decision = ExecutionPolicy::default().evaluate(
+            &action,
+            &Library(Vec::new()),
+            &InterpreterRegistry::default(),
+        );
+        assert_eq!(decision.verdict, PolicyVerdict::Confirm);
+        assert_eq!(decision.reasons.len(), 1);
+    }
+}
@@ -0,0 +1,131 @@
+//! Sending `http_request` payloads once their templates are rendered.
+
+use crate::services::templating::Escaping;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Client, Method};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Headers whose values are hidden in plans.
+const SECRET_HEADERS: &[&str] = &["authorization", "proxy-authorization", "cookie"];
+
+/// A request with every placeholder resolved.
+#[derive(Debug, Clone)]
+pub struct PreparedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<String>,
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    /// At most the `limit` passed to [`send`]; the rest is not read.
+    pub body: Vec<u8>,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum HttpRequestError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error("request timed out")]
+    TimedOut,
+    #[error("{0}")]
+    Transport(String),
+}
+
+/// Escaping for placeholders in the body, chosen from its content type so a
+/// value cannot break out of a JSON string or form field.
+pub fn body_escaping(headers: &BTreeMap<String, String>) -> Escaping {
+    let content_type = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()))
+        .map(|(_, value)| value.to_ascii_lowercase())
+        .unwrap_or_default();
+    if content_type.contains("json") {
+        Escaping::Json
+    } else if content_type.contains("x-www-form-urlencoded") {
+        Escaping::Url
+    } else {
+        Escaping::Raw
+    }
+}
+
+pub fn is_secret_header(name: &str) -> bool {
+    SECRET_HEADERS
+        .iter()
+        .any(|secret| name.trim().eq_ignore_ascii_case(secret))
+}
+
+/// Header map for plans, with credentials replaced by `***`.
+pub fn redacted_headers(headers: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_secret_header(name) {
+                "***".into()
+            } else {
+                value.clone()
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+pub async fn send(
+    client: &Client,
+    request: &PreparedRequest,
+    limit: usize,
+) -> Result<HttpResponse, HttpRequestError> {
+    let method = Method::from_bytes(request.method.trim().to_ascii_uppercase().as_bytes())
+        .map_err(|_| HttpRequestError::Invalid(format!("invalid method '{}'", request.method)))?;
+    let mut headers = HeaderMap::new();
+    for (name, value) in &request.headers {
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| HttpRequestError::Invalid(format!("invalid header name '{name}'")))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| HttpRequestError::Invalid(format!("invalid value for header '{name}'")))?;
+        headers.append(name, value);
+    }
+
+    let mut builder = client.request(method, request.url.trim()).headers(headers);
+    if let Some(body) = &request.body {
+        builder = builder.body(body.clone());
+    }
+    if let Some(timeout) = request.timeout {
+        builder = builder.timeout(timeout);
+    }
+
+    let mut response = builder.send().await.map_err(transport_error)?;
+    let status = response.status().as_u16();
+    let mut body = Vec::new();
+    let mut truncated = false;
+    while let Some(chunk) = response.chunk().await.map_err(transport_error)? {
+        let room = limit - body.len();
+        if chunk.len() > room {
+            body.extend_from_slice(&chunk[..room]);
+            truncated = true;
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(HttpResponse {
+        status,
+        body,
+        truncated,
+    })
+}
+
+fn transport_error(err: reqwest::Error) -> HttpRequestError {
+    if err.is_timeout() {
+        HttpRequestError::TimedOut
+    } else if err.is_builder() {
+        HttpRequestError::Invalid(err.to_string())
+    } else {
+        HttpRequestError::Transport(err.to_string())
+    }
+}
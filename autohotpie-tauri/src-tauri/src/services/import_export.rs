@@ -3,7 +3,7 @@ use crate::domain::profile::ProfileId;
 use crate::domain::validation::validate_profile;
 use crate::models::Settings;
 use crate::services::audit_log::AuditLogger;
-use crate::storage::profile_repository::{ProfileRecord, ProfileStore, PROFILES_SCHEMA_VERSION};
+use crate::storage::profile_repository::{ProfileStore, PROFILES_SCHEMA_VERSION};
 use crate::storage::StorageManager;
 use base64::{engine::general_purpose, Engine as _};
 use serde::{Deserialize, Serialize};
@@ -64,7 +64,7 @@ impl<'a> ImportExportService<'a> {
     ) -> Result<ImportExportBundle, AppError> {
         let mut export_store = self.filter_profiles(store, filter);
         self.ensure_active_profile(&mut export_store);
-        self.validate_profile_records(&export_store.profiles)?;
+        self.validate_profile_records(&export_store)?;
 
         let icons = self.read_icon_files()?;
         let exported_at = OffsetDateTime::now_utc().to_string();
@@ -111,10 +111,14 @@ impl<'a> ImportExportService<'a> {
             )));
         }
 
-        self.validate_profile_records(&bundle.profiles.profiles)?;
+        self.validate_profile_records(&bundle.profiles)?;
         let mut normalized_store = bundle.profiles.clone();
         normalized_store.schema_version = PROFILES_SCHEMA_VERSION;
         self.ensure_active_profile(&mut normalized_store);
+        // Imported payloads run only after confirmation until reviewed.
+        for action in normalized_store.all_actions_mut() {
+            action.untrusted = true;
+        }
 
         let mut warnings = Vec::new();
         warnings.extend(self.write_icon_files(&bundle.icons)?);
@@ -175,12 +179,14 @@ impl<'a> ImportExportService<'a> {
         store.active_profile_id = store.profiles.first().map(|record| record.profile.id);
     }
 
-    fn validate_profile_records(&self, records: &[ProfileRecord]) -> Result<(), AppError> {
+    fn validate_profile_records(&self, store: &ProfileStore) -> Result<(), AppError> {
         let mut errors = Vec::new();
-        for record in records {
-            if let Err(mut validation_errors) =
-                validate_profile(&record.profile, &record.menus, &record.actions)
-            {
+        for record in &store.profiles {
+            if let Err(mut validation_errors) = validate_profile(
+                &record.profile,
+                &record.menus,
+                store.actions_in_scope(record),
+            ) {
                 errors.append(&mut validation_errors);
             }
         }
@@ -0,0 +1,422 @@
+//! Interpreters used by `RunScript` payloads and macro `script` steps.
+//!
+//! The built-in entries reproduce the languages the runner has always
+//! supported. Entries under `actions.interpreters` in the settings add new
+//! languages or replace a built-in one with the same id, e.g.
+//!
+//! ```json
+//! { "language": "python", "executable": "/home/me/venv/bin/python",
+//!   "args": ["{file}"], "mode": "file", "extension": ".py", "escaping": "python" }
+//! ```
+//!
+//! `{script}` in `args` is replaced with the script text and `{file}` with
+//! the path of a temporary file holding it. If neither appears, the script
+//! (inline mode) or the file path (file mode) is appended as the last
+//! argument.
+
+use crate::commands::AppError;
+use crate::models::Settings;
+use crate::services::process_tree;
+use crate::services::templating::Escaping;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptMode {
+    /// The script is passed on the command line.
+    #[default]
+    Inline,
+    /// The script is written to a temporary file first.
+    File,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InterpreterSource {
+    Builtin,
+    Settings,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterpreterConfig {
+    pub language: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub executable: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub mode: ScriptMode,
+    /// Extension of the temporary file in `file` mode, e.g. `.rb`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension: Option<String>,
+    /// How `{{...}}` values are quoted inside scripts of this language.
+    #[serde(default = "default_escaping")]
+    pub escaping: Escaping,
+}
+
+fn default_escaping() -> Escaping {
+    Escaping::Raw
+}
+
+impl InterpreterConfig {
+    fn builtin(
+        language: &str,
+        aliases: &[&str],
+        executable: &str,
+        args: &[&str],
+        escaping: Escaping,
+    ) -> Self {
+        Self {
+            language: language.to_string(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            executable: executable.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            mode: ScriptMode::Inline,
+            extension: None,
+            escaping,
+        }
+    }
+
+    fn answers_to(&self, language: &str) -> bool {
+        self.language.eq_ignore_ascii_case(language)
+            || self
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(language))
+    }
+
+    /// Short name used in messages: the executable's file stem.
+    pub fn label(&self) -> String {
+        Path::new(&self.executable)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.executable.clone())
+    }
+}
+
+/// Script language as reported by `available_script_languages`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptLanguageInfo {
+    pub language: String,
+    pub aliases: Vec<String>,
+    pub executable: String,
+    pub mode: ScriptMode,
+    pub source: InterpreterSource,
+    /// Where the executable was found, `None` if it does not exist.
+    pub resolved_path: Option<String>,
+    pub available: bool,
+}
+
+/// Temporary script file removed when dropped.
+#[derive(Debug)]
+pub struct ScriptFile {
+    path: PathBuf,
+    written: bool,
+}
+
+impl ScriptFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ScriptFile {
+    fn drop(&mut self) {
+        if self.written {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// A ready-to-spawn interpreter command. Keep it alive until the process
+/// exits so a temporary script file is not removed under it.
+#[derive(Debug)]
+pub struct PreparedScript {
+    pub command: Command,
+    pub label: String,
+    pub file: Option<ScriptFile>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InterpreterRegistry {
+    entries: Vec<(InterpreterConfig, InterpreterSource)>,
+}
+
+impl Default for InterpreterRegistry {
+    fn default() -> Self {
+        Self {
+            entries: builtin_interpreters()
+                .into_iter()
+                .map(|config| (config, InterpreterSource::Builtin))
+                .collect(),
+        }
+    }
+}
+
+impl InterpreterRegistry {
+    /// Built-ins overlaid with the entries from `actions.interpreters`.
+    /// Invalid entries are reported and skipped.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let mut registry = Self::default();
+        for entry in settings.script_interpreter_entries() {
+            match serde_json::from_value::<InterpreterConfig>(entry.clone()) {
+                Ok(config) if config.language.trim().is_empty() => {
+                    eprintln!("ignoring script interpreter without a language id");
+                }
+                Ok(config) => registry.insert(config, InterpreterSource::Settings),
+                Err(err) => eprintln!("ignoring invalid script interpreter: {err}"),
+            }
+        }
+        registry
+    }
+
+    /// Adds `config`, replacing any entry with the same language id.
+    pub fn insert(&mut self, config: InterpreterConfig, source: InterpreterSource) {
+        self.entries
+            .retain(|(existing, _)| !existing.language.eq_ignore_ascii_case(&config.language));
+        self.entries.push((config, source));
+    }
+
+    /// Finds the interpreter for `language` by id or alias. Entries added
+    /// later win, so a settings alias can shadow a built-in one.
+    pub fn get(&self, language: &str) -> Option<&InterpreterConfig> {
+        let language = language.trim();
+        self.entries
+            .iter()
+            .rev()
+            .map(|(config, _)| config)
+            .find(|config| config.answers_to(language))
+    }
+
+    pub fn escaping(&self, language: &str) -> Escaping {
+        self.get(language)
+            .map(|config| config.escaping)
+            .unwrap_or(Escaping::Raw)
+    }
+
+    /// Builds the interpreter command for `script`. In `file` mode the
+    /// script is written to a temporary file only when `write_file` is set;
+    /// dry runs pass `false` and get the path it would use.
+    pub fn prepare(
+        &self,
+        language: &str,
+        script: &str,
+        write_file: bool,
+    ) -> Result<PreparedScript, AppError> {
+        let config = self.get(language).ok_or_else(|| {
+            AppError::Message(format!(
+                "unsupported script language '{}'",
+                language.trim().to_ascii_lowercase()
+            ))
+        })?;
+
+        let file = match config.mode {
+            ScriptMode::Inline => None,
+            ScriptMode::File => {
+                let extension = config.extension.as_deref().unwrap_or_default();
+                let path = std::env::temp_dir().join(format!(
+                    "autohotpie-script-{}{}",
+                    Uuid::new_v4(),
+                    extension
+                ));
+                let mut file = ScriptFile {
+                    path,
+                    written: false,
+                };
+                if write_file {
+                    write_private(&file.path, script).map_err(|err| {
+                        AppError::Message(format!(
+                            "failed to write {} script file: {err}",
+                            config.label()
+                        ))
+                    })?;
+                    file.written = true;
+                }
+                Some(file)
+            }
+        };
+        let file_path = file
+            .as_ref()
+            .map(|file| file.path.display().to_string())
+            .unwrap_or_default();
+
+        let mut substituted = false;
+        let mut args: Vec<String> = config
+            .args
+            .iter()
+            .map(|arg| {
+                substituted |= arg.contains("{script}") || arg.contains("{file}");
+                arg.replace("{file}", &file_path)
+                    .replace("{script}", script)
+            })
+            .collect();
+        if !substituted {
+            args.push(match config.mode {
+                ScriptMode::Inline => script.to_string(),
+                ScriptMode::File => file_path,
+            });
+        }
+
+        let mut command = Command::new(&config.executable);
+        command.args(args);
+        Ok(PreparedScript {
+            command,
+            label: config.label(),
+            file,
+        })
+    }
+
+    /// Every language with whether its executable can be found.
+    pub fn probe(&self) -> Vec<ScriptLanguageInfo> {
+        let mut languages: Vec<ScriptLanguageInfo> = self
+            .entries
+            .iter()
+            .map(|(config, source)| {
+                let resolved = process_tree::find_program(&config.executable);
+                ScriptLanguageInfo {
+                    language: config.language.clone(),
+                    aliases: config.aliases.clone(),
+                    executable: config.executable.clone(),
+                    mode: config.mode,
+                    source: *source,
+                    available: resolved.is_some(),
+                    resolved_path: resolved.map(|path| path.display().to_string()),
+                }
+            })
+            .collect();
+        languages.sort_by(|a, b| a.language.cmp(&b.language));
+        languages
+    }
+}
+
+/// Creates `path` readable by the current user only. Fails instead of
+/// following a file or link someone else planted at that name.
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+fn builtin_interpreters() -> Vec<InterpreterConfig> {
+    let powershell = if cfg!(target_os = "windows") {
+        "powershell"
+    } else {
+        "pwsh"
+    };
+    let mut builtins = vec![
+        InterpreterConfig::builtin(
+            "powershell",
+            &["pwsh"],
+            powershell,
+            &["-NoLogo", "-NoProfile", "-Command", "{script}"],
+            Escaping::PowerShell,
+        ),
+        InterpreterConfig::builtin("bash", &[], "bash", &["-c", "{script}"], Escaping::Posix),
+        InterpreterConfig::builtin("sh", &[], "sh", &["-c", "{script}"], Escaping::Posix),
+        InterpreterConfig::builtin(
+            "python",
+            &[],
+            "python",
+            &["-c", "{script}"],
+            Escaping::Python,
+        ),
+        InterpreterConfig::builtin(
+            "node",
+            &["javascript", "js"],
+            "node",
+            &["-e", "{script}"],
+            Escaping::JavaScript,
+        ),
+    ];
+    if cfg!(target_os = "windows") {
+        builtins.push(InterpreterConfig::builtin(
+            "cmd",
+            &[],
+            "cmd",
+            &["/C", "{script}"],
+            Escaping::Cmd,
+        ));
+    }
+    builtins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn args(prepared: &PreparedScript) -> Vec<String> {
+        prepared
+            .command
+            .as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn settings_entries_add_and_replace_languages() {
+        let mut settings = Settings::default();
+        settings.global["actions"]["interpreters"] = json!([
+            { "language": "ruby", "aliases": ["rb"], "executable": "ruby", "args": ["-e"] },
+            { "language": "Python", "executable": "/opt/venv/bin/python", "mode": "file",
+              "extension": ".py", "escaping": "python" },
+            { "executable": "missing-language" }
+        ]);
+        let registry = InterpreterRegistry::from_settings(&settings);
+
+        let ruby = registry.prepare("RB", "puts 1", false).expect("ruby");
+        assert_eq!(ruby.label, "ruby");
+        assert_eq!(args(&ruby), ["-e", "puts 1"]);
+
+        let python = registry
+            .prepare("python", "print(1)", true)
+            .expect("python");
+        let file = python.file.as_ref().expect("script file");
+        assert_eq!(
+            python.command.as_std().get_program(),
+            "/opt/venv/bin/python"
+        );
+        assert_eq!(args(&python), [file.path().display().to_string()]);
+        assert!(file.path().to_string_lossy().ends_with(".py"));
+        assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "print(1)");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(file.path()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(registry.escaping("python"), Escaping::Python);
+
+        let path = file.path().to_path_buf();
+        drop(python);
+        assert!(!path.exists());
+
+        let sh = registry
+            .prepare("sh", "echo hi", false)
+            .expect("builtin sh");
+        assert_eq!(args(&sh), ["-c", "echo hi"]);
+        assert!(registry.prepare("lua", "", false).is_err());
+
+        let probed = registry.probe();
+        let ruby = probed.iter().find(|info| info.language == "ruby").unwrap();
+        assert_eq!(ruby.source, InterpreterSource::Settings);
+        #[cfg(unix)]
+        assert!(probed
+            .iter()
+            .any(|info| info.language == "sh" && info.available));
+    }
+}
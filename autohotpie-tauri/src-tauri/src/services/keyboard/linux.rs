@@ -0,0 +1,173 @@
+use super::{Key, KeyboardBackend, KeyboardError, NamedKey};
+use parking_lot::Mutex;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    ConnectionExt as _, Keycode, Keysym, Window, KEY_PRESS_EVENT, KEY_RELEASE_EVENT,
+};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::rust_connection::RustConnection;
+
+const SHIFT_L: Keysym = 0xffe1;
+
+struct XTestSession {
+    conn: RustConnection,
+    root: Window,
+    min_keycode: Keycode,
+    keysyms_per_keycode: usize,
+    keysyms: Vec<Keysym>,
+}
+
+impl XTestSession {
+    fn connect() -> Result<Self, KeyboardError> {
+        let (conn, screen) =
+            x11rb::connect(None).map_err(|err| KeyboardError::Unavailable(err.to_string()))?;
+        let setup = conn.setup();
+        let root = setup.roots[screen].root;
+        let min_keycode = setup.min_keycode;
+        let count = setup.max_keycode - min_keycode + 1;
+        let mapping = conn
+            .get_keyboard_mapping(min_keycode, count)
+            .map_err(backend_error)?
+            .reply()
+            .map_err(backend_error)?;
+        // Fail early if the server lacks XTest rather than on the first key.
+        conn.xtest_get_version(2, 2)
+            .map_err(backend_error)?
+            .reply()
+            .map_err(|err| KeyboardError::Unavailable(format!("XTest extension: {err}")))?;
+        Ok(Self {
+            conn,
+            root,
+            min_keycode,
+            keysyms_per_keycode: usize::from(mapping.keysyms_per_keycode),
+            keysyms: mapping.keysyms,
+        })
+    }
+
+    /// Finds a keycode producing `keysym` and whether Shift must be held.
+    fn lookup(&self, keysym: Keysym) -> Option<(Keycode, bool)> {
+        if self.keysyms_per_keycode == 0 {
+            return None;
+        }
+        self.keysyms
+            .chunks(self.keysyms_per_keycode)
+            .enumerate()
+            .find_map(|(offset, syms)| {
+                let level = syms.iter().take(2).position(|sym| *sym == keysym)?;
+                Some((self.min_keycode + offset as u8, level == 1))
+            })
+    }
+
+    fn fake(&self, event: u8, keycode: Keycode) -> Result<(), KeyboardError> {
+        self.conn
+            .xtest_fake_input(event, keycode, 0, self.root, 0, 0, 0)
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    fn send(&self, key: Key, pressed: bool) -> Result<(), KeyboardError> {
+        let (keycode, shifted) = self
+            .lookup(keysym_for(key))
+            .ok_or(KeyboardError::Unmapped(key))?;
+        let shift = if shifted { self.lookup(SHIFT_L) } else { None };
+        if pressed {
+            if let Some((shift_code, _)) = shift {
+                self.fake(KEY_PRESS_EVENT, shift_code)?;
+            }
+            self.fake(KEY_PRESS_EVENT, keycode)?;
+        } else {
+            self.fake(KEY_RELEASE_EVENT, keycode)?;
+            if let Some((shift_code, _)) = shift {
+                self.fake(KEY_RELEASE_EVENT, shift_code)?;
+            }
+        }
+        self.conn.flush().map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+/// Injects keys through the X server's XTest extension. The connection is
+/// opened on first use so the app starts fine without a display.
+#[derive(Default)]
+pub struct XTestBackend {
+    session: Mutex<Option<XTestSession>>,
+}
+
+impl XTestBackend {
+    fn with_session(
+        &self,
+        f: impl FnOnce(&XTestSession) -> Result<(), KeyboardError>,
+    ) -> Result<(), KeyboardError> {
+        let mut guard = self.session.lock();
+        if guard.is_none() {
+            *guard = Some(XTestSession::connect()?);
+        }
+        let result = f(guard.as_ref().expect("session initialised"));
+        if matches!(result, Err(KeyboardError::Backend(_))) {
+            // Drop broken connections so the next call reconnects.
+            *guard = None;
+        }
+        result
+    }
+}
+
+impl KeyboardBackend for XTestBackend {
+    fn name(&self) -> &'static str {
+        "xtest"
+    }
+
+    fn press(&self, key: Key) -> Result<(), KeyboardError> {
+        self.with_session(|session| session.send(key, true))
+    }
+
+    fn release(&self, key: Key) -> Result<(), KeyboardError> {
+        self.with_session(|session| session.send(key, false))
+    }
+}
+
+fn backend_error(err: impl std::fmt::Display) -> KeyboardError {
+    KeyboardError::Backend(err.to_string())
+}
+
+fn keysym_for(key: Key) -> Keysym {
+    match key {
+        Key::Char(ch) => {
+            let code = ch as u32;
+            // Latin-1 keysyms equal their code point; the rest use the
+            // Unicode keysym range.
+            if (0x20..=0x7e).contains(&code) || (0xa0..=0xff).contains(&code) {
+                code
+            } else {
+                0x0100_0000 + code
+            }
+        }
+        Key::Named(named) => match named {
+            NamedKey::Enter => 0xff0d,
+            NamedKey::Tab => 0xff09,
+            NamedKey::Escape => 0xff1b,
+            NamedKey::Space => 0x0020,
+            NamedKey::Backspace => 0xff08,
+            NamedKey::Delete => 0xffff,
+            NamedKey::Insert => 0xff63,
+            NamedKey::Home => 0xff50,
+            NamedKey::End => 0xff57,
+            NamedKey::PageUp => 0xff55,
+            NamedKey::PageDown => 0xff56,
+            NamedKey::Left => 0xff51,
+            NamedKey::Up => 0xff52,
+            NamedKey::Right => 0xff53,
+            NamedKey::Down => 0xff54,
+            NamedKey::CapsLock => 0xffe5,
+            NamedKey::NumLock => 0xff7f,
+            NamedKey::ScrollLock => 0xff14,
+            NamedKey::PrintScreen => 0xff61,
+            NamedKey::Pause => 0xff13,
+            NamedKey::Menu => 0xff67,
+            NamedKey::Ctrl => 0xffe3,
+            NamedKey::Shift => SHIFT_L,
+            NamedKey::Alt => 0xffe9,
+            NamedKey::Super => 0xffeb,
+            NamedKey::F(number) => 0xffbe + u32::from(number.saturating_sub(1)),
+        },
+    }
+}
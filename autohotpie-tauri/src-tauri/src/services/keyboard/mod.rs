@@ -0,0 +1,172 @@
+#![allow(dead_code)]
+
+mod parser;
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+pub use parser::parse_sequence;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamedKey {
+    Enter,
+    Tab,
+    Escape,
+    Space,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+    CapsLock,
+    NumLock,
+    ScrollLock,
+    PrintScreen,
+    Pause,
+    Menu,
+    Ctrl,
+    Shift,
+    Alt,
+    Super,
+    F(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// A character typed as-is; backends add Shift when the layout needs it.
+    Char(char),
+    Named(NamedKey),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEvent {
+    Press(Key),
+    Release(Key),
+}
+
+#[derive(Debug, Error)]
+pub enum KeyboardError {
+    #[error("keyboard backend is not available: {0}")]
+    Unavailable(String),
+    #[error("key {0:?} cannot be produced with the current keyboard layout")]
+    Unmapped(Key),
+    #[error("keyboard backend failed: {0}")]
+    Backend(String),
+}
+
+/// Synthesizes key presses on the host. Implementations must be cheap to call
+/// repeatedly; [`send_events`] drives them one event at a time.
+pub trait KeyboardBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn press(&self, key: Key) -> Result<(), KeyboardError>;
+    fn release(&self, key: Key) -> Result<(), KeyboardError>;
+}
+
+/// Keeps every event in memory instead of touching the OS.
+#[derive(Default)]
+pub struct RecordingBackend {
+    events: Mutex<Vec<KeyEvent>>,
+}
+
+impl RecordingBackend {
+    pub fn events(&self) -> Vec<KeyEvent> {
+        self.events
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
+    }
+
+    fn record(&self, event: KeyEvent) -> Result<(), KeyboardError> {
+        self.events
+            .lock()
+            .map_err(|_| KeyboardError::Backend("recording backend poisoned".into()))?
+            .push(event);
+        Ok(())
+    }
+}
+
+impl KeyboardBackend for RecordingBackend {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn press(&self, key: Key) -> Result<(), KeyboardError> {
+        self.record(KeyEvent::Press(key))
+    }
+
+    fn release(&self, key: Key) -> Result<(), KeyboardError> {
+        self.record(KeyEvent::Release(key))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+struct UnsupportedBackend;
+
+#[cfg(not(target_os = "linux"))]
+impl KeyboardBackend for UnsupportedBackend {
+    fn name(&self) -> &'static str {
+        "unsupported"
+    }
+
+    fn press(&self, _key: Key) -> Result<(), KeyboardError> {
+        Err(KeyboardError::Unavailable(
+            "SendKeys is not supported on this platform yet".into(),
+        ))
+    }
+
+    fn release(&self, key: Key) -> Result<(), KeyboardError> {
+        self.press(key)
+    }
+}
+
+/// Backend used by the app: XTest on Linux, unsupported elsewhere.
+pub fn default_backend() -> Arc<dyn KeyboardBackend> {
+    #[cfg(target_os = "linux")]
+    {
+        Arc::new(linux::XTestBackend::default())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Arc::new(UnsupportedBackend)
+    }
+}
+
+/// Replays parsed events, pausing `delay` after every release. Keys still held
+/// when an error occurs are released before returning it.
+pub async fn send_events(
+    backend: &dyn KeyboardBackend,
+    events: &[KeyEvent],
+    delay: Option<Duration>,
+) -> Result<(), KeyboardError> {
+    let mut held: Vec<Key> = Vec::new();
+    for event in events {
+        let result = match *event {
+            KeyEvent::Press(key) => backend.press(key).map(|_| held.push(key)),
+            KeyEvent::Release(key) => backend.release(key).map(|_| {
+                if let Some(pos) = held.iter().rposition(|candidate| *candidate == key) {
+                    held.remove(pos);
+                }
+            }),
+        };
+        if let Err(err) = result {
+            for key in held.into_iter().rev() {
+                let _ = backend.release(key);
+            }
+            return Err(err);
+        }
+        if let (KeyEvent::Release(_), Some(delay)) = (event, delay) {
+            tokio::time::sleep(delay).await;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,313 @@
+use super::{Key, KeyEvent, NamedKey};
+use thiserror::Error;
+
+const MAX_REPEAT: u32 = 500;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KeyParseError {
+    #[error("key sequence is empty")]
+    Empty,
+    #[error("unterminated '{{' at position {0}")]
+    UnterminatedBrace(usize),
+    #[error("unknown key name '{0}'")]
+    UnknownKey(String),
+    #[error("invalid argument '{argument}' for key '{key}'")]
+    InvalidArgument { key: String, argument: String },
+    #[error("modifier '{0}' is not followed by a key")]
+    DanglingModifier(char),
+}
+
+enum Stroke {
+    Tap(u32),
+    Down,
+    Up,
+}
+
+/// Parses a SendKeys sequence into press/release events.
+///
+/// Two syntaxes are accepted. A hotkey accelerator such as `Control+Shift+P`
+/// is sent as one chord. Anything else follows AutoHotkey conventions:
+/// `^`, `+`, `!` and `#` hold Ctrl, Shift, Alt and Super for the next key,
+/// `{Name}` sends a named key, `{Name 3}` repeats it and `{Name down}` /
+/// `{Name up}` hold or release it. Other characters are typed literally.
+pub fn parse_sequence(input: &str) -> Result<Vec<KeyEvent>, KeyParseError> {
+    if input.is_empty() {
+        return Err(KeyParseError::Empty);
+    }
+    if let Some(events) = parse_accelerator(input) {
+        return Ok(events);
+    }
+
+    let chars: Vec<char> = input.chars().collect();
+    let mut events = Vec::new();
+    let mut modifiers: Vec<NamedKey> = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let ch = chars[index];
+        let (key, stroke) = match ch {
+            '^' | '+' | '!' | '#' => {
+                modifiers.push(match ch {
+                    '^' => NamedKey::Ctrl,
+                    '+' => NamedKey::Shift,
+                    '!' => NamedKey::Alt,
+                    _ => NamedKey::Super,
+                });
+                if index + 1 == chars.len() {
+                    return Err(KeyParseError::DanglingModifier(ch));
+                }
+                index += 1;
+                continue;
+            }
+            '{' => {
+                // Search from index + 2 so `{}}` names the closing brace itself.
+                let close = chars
+                    .iter()
+                    .skip(index + 2)
+                    .position(|candidate| *candidate == '}')
+                    .map(|offset| index + 2 + offset)
+                    .ok_or(KeyParseError::UnterminatedBrace(index))?;
+                let body: String = chars[index + 1..close].iter().collect();
+                index = close + 1;
+                parse_braced(&body)?
+            }
+            '\n' => {
+                index += 1;
+                (Key::Named(NamedKey::Enter), Stroke::Tap(1))
+            }
+            '\r' => {
+                index += 1;
+                continue;
+            }
+            '\t' => {
+                index += 1;
+                (Key::Named(NamedKey::Tab), Stroke::Tap(1))
+            }
+            other => {
+                index += 1;
+                (Key::Char(other), Stroke::Tap(1))
+            }
+        };
+
+        push_stroke(&mut events, &std::mem::take(&mut modifiers), key, stroke);
+    }
+
+    Ok(events)
+}
+
+fn parse_braced(body: &str) -> Result<(Key, Stroke), KeyParseError> {
+    // `{ }` is a literal space; everything else is `name [argument]`.
+    if body == " " {
+        return Ok((Key::Named(NamedKey::Space), Stroke::Tap(1)));
+    }
+    let mut parts = body.split_whitespace();
+    let name = parts.next().unwrap_or_default();
+    let key = resolve_key_name(name).ok_or_else(|| KeyParseError::UnknownKey(body.to_string()))?;
+    let stroke = match parts.next() {
+        None => Stroke::Tap(1),
+        Some(argument) => {
+            let invalid = || KeyParseError::InvalidArgument {
+                key: name.to_string(),
+                argument: argument.to_string(),
+            };
+            if parts.next().is_some() {
+                return Err(invalid());
+            }
+            match argument.to_ascii_lowercase().as_str() {
+                "down" => Stroke::Down,
+                "up" => Stroke::Up,
+                count => match count.parse::<u32>() {
+                    Ok(count) if count <= MAX_REPEAT => Stroke::Tap(count),
+                    _ => return Err(invalid()),
+                },
+            }
+        }
+    };
+    Ok((key, stroke))
+}
+
+fn push_stroke(events: &mut Vec<KeyEvent>, modifiers: &[NamedKey], key: Key, stroke: Stroke) {
+    for modifier in modifiers {
+        events.push(KeyEvent::Press(Key::Named(*modifier)));
+    }
+    match stroke {
+        Stroke::Tap(count) => {
+            for _ in 0..count {
+                events.push(KeyEvent::Press(key));
+                events.push(KeyEvent::Release(key));
+            }
+        }
+        Stroke::Down => events.push(KeyEvent::Press(key)),
+        Stroke::Up => events.push(KeyEvent::Release(key)),
+    }
+    for modifier in modifiers.iter().rev() {
+        events.push(KeyEvent::Release(Key::Named(*modifier)));
+    }
+}
+
+/// Recognises the accelerator syntax used for hotkeys (`Ctrl+Alt+Delete`).
+fn parse_accelerator(input: &str) -> Option<Vec<KeyEvent>> {
+    let tokens: Vec<&str> = input.split('+').map(str::trim).collect();
+    let (last, modifier_tokens) = tokens.split_last()?;
+    if modifier_tokens.is_empty() || last.is_empty() {
+        return None;
+    }
+    let modifiers = modifier_tokens
+        .iter()
+        .map(|token| match resolve_key_name(token)? {
+            Key::Named(
+                named @ (NamedKey::Ctrl | NamedKey::Shift | NamedKey::Alt | NamedKey::Super),
+            ) => Some(named),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let key = match resolve_key_name(last)? {
+        // Accelerators name the physical key, so `Ctrl+P` must not add Shift.
+        Key::Char(ch) => Key::Char(ch.to_ascii_lowercase()),
+        named => named,
+    };
+
+    let mut events = Vec::new();
+    push_stroke(&mut events, &modifiers, key, Stroke::Tap(1));
+    Some(events)
+}
+
+fn resolve_key_name(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(ch));
+    }
+
+    let lower = name.to_ascii_lowercase();
+    let named = match lower.as_str() {
+        "enter" | "return" => NamedKey::Enter,
+        "tab" => NamedKey::Tab,
+        "esc" | "escape" => NamedKey::Escape,
+        "space" => NamedKey::Space,
+        "bs" | "backspace" => NamedKey::Backspace,
+        "del" | "delete" => NamedKey::Delete,
+        "ins" | "insert" => NamedKey::Insert,
+        "home" => NamedKey::Home,
+        "end" => NamedKey::End,
+        "pgup" | "pageup" => NamedKey::PageUp,
+        "pgdn" | "pagedown" => NamedKey::PageDown,
+        "up" | "arrowup" => NamedKey::Up,
+        "down" | "arrowdown" => NamedKey::Down,
+        "left" | "arrowleft" => NamedKey::Left,
+        "right" | "arrowright" => NamedKey::Right,
+        "capslock" => NamedKey::CapsLock,
+        "numlock" => NamedKey::NumLock,
+        "scrolllock" => NamedKey::ScrollLock,
+        "printscreen" | "prtsc" => NamedKey::PrintScreen,
+        "pause" => NamedKey::Pause,
+        "appskey" | "menu" | "contextmenu" => NamedKey::Menu,
+        "ctrl" | "control" | "lctrl" | "rctrl" | "lcontrol" | "rcontrol" => NamedKey::Ctrl,
+        "shift" | "lshift" | "rshift" => NamedKey::Shift,
+        "alt" | "lalt" | "ralt" | "option" => NamedKey::Alt,
+        "win" | "lwin" | "rwin" | "super" | "meta" | "cmd" | "command" => NamedKey::Super,
+        "commandorcontrol" | "commandorctrl" | "cmdorctrl" | "cmdorcontrol" => {
+            if cfg!(target_os = "macos") {
+                NamedKey::Super
+            } else {
+                NamedKey::Ctrl
+            }
+        }
+        other => {
+            if let Some(number) = other.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                if (1..=24).contains(&number) {
+                    return Some(Key::Named(NamedKey::F(number)));
+                }
+                return None;
+            }
+            // Tauri accelerators also accept `KeyA` / `Digit1` code names.
+            let code = other
+                .strip_prefix("key")
+                .or_else(|| other.strip_prefix("digit"))?;
+            let mut chars = code.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(ch), None) if ch.is_ascii_alphanumeric() => Some(Key::Char(ch)),
+                _ => None,
+            };
+        }
+    };
+    Some(Key::Named(named))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tap(key: Key) -> [KeyEvent; 2] {
+        [KeyEvent::Press(key), KeyEvent::Release(key)]
+    }
+
+    const CTRL: Key = Key::Named(NamedKey::Ctrl);
+    const SHIFT: Key = Key::Named(NamedKey::Shift);
+
+    #[test]
+    fn parses_modifier_prefixes_and_literals() {
+        let err = parse_sequence("^c!").expect_err("dangling alt");
+        assert_eq!(err, KeyParseError::DanglingModifier('!'));
+
+        let events = parse_sequence("^cA").expect("parse");
+        let mut expected = vec![KeyEvent::Press(CTRL)];
+        expected.extend(tap(Key::Char('c')));
+        expected.push(KeyEvent::Release(CTRL));
+        expected.extend(tap(Key::Char('A')));
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn parses_braced_names_repeats_and_holds() {
+        let events = parse_sequence("{Ctrl down}{Enter 2}{ctrl up}{}}").expect("parse");
+        let enter = Key::Named(NamedKey::Enter);
+        let mut expected = vec![KeyEvent::Press(CTRL)];
+        expected.extend(tap(enter));
+        expected.extend(tap(enter));
+        expected.push(KeyEvent::Release(CTRL));
+        expected.extend(tap(Key::Char('}')));
+        assert_eq!(events, expected);
+
+        let events = parse_sequence("+{Left 2}").expect("parse");
+        let left = Key::Named(NamedKey::Left);
+        let mut expected = vec![KeyEvent::Press(SHIFT)];
+        expected.extend(tap(left));
+        expected.extend(tap(left));
+        expected.push(KeyEvent::Release(SHIFT));
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn parses_hotkey_accelerators_as_chords() {
+        let events = parse_sequence("Control+Shift+P").expect("parse");
+        let mut expected = vec![KeyEvent::Press(CTRL), KeyEvent::Press(SHIFT)];
+        expected.extend(tap(Key::Char('p')));
+        expected.extend([KeyEvent::Release(SHIFT), KeyEvent::Release(CTRL)]);
+        assert_eq!(events, expected);
+
+        // `a+b` is not an accelerator: `+` shifts the following key.
+        let events = parse_sequence("a+b").expect("parse");
+        let mut expected = tap(Key::Char('a')).to_vec();
+        expected.push(KeyEvent::Press(SHIFT));
+        expected.extend(tap(Key::Char('b')));
+        expected.push(KeyEvent::Release(SHIFT));
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn rejects_malformed_sequences() {
+        assert_eq!(parse_sequence(""), Err(KeyParseError::Empty));
+        assert_eq!(
+            parse_sequence("ab{Enter"),
+            Err(KeyParseError::UnterminatedBrace(2))
+        );
+        assert_eq!(
+            parse_sequence("{Bogus}"),
+            Err(KeyParseError::UnknownKey("Bogus".into()))
+        );
+        assert!(matches!(
+            parse_sequence("{Enter 9999}"),
+            Err(KeyParseError::InvalidArgument { .. })
+        ));
+    }
+}
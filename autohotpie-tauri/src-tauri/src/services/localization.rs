@@ -50,6 +50,9 @@ struct LocalizationState {
     cache: HashMap<String, LocalizationPack>,
     watcher: Option<RecommendedWatcher>,
     directory: Option<PathBuf>,
+    /// Language the UI last loaded, used for strings the backend shows
+    /// itself (tray menu).
+    current: Option<String>,
 }
 
 impl Default for LocalizationState {
@@ -58,6 +61,7 @@ impl Default for LocalizationState {
             cache: HashMap::new(),
             watcher: None,
             directory: None,
+            current: None,
         }
     }
 }
@@ -175,6 +179,25 @@ pub fn get_pack(language: &str) -> Option<LocalizationPack> {
     finalize_pack(language, &state.cache)
 }
 
+/// Remembers `language` as the one the UI shows.
+pub fn set_current(language: &str) {
+    LOCALIZATION_STATE.write().current = Some(language.to_string());
+}
+
+/// `key` in the current language, falling back to English and then to
+/// `fallback`.
+pub fn text(key: &str, fallback: &str) -> String {
+    let state = LOCALIZATION_STATE.read();
+    let language = state.current.as_deref().unwrap_or(DEFAULT_LANGUAGE);
+    finalize_pack(language, &state.cache)
+        .and_then(|pack| pack.strings.get(key).cloned())
+        .or_else(|| {
+            finalize_pack(DEFAULT_LANGUAGE, &state.cache)
+                .and_then(|pack| pack.strings.get(key).cloned())
+        })
+        .unwrap_or_else(|| fallback.to_string())
+}
+
 pub fn available_languages() -> Vec<String> {
     let mut languages = LOCALIZATION_STATE
         .read()
@@ -1,14 +1,26 @@
 pub mod action_events;
+pub mod action_plan;
 pub mod action_runner;
 pub mod audit_log;
 pub mod autostart;
+pub mod clipboard;
 pub mod connectivity;
+pub mod custom_handlers;
+pub mod execution_history;
+pub mod execution_policy;
+pub mod http_request;
 pub mod import_export;
+pub mod interpreters;
+pub mod keyboard;
 pub mod localization;
+pub mod process_tree;
 pub mod profile_router;
 pub mod storage_guard;
 pub mod system_status;
+pub mod templating;
 pub mod tray;
+pub mod triggers;
 pub mod update_checker;
+pub mod window_control;
 pub mod pie_overlay;
 pub mod window_info;
@@ -0,0 +1,310 @@
+use std::ffi::OsStr;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, Command};
+use tokio::time::timeout;
+
+/// How long output is still read after the child exits. Background
+/// processes it started may keep the pipes open indefinitely.
+const DRAIN_GRACE: Duration = Duration::from_millis(200);
+
+/// Exit status plus the tail of each output stream.
+#[derive(Debug)]
+pub struct ProcessOutput {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub stdout_truncated: bool,
+    pub stderr_truncated: bool,
+}
+
+/// Starts the command in its own process group (a new console process group
+/// on Windows) so the whole tree can be killed together.
+pub fn isolate(command: &mut Command) -> &mut Command {
+    #[cfg(unix)]
+    command.process_group(0);
+    #[cfg(windows)]
+    {
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+    command
+}
+
+/// Kills the process tree rooted at `child` when dropped, unless the child
+/// was reaped first. Dropping the future that awaits the child (timeout or
+/// cancellation) therefore takes its descendants down as well.
+pub struct ProcessTreeGuard {
+    pid: Option<u32>,
+}
+
+impl ProcessTreeGuard {
+    /// `child` must have been spawned from a command passed to [`isolate`].
+    pub fn new(child: &Child) -> Self {
+        Self { pid: child.id() }
+    }
+
+    /// Call once the child has exited.
+    pub fn disarm(&mut self) {
+        self.pid = None;
+    }
+}
+
+impl Drop for ProcessTreeGuard {
+    fn drop(&mut self) {
+        if let Some(pid) = self.pid.take() {
+            kill_tree(pid);
+        }
+    }
+}
+
+/// Runs the command in its own process group and captures stdout and
+/// stderr. The tree is killed if the returned future is dropped before the
+/// child exits. The streams are read while the child runs so it never
+/// blocks on a full pipe, and for [`DRAIN_GRACE`] after it exits; only the
+/// last `limit` bytes of each are kept.
+pub async fn output(command: &mut Command, limit: usize) -> io::Result<ProcessOutput> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    collect(command, limit).await
+}
+
+/// Like [`output`], but keeps the command's own stdio settings; only the
+/// streams set to [`Stdio::piped`] are captured.
+pub async fn collect(command: &mut Command, limit: usize) -> io::Result<ProcessOutput> {
+    collect_with_input(command, &[], limit).await
+}
+
+/// Like [`collect`], and writes `input` to the child's stdin, if piped,
+/// while the output is read. Stdin is closed afterwards. A child that exits
+/// without reading all of it is not an error.
+pub async fn collect_with_input(
+    command: &mut Command,
+    input: &[u8],
+    limit: usize,
+) -> io::Result<ProcessOutput> {
+    let mut child = isolate(command).spawn()?;
+    let mut guard = ProcessTreeGuard::new(&child);
+    let stdin = child.stdin.take();
+    let mut stdout = Tail::new(child.stdout.take(), limit);
+    let mut stderr = Tail::new(child.stderr.take(), limit);
+    let status = {
+        let feed = async {
+            if let Some(mut stdin) = stdin {
+                // The child may exit or close stdin before reading it all.
+                let _ = stdin.write_all(input).await;
+            }
+            Ok::<_, io::Error>(())
+        };
+        let drain = async { tokio::try_join!(feed, stdout.read_to_end(), stderr.read_to_end()) };
+        tokio::pin!(drain);
+        let mut drained = false;
+        let status = loop {
+            tokio::select! {
+                status = child.wait() => break status?,
+                result = &mut drain, if !drained => {
+                    result?;
+                    drained = true;
+                }
+            }
+        };
+        // Whatever the child left running is no longer ours to kill.
+        guard.disarm();
+        if !drained {
+            if let Ok(result) = timeout(DRAIN_GRACE, &mut drain).await {
+                result?;
+            }
+        }
+        status
+    };
+    Ok(ProcessOutput {
+        status,
+        stdout: stdout.kept,
+        stderr: stderr.kept,
+        stdout_truncated: stdout.truncated,
+        stderr_truncated: stderr.truncated,
+    })
+}
+
+/// The last `limit` bytes read from a stream. Reading can stop at any
+/// point and keeps what arrived so far.
+struct Tail<R> {
+    reader: Option<R>,
+    limit: usize,
+    kept: Vec<u8>,
+    truncated: bool,
+}
+
+impl<R: AsyncRead + Unpin> Tail<R> {
+    fn new(reader: Option<R>, limit: usize) -> Self {
+        Self {
+            reader,
+            limit,
+            kept: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    async fn read_to_end(&mut self) -> io::Result<()> {
+        let Some(reader) = self.reader.as_mut() else {
+            return Ok(());
+        };
+        let mut chunk = [0u8; 4096];
+        loop {
+            let read = reader.read(&mut chunk).await?;
+            if read == 0 {
+                return Ok(());
+            }
+            self.kept.extend_from_slice(&chunk[..read]);
+            if self.kept.len() > self.limit {
+                self.kept.drain(..self.kept.len() - self.limit);
+                self.truncated = true;
+            }
+        }
+    }
+}
+
+/// Locates `program` the way spawning it would: names containing a path
+/// separator are checked as given, bare names are searched on `PATH` (with
+/// the `PATHEXT` extensions on Windows). Returns `None` if nothing
+/// executable is found.
+pub fn find_program(program: &str) -> Option<PathBuf> {
+    find_program_in(program, std::env::var_os("PATH").as_deref())
+}
+
+/// Like [`find_program`], but searches `search_path` (a `PATH` value)
+/// instead of this process's `PATH`.
+pub fn find_program_in(program: &str, search_path: Option<&OsStr>) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.is_absolute() || path.components().count() > 1 {
+        return is_executable(path).then(|| path.to_path_buf());
+    }
+
+    let mut extensions = vec![String::new()];
+    if cfg!(windows) {
+        let pathext = std::env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.CMD;.BAT".into());
+        extensions.extend(pathext.split(';').map(str::to_string));
+    }
+    std::env::split_paths(search_path?).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|extension| dir.join(format!("{program}{extension}")))
+            .find(|candidate| is_executable(candidate))
+    })
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+pub fn kill_tree(pid: u32) {
+    #[cfg(unix)]
+    {
+        let Ok(pgid) = libc::pid_t::try_from(pid) else {
+            return;
+        };
+        // SAFETY: kill has no memory-safety preconditions; a negative pid
+        // addresses the process group created by `isolate`.
+        unsafe {
+            libc::kill(-pgid, libc::SIGKILL);
+        }
+    }
+    #[cfg(windows)]
+    {
+        let _ = std::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn dropping_guard_kills_grandchildren() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let pid_file = dir.path().join("grandchild.pid");
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            &format!("sleep 30 & echo $! > '{}'; wait", pid_file.display()),
+        ]);
+        let mut child = isolate(&mut command).spawn().expect("spawn");
+        let guard = ProcessTreeGuard::new(&child);
+
+        let mut grandchild = None;
+        for _ in 0..100 {
+            if let Ok(raw) = std::fs::read_to_string(&pid_file) {
+                if let Ok(pid) = raw.trim().parse::<i32>() {
+                    grandchild = Some(pid);
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let grandchild = grandchild.expect("grandchild pid");
+
+        drop(guard);
+        child.wait().await.expect("reap child");
+
+        let mut alive = true;
+        for _ in 0..100 {
+            // Orphans may linger as zombies until init reaps them.
+            alive = std::fs::read_to_string(format!("/proc/{grandchild}/stat"))
+                .map(|stat| !stat.contains(") Z "))
+                .unwrap_or(false);
+            if !alive {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!alive, "grandchild {grandchild} survived");
+    }
+
+    #[tokio::test]
+    async fn output_keeps_the_tail_of_each_stream() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "seq 1 5000; echo done >&2"]);
+        let output = output(&mut command, 64).await.expect("run");
+        assert!(output.status.success());
+        assert!(output.stdout_truncated);
+        assert_eq!(output.stdout.len(), 64);
+        assert!(String::from_utf8_lossy(&output.stdout).ends_with("4999\n5000\n"));
+        assert_eq!(output.stderr, b"done\n");
+        assert!(!output.stderr_truncated);
+    }
+
+    #[tokio::test]
+    async fn background_processes_holding_stdout_do_not_block_the_run() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 30 & echo hi"]);
+        let started = std::time::Instant::now();
+        let output = output(&mut command, 64).await.expect("run");
+        assert!(
+            started.elapsed() < Duration::from_secs(5),
+            "waited {:?} for the background process",
+            started.elapsed()
+        );
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"hi\n");
+    }
+}
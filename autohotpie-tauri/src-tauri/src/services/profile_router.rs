@@ -1,21 +1,32 @@
-use crate::commands::{AppState, SystemState};
+use crate::commands::{profiles::PROFILES_STORE_EVENT, AppState, SystemState};
+use crate::domain::profile::{ActivationRule, ProfileId};
 use crate::services::{
     audit_log::AuditLogger,
     system_status::{ScreenAreaSnapshot, WindowSnapshot},
+    window_info::WINDOW_EVENT,
 };
-use crate::storage::profile_repository::ProfileStore;
+use crate::storage::profile_repository::{ProfileRecord, ProfileStore};
 use anyhow::{anyhow, Result};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri::{AppHandle, Emitter, Listener, Manager, Runtime};
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
-use tokio::time::{interval, Duration};
-
-const PROFILE_EVENT: &str = "profiles://active-changed";
-const POLL_INTERVAL: Duration = Duration::from_millis(1000);
+use tokio::sync::Notify;
+use tokio::time::{interval, timeout, Duration, Instant};
+
+pub(crate) const PROFILE_EVENT: &str = "profiles://active-changed";
+/// The router re-evaluates when the foreground window or the profiles
+/// change; polling only covers notifications that never arrive.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Changes closer together than this are evaluated once, so focus flicker
+/// does not emit a profile change per window.
+const SETTLE_DELAY: Duration = Duration::from_millis(120);
+/// Longest a stream of changes may postpone the evaluation.
+const MAX_SETTLE_DELAY: Duration = Duration::from_millis(500);
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -28,10 +39,39 @@ pub enum MatchKind {
     Fallback,
 }
 
+/// What decides between profiles whose rules match the same window, most
+/// significant first.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RankingCriterion {
+    /// The higher `Profile::priority` wins.
+    Priority,
+    /// The more specific rule wins: screen area, then process name, window
+    /// class, window title and finally custom rules.
+    Specificity,
+    /// The profile selected most recently wins.
+    Recency,
+    /// The profile listed first wins.
+    Order,
+}
+
+/// The order in which [`RankingCriterion`]s are applied. It is the same for
+/// profiles that match no rule, which only apply when nothing matches.
+pub const RANKING_ORDER: [RankingCriterion; 4] = [
+    RankingCriterion::Priority,
+    RankingCriterion::Specificity,
+    RankingCriterion::Recency,
+    RankingCriterion::Order,
+];
+
+/// Picks the best matching profile by [`RANKING_ORDER`]. A profile activated
+/// by hand applies only while no rule matches, and the best profile without
+/// a matching rule only when neither applies.
 fn select_profile(
     store: &ProfileStore,
     window: &WindowSnapshot,
-    history: &Arc<Mutex<HashMap<usize, OffsetDateTime>>>,
+    history: &Arc<Mutex<HashMap<ProfileId, OffsetDateTime>>>,
+    rules: &RuleCache,
 ) -> Option<ActiveProfileSnapshot> {
     let mut best: Option<ProfileCandidate> = None;
     let mut fallback: Option<ProfileCandidate> = None;
@@ -46,9 +86,11 @@ fn select_profile(
             if active_id == record.profile.id {
                 manual = Some(ActiveProfileSnapshot {
                     index,
+                    profile_id: record.profile.id,
                     name: record.profile.name.clone(),
                     match_kind: MatchKind::Custom,
                     hold_to_open: record.profile.hold_to_open,
+                    priority: record.profile.priority,
                     selector_score: Some(u8::MAX),
                     matched_rule: Some("manual override".into()),
                     selected_at: None,
@@ -58,9 +100,9 @@ fn select_profile(
             }
         }
 
-        let rules = parse_rules(&record.profile.activation_rules);
+        let compiled = rules.rules_for(record);
         let match_info = match_rules(
-            &rules,
+            &compiled,
             window.process_name.as_deref(),
             window.window_title.as_deref(),
             window.window_class.as_deref(),
@@ -70,31 +112,20 @@ fn select_profile(
         let last_selected = history
             .lock()
             .ok()
-            .and_then(|record| record.get(&index).copied());
+            .and_then(|history| history.get(&record.profile.id).copied());
 
         let candidate = match match_info {
-            Some(info) => ProfileCandidate::from_match(
-                index,
-                record.profile.name.clone(),
-                info,
-                last_selected,
-                record.profile.hold_to_open,
-            ),
-            None => ProfileCandidate::fallback(
-                index,
-                record.profile.name.clone(),
-                last_selected,
-                record.profile.hold_to_open,
-            ),
+            Some(info) => ProfileCandidate::from_match(index, record, info, last_selected),
+            None => ProfileCandidate::fallback(index, record, last_selected),
         };
 
-        if candidate.snapshot.match_kind == MatchKind::Fallback {
-            fallback = Some(candidate);
-            continue;
-        }
-
-        if is_better_candidate(&candidate, best.as_ref()) {
-            best = Some(candidate);
+        let slot = if candidate.snapshot.match_kind == MatchKind::Fallback {
+            &mut fallback
+        } else {
+            &mut best
+        };
+        if is_better_candidate(&candidate, slot.as_ref()) {
+            *slot = Some(candidate);
         }
     }
 
@@ -108,9 +139,12 @@ fn select_profile(
 #[serde(rename_all = "camelCase")]
 pub struct ActiveProfileSnapshot {
     pub index: usize,
+    pub profile_id: ProfileId,
     pub name: String,
     pub match_kind: MatchKind,
     pub hold_to_open: bool,
+    #[serde(default)]
+    pub priority: i32,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub selector_score: Option<u8>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -126,9 +160,11 @@ pub struct ActiveProfileSnapshot {
 impl PartialEq for ActiveProfileSnapshot {
     fn eq(&self, other: &Self) -> bool {
         self.index == other.index
+            && self.profile_id == other.profile_id
             && self.name == other.name
             && self.match_kind == other.match_kind
             && self.hold_to_open == other.hold_to_open
+            && self.priority == other.priority
             && self.selector_score == other.selector_score
             && self.matched_rule == other.matched_rule
             && self.fallback_applied == other.fallback_applied
@@ -137,7 +173,7 @@ impl PartialEq for ActiveProfileSnapshot {
 
 impl Eq for ActiveProfileSnapshot {}
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ActiveProfileEvent {
     pub profile: Option<ActiveProfileSnapshot>,
@@ -146,7 +182,8 @@ pub struct ActiveProfileEvent {
 #[derive(Clone)]
 pub struct ProfileRouterState {
     current: Arc<Mutex<Option<ActiveProfileSnapshot>>>,
-    history: Arc<Mutex<HashMap<usize, OffsetDateTime>>>,
+    history: Arc<Mutex<HashMap<ProfileId, OffsetDateTime>>>,
+    rules: Arc<RuleCache>,
 }
 
 impl Default for ProfileRouterState {
@@ -154,7 +191,69 @@ impl Default for ProfileRouterState {
         Self {
             current: Arc::new(Mutex::new(None)),
             history: Arc::new(Mutex::new(HashMap::new())),
+            rules: Arc::new(RuleCache::default()),
+        }
+    }
+}
+
+/// A rule that cannot be compiled and therefore never matches.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleDiagnostic {
+    pub profile_id: ProfileId,
+    pub profile_name: String,
+    /// Position of the rule in the profile's activation rules.
+    pub rule_index: usize,
+    pub pattern: String,
+    pub error: String,
+}
+
+#[derive(Default)]
+struct CompiledRules {
+    revision: Option<u64>,
+    profiles: HashMap<ProfileId, Arc<Vec<Condition>>>,
+    unreported: Vec<RuleDiagnostic>,
+}
+
+/// Compiled activation rules per profile, so regexes are built once per
+/// change of the profiles rather than on every evaluation.
+#[derive(Default)]
+pub struct RuleCache {
+    compiled: Mutex<CompiledRules>,
+}
+
+impl RuleCache {
+    /// Drops every compiled profile if the store changed since `revision`
+    /// was last seen; see [`AppState::profiles_revision`].
+    fn sync(&self, revision: u64) {
+        if let Ok(mut compiled) = self.compiled.lock() {
+            if compiled.revision != Some(revision) {
+                compiled.revision = Some(revision);
+                compiled.profiles.clear();
+            }
+        }
+    }
+
+    fn rules_for(&self, record: &ProfileRecord) -> Arc<Vec<Condition>> {
+        let Ok(mut compiled) = self.compiled.lock() else {
+            return Arc::new(compile_rules(record).0);
+        };
+        if let Some(rules) = compiled.profiles.get(&record.profile.id) {
+            return rules.clone();
         }
+        let (rules, diagnostics) = compile_rules(record);
+        let rules = Arc::new(rules);
+        compiled.unreported.extend(diagnostics);
+        compiled.profiles.insert(record.profile.id, rules.clone());
+        rules
+    }
+
+    /// Diagnostics found since the last call.
+    fn take_diagnostics(&self) -> Vec<RuleDiagnostic> {
+        self.compiled
+            .lock()
+            .map(|mut compiled| std::mem::take(&mut compiled.unreported))
+            .unwrap_or_default()
     }
 }
 
@@ -170,9 +269,13 @@ impl ProfileRouterState {
         self.current.clone()
     }
 
-    pub(crate) fn history(&self) -> Arc<Mutex<HashMap<usize, OffsetDateTime>>> {
+    pub(crate) fn history(&self) -> Arc<Mutex<HashMap<ProfileId, OffsetDateTime>>> {
         self.history.clone()
     }
+
+    pub(crate) fn rules(&self) -> Arc<RuleCache> {
+        self.rules.clone()
+    }
 }
 
 pub fn start_router<R: Runtime>(app: AppHandle<R>) {
@@ -184,32 +287,60 @@ pub fn start_router<R: Runtime>(app: AppHandle<R>) {
         let router_state = app.state::<ProfileRouterState>();
         router_state.history()
     };
+    let rules = app.state::<ProfileRouterState>().rules();
+
+    let wake = Arc::new(Notify::new());
+    for event in [WINDOW_EVENT, PROFILES_STORE_EVENT] {
+        let wake = wake.clone();
+        app.listen_any(event, move |_| wake.notify_one());
+    }
 
     tauri::async_runtime::spawn(async move {
         let mut ticker = interval(POLL_INTERVAL);
         loop {
-            ticker.tick().await;
-            if let Err(err) = evaluate(&app, &shared_state, &history_state).await {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = wake.notified() => settle(&wake).await,
+            }
+            if let Err(err) = evaluate(&app, &shared_state, &history_state, &rules).await {
                 eprintln!("profile router tick failed: {err}");
             }
         }
     });
 }
 
+/// Returns once no wake-up arrived for `SETTLE_DELAY`, or after
+/// `MAX_SETTLE_DELAY` at the latest.
+async fn settle(wake: &Notify) {
+    let deadline = Instant::now() + MAX_SETTLE_DELAY;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return;
+        }
+        let quiet = timeout(SETTLE_DELAY.min(remaining), wake.notified()).await;
+        if quiet.is_err() {
+            return;
+        }
+    }
+}
+
 async fn evaluate<R: Runtime>(
     app: &AppHandle<R>,
     shared_state: &Arc<Mutex<Option<ActiveProfileSnapshot>>>,
-    history: &Arc<Mutex<HashMap<usize, OffsetDateTime>>>,
+    history: &Arc<Mutex<HashMap<ProfileId, OffsetDateTime>>>,
+    rules: &RuleCache,
 ) -> Result<()> {
-    let (store, audit) = {
+    let (store, revision, audit) = {
         let app_state = app.state::<AppState>();
         let guard = app_state
             .profiles
             .lock()
             .map_err(|_| anyhow!("profile store state poisoned"))?;
         let audit = app_state.audit().clone();
-        (guard.clone(), audit)
+        (guard.clone(), app_state.profiles_revision(), audit)
     };
+    rules.sync(revision);
 
     let status = {
         let system_state = app.state::<SystemState>();
@@ -225,6 +356,7 @@ async fn evaluate<R: Runtime>(
         &status.window,
         shared_state,
         history,
+        rules,
         &audit,
         |payload| {
             if let Err(err) = app.emit(PROFILE_EVENT, ActiveProfileEvent { profile: payload }) {
@@ -238,14 +370,18 @@ fn evaluate_from_state<F>(
     store: &ProfileStore,
     window: &WindowSnapshot,
     shared_state: &Arc<Mutex<Option<ActiveProfileSnapshot>>>,
-    history: &Arc<Mutex<HashMap<usize, OffsetDateTime>>>,
+    history: &Arc<Mutex<HashMap<ProfileId, OffsetDateTime>>>,
+    rules: &RuleCache,
     audit: &AuditLogger,
     mut on_change: F,
 ) -> Result<()>
 where
     F: FnMut(Option<ActiveProfileSnapshot>),
 {
-    let next_profile = select_profile(store, window, history);
+    let next_profile = select_profile(store, window, history, rules);
+    for diagnostic in rules.take_diagnostics() {
+        log_diagnostic(audit, &diagnostic);
+    }
     let now = OffsetDateTime::now_utc();
     if let Some(payload) = update_active_profile(shared_state, history, next_profile, now)? {
         if let Some(snapshot) = payload.clone() {
@@ -258,7 +394,7 @@ where
 
 fn update_active_profile(
     shared_state: &Arc<Mutex<Option<ActiveProfileSnapshot>>>,
-    history: &Arc<Mutex<HashMap<usize, OffsetDateTime>>>,
+    history: &Arc<Mutex<HashMap<ProfileId, OffsetDateTime>>>,
     next_profile: Option<ActiveProfileSnapshot>,
     now: OffsetDateTime,
 ) -> Result<Option<Option<ActiveProfileSnapshot>>> {
@@ -273,7 +409,7 @@ fn update_active_profile(
         });
         if let Some(snapshot) = &updated {
             if let Ok(mut record) = history.lock() {
-                record.insert(snapshot.index, now);
+                record.insert(snapshot.profile_id, now);
             }
         }
         *guard = updated.clone();
@@ -306,6 +442,23 @@ struct Rule {
     raw: String,
 }
 
+/// A compiled activation rule. Rules with `negate` set are wrapped in `Not`.
+enum Condition {
+    Rule(Rule),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+    /// Holds when none of the conditions do.
+    Not(Vec<Condition>),
+}
+
+/// The window fields rules look at.
+struct Subject<'a> {
+    process_name: Option<&'a str>,
+    window_title: Option<&'a str>,
+    window_class: Option<&'a str>,
+    screen_area: Option<&'a ScreenAreaSnapshot>,
+}
+
 #[derive(Debug, Clone)]
 struct MatchInfo {
     kind: MatchKind,
@@ -317,6 +470,7 @@ struct MatchInfo {
 #[derive(Debug, Clone)]
 struct ProfileCandidate {
     snapshot: ActiveProfileSnapshot,
+    priority: i32,
     score: u8,
     last_selected: Option<OffsetDateTime>,
     order: usize,
@@ -325,16 +479,17 @@ struct ProfileCandidate {
 impl ProfileCandidate {
     fn from_match(
         index: usize,
-        name: String,
+        record: &ProfileRecord,
         info: MatchInfo,
         last_selected: Option<OffsetDateTime>,
-        hold_to_open: bool,
     ) -> Self {
         let snapshot = ActiveProfileSnapshot {
             index,
-            name,
+            profile_id: record.profile.id,
+            name: record.profile.name.clone(),
             match_kind: info.kind,
-            hold_to_open,
+            hold_to_open: record.profile.hold_to_open,
+            priority: record.profile.priority,
             selector_score: Some(info.score),
             matched_rule: Some(info.rule),
             selected_at: None,
@@ -343,6 +498,7 @@ impl ProfileCandidate {
 
         Self {
             snapshot,
+            priority: record.profile.priority,
             score: info.score,
             last_selected,
             order: index,
@@ -351,15 +507,16 @@ impl ProfileCandidate {
 
     fn fallback(
         index: usize,
-        name: String,
+        record: &ProfileRecord,
         last_selected: Option<OffsetDateTime>,
-        hold_to_open: bool,
     ) -> Self {
         let snapshot = ActiveProfileSnapshot {
             index,
-            name,
+            profile_id: record.profile.id,
+            name: record.profile.name.clone(),
             match_kind: MatchKind::Fallback,
-            hold_to_open,
+            hold_to_open: record.profile.hold_to_open,
+            priority: record.profile.priority,
             selector_score: Some(0),
             matched_rule: Some("fallback".into()),
             selected_at: None,
@@ -368,6 +525,7 @@ impl ProfileCandidate {
 
         Self {
             snapshot,
+            priority: record.profile.priority,
             score: 0,
             last_selected,
             order: index,
@@ -375,35 +533,114 @@ impl ProfileCandidate {
     }
 }
 
-fn parse_rules(handles: &[crate::domain::profile::ActivationRule]) -> Vec<Rule> {
-    handles.iter().filter_map(parse_rule).collect()
+/// Compiles the profile's rules, skipping empty ones and reporting those
+/// that cannot be compiled.
+fn compile_rules(record: &ProfileRecord) -> (Vec<Condition>, Vec<RuleDiagnostic>) {
+    let mut rules = Vec::new();
+    let mut diagnostics = Vec::new();
+    for (index, rule) in record.profile.activation_rules.iter().enumerate() {
+        match parse_rule(rule) {
+            Ok(Some(rule)) => rules.push(rule),
+            Ok(None) => {}
+            Err((pattern, error)) => diagnostics.push(RuleDiagnostic {
+                profile_id: record.profile.id,
+                profile_name: record.profile.name.clone(),
+                rule_index: index,
+                pattern,
+                error,
+            }),
+        }
+    }
+    (rules, diagnostics)
 }
 
-fn parse_rule(rule: &crate::domain::profile::ActivationRule) -> Option<Rule> {
+/// `Ok(None)` for rules without anything to match, such as an empty value
+/// or group. `Err` carries the offending pattern and why it was rejected.
+type Parsed<T> = std::result::Result<Option<T>, (String, String)>;
+
+fn parse_rule(rule: &ActivationRule) -> Parsed<Condition> {
     use crate::domain::profile::ActivationMatchMode as Mode;
 
-    match rule.mode {
-        Mode::Always => Some(Rule {
+    let condition = match rule.mode {
+        Mode::Always => Some(Condition::Rule(Rule {
             target: Target::Any,
             matcher: Matcher::Fallback,
             raw: "fallback".to_string(),
-        }),
-        Mode::ProcessName => build_text_rule(rule, Target::Process),
-        Mode::WindowTitle => build_text_rule(rule, Target::WindowTitle),
-        Mode::WindowClass => build_text_rule(rule, Target::WindowClass),
-        Mode::Custom => build_text_rule(rule, Target::Any),
-        Mode::ScreenArea => build_screen_area_rule(rule),
+        })),
+        Mode::ProcessName => build_text_rule(rule, Target::Process)?.map(Condition::Rule),
+        Mode::WindowTitle => build_text_rule(rule, Target::WindowTitle)?.map(Condition::Rule),
+        Mode::WindowClass => build_text_rule(rule, Target::WindowClass)?.map(Condition::Rule),
+        Mode::Custom => build_text_rule(rule, Target::Any)?.map(Condition::Rule),
+        Mode::ScreenArea => build_screen_area_rule(rule).map(Condition::Rule),
+        Mode::All | Mode::Any | Mode::Not => build_group(rule)?,
+    };
+    Ok(condition.map(|condition| {
+        if rule.negate == Some(true) {
+            Condition::Not(vec![condition])
+        } else {
+            condition
+        }
+    }))
+}
+
+/// A group fails as a whole when one of its rules cannot be compiled, so
+/// that an `all` group never matches with a member missing.
+fn build_group(rule: &ActivationRule) -> Parsed<Condition> {
+    use crate::domain::profile::ActivationMatchMode as Mode;
+
+    let mut children = Vec::new();
+    for child in &rule.rules {
+        children.extend(parse_rule(child)?);
     }
+    if children.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(match rule.mode {
+        Mode::All => Condition::All(children),
+        Mode::Any => Condition::Any(children),
+        _ => Condition::Not(children),
+    }))
+}
+
+/// Pattern and flags of a `json:` rule value; the profile editor stores
+/// negated and case-sensitive rules this way.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TextRulePayload {
+    #[serde(default)]
+    pattern: String,
+    #[serde(default)]
+    is_regex: Option<bool>,
+    #[serde(default)]
+    case_sensitive: Option<bool>,
 }
 
-fn build_text_rule(rule: &crate::domain::profile::ActivationRule, target: Target) -> Option<Rule> {
-    let raw = rule.value.as_ref()?.trim();
+fn build_text_rule(rule: &ActivationRule, target: Target) -> Parsed<Rule> {
+    let Some(value) = rule.value.as_deref().map(str::trim) else {
+        return Ok(None);
+    };
+    let payload = match value.strip_prefix("json:") {
+        Some(json) => match serde_json::from_str::<TextRulePayload>(json) {
+            Ok(payload) => Some(payload),
+            Err(err) => return Err((value.to_string(), err.to_string())),
+        },
+        None => None,
+    };
+    let raw = payload
+        .as_ref()
+        .map_or(value, |payload| payload.pattern.trim());
     if raw.is_empty() {
-        return None;
+        return Ok(None);
     }
 
-    let is_regex = rule.is_regex.unwrap_or_else(|| raw.starts_with("regex:"));
-    let case_sensitive = rule.case_sensitive.unwrap_or(false);
+    let is_regex = rule
+        .is_regex
+        .or(payload.as_ref().and_then(|payload| payload.is_regex))
+        .unwrap_or_else(|| raw.starts_with("regex:"));
+    let case_sensitive = rule
+        .case_sensitive
+        .or(payload.as_ref().and_then(|payload| payload.case_sensitive))
+        .unwrap_or(false);
 
     if is_regex {
         let pattern = if raw.starts_with("regex:") {
@@ -413,33 +650,30 @@ fn build_text_rule(rule: &crate::domain::profile::ActivationRule, target: Target
         };
 
         if pattern.is_empty() {
-            return None;
+            return Ok(None);
         }
 
         match Regex::new(pattern) {
-            Ok(regex) => Some(Rule {
+            Ok(regex) => Ok(Some(Rule {
                 target,
                 matcher: Matcher::Regex(regex),
                 raw: raw.to_string(),
-            }),
-            Err(err) => {
-                eprintln!("invalid regex '{pattern}' in profile rule: {err}");
-                None
-            }
+            })),
+            Err(err) => Err((pattern.to_string(), err.to_string())),
         }
     } else {
-        Some(Rule {
+        Ok(Some(Rule {
             target,
             matcher: Matcher::Exact {
                 value: raw.to_string(),
                 case_sensitive,
             },
             raw: raw.to_string(),
-        })
+        }))
     }
 }
 
-fn build_screen_area_rule(rule: &crate::domain::profile::ActivationRule) -> Option<Rule> {
+fn build_screen_area_rule(rule: &ActivationRule) -> Option<Rule> {
     let area = rule.screen_area.as_ref()?;
     Some(Rule {
         target: Target::ScreenArea,
@@ -478,96 +712,124 @@ impl Target {
     }
 }
 
+/// The first matching rule, described in full when it is a group.
 fn match_rules(
-    rules: &[Rule],
+    rules: &[Condition],
     process_name: Option<&str>,
     window_title: Option<&str>,
     window_class: Option<&str>,
     screen_area: Option<&ScreenAreaSnapshot>,
 ) -> Option<MatchInfo> {
-    for rule in rules {
-        match &rule.matcher {
-            Matcher::Fallback => {
-                return Some(MatchInfo {
-                    kind: MatchKind::Fallback,
-                    score: rule.target.match_score(),
-                    rule: rule.raw.clone(),
-                    fallback: true,
-                })
-            }
-            Matcher::Exact {
-                value,
-                case_sensitive,
-            } => {
-                if rule_applies(
-                    rule.target,
-                    process_name,
-                    window_title,
-                    window_class,
-                    |candidate| {
-                        if *case_sensitive {
-                            candidate == value
-                        } else {
-                            candidate.eq_ignore_ascii_case(value)
-                        }
-                    },
-                ) {
-                    return Some(MatchInfo {
-                        kind: rule.target.match_kind(),
-                        score: rule.target.match_score(),
-                        rule: rule.raw.clone(),
-                        fallback: false,
-                    });
-                }
-            }
-            Matcher::Regex(regex) => {
-                if rule_applies(
-                    rule.target,
-                    process_name,
-                    window_title,
-                    window_class,
-                    |candidate| regex.is_match(candidate),
-                ) {
-                    return Some(MatchInfo {
-                        kind: rule.target.match_kind(),
-                        score: rule.target.match_score(),
-                        rule: rule.raw.clone(),
-                        fallback: false,
-                    });
-                }
-            }
-            Matcher::ScreenArea(expected) => {
-                if let Some(actual) = screen_area {
-                    if actual.x == expected.x
-                        && actual.y == expected.y
-                        && actual.width == expected.width
-                        && actual.height == expected.height
-                    {
-                        return Some(MatchInfo {
-                            kind: MatchKind::ScreenArea,
-                            score: rule.target.match_score(),
-                            rule: rule.raw.clone(),
-                            fallback: false,
-                        });
+    let subject = Subject {
+        process_name,
+        window_title,
+        window_class,
+        screen_area,
+    };
+    rules.iter().find_map(|condition| {
+        let info = condition.evaluate(&subject)?;
+        Some(match condition {
+            Condition::Rule(_) => info,
+            _ => MatchInfo {
+                rule: condition.describe(),
+                ..info
+            },
+        })
+    })
+}
+
+impl Condition {
+    /// A matching `all` group reports its most specific member; `not`
+    /// reports a custom match, since it says nothing specific about the
+    /// window.
+    fn evaluate(&self, subject: &Subject<'_>) -> Option<MatchInfo> {
+        match self {
+            Condition::Rule(rule) => match_rule(rule, subject),
+            Condition::All(conditions) => {
+                let mut best: Option<MatchInfo> = None;
+                for condition in conditions {
+                    let info = condition.evaluate(subject)?;
+                    if best.as_ref().map_or(true, |best| info.score > best.score) {
+                        best = Some(info);
                     }
                 }
+                best
             }
+            Condition::Any(conditions) => conditions
+                .iter()
+                .find_map(|condition| condition.evaluate(subject)),
+            Condition::Not(conditions) => conditions
+                .iter()
+                .all(|condition| condition.evaluate(subject).is_none())
+                .then(|| MatchInfo {
+                    kind: MatchKind::Custom,
+                    score: Target::Any.match_score(),
+                    rule: self.describe(),
+                    fallback: false,
+                }),
+        }
+    }
+
+    fn describe(&self) -> String {
+        let list = |conditions: &[Condition]| {
+            conditions
+                .iter()
+                .map(Condition::describe)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Condition::Rule(rule) => rule.raw.clone(),
+            Condition::All(conditions) => format!("all({})", list(conditions)),
+            Condition::Any(conditions) => format!("any({})", list(conditions)),
+            Condition::Not(conditions) => format!("not({})", list(conditions)),
         }
     }
-    None
 }
 
-fn rule_applies<F>(
-    target: Target,
-    process: Option<&str>,
-    window: Option<&str>,
-    class: Option<&str>,
-    predicate: F,
-) -> bool
+fn match_rule(rule: &Rule, subject: &Subject<'_>) -> Option<MatchInfo> {
+    let matched = match &rule.matcher {
+        Matcher::Fallback => {
+            return Some(MatchInfo {
+                kind: MatchKind::Fallback,
+                score: rule.target.match_score(),
+                rule: rule.raw.clone(),
+                fallback: true,
+            })
+        }
+        Matcher::Exact {
+            value,
+            case_sensitive,
+        } => rule_applies(rule.target, subject, |candidate| {
+            if *case_sensitive {
+                candidate == value
+            } else {
+                candidate.eq_ignore_ascii_case(value)
+            }
+        }),
+        Matcher::Regex(regex) => {
+            rule_applies(rule.target, subject, |candidate| regex.is_match(candidate))
+        }
+        Matcher::ScreenArea(expected) => subject.screen_area == Some(expected),
+    };
+    matched.then(|| MatchInfo {
+        kind: rule.target.match_kind(),
+        score: rule.target.match_score(),
+        rule: rule.raw.clone(),
+        fallback: false,
+    })
+}
+
+fn rule_applies<F>(target: Target, subject: &Subject<'_>, predicate: F) -> bool
 where
     F: Fn(&str) -> bool,
 {
     let check = |candidate: Option<&str>| candidate.map_or(false, |value| predicate(value));
+    let (process, window, class) = (
+        subject.process_name,
+        subject.window_title,
+        subject.window_class,
+    );
     match target {
         Target::Process => check(process),
         Target::WindowTitle => check(window),
@@ -578,21 +840,22 @@ where
 }
 
 fn is_better_candidate(new: &ProfileCandidate, current: Option<&ProfileCandidate>) -> bool {
-    match current {
-        None => true,
-        Some(existing) => {
-            if new.score != existing.score {
-                return new.score > existing.score;
-            }
-
-            match (new.last_selected, existing.last_selected) {
-                (Some(a), Some(b)) => a > b,
-                (Some(_), None) => true,
-                (None, Some(_)) => false,
-                (None, None) => new.order < existing.order,
-            }
+    let Some(existing) = current else {
+        return true;
+    };
+    for criterion in RANKING_ORDER {
+        let ordering = match criterion {
+            RankingCriterion::Priority => new.priority.cmp(&existing.priority),
+            RankingCriterion::Specificity => new.score.cmp(&existing.score),
+            // Never selected ranks below any selection.
+            RankingCriterion::Recency => new.last_selected.cmp(&existing.last_selected),
+            RankingCriterion::Order => existing.order.cmp(&new.order),
+        };
+        if ordering != Ordering::Equal {
+            return ordering == Ordering::Greater;
         }
     }
+    false
 }
 
 fn log_selection(audit: &AuditLogger, snapshot: &ActiveProfileSnapshot) {
@@ -615,6 +878,16 @@ fn log_selection(audit: &AuditLogger, snapshot: &ActiveProfileSnapshot) {
     }
 }
 
+fn log_diagnostic(audit: &AuditLogger, diagnostic: &RuleDiagnostic) {
+    let entry = json!({
+        "component": "profile_router",
+        "invalidRule": diagnostic,
+    });
+    if let Err(err) = audit.log("WARN", &entry.to_string()) {
+        eprintln!("failed to write profile rule diagnostic: {err}");
+    }
+}
+
 fn format_timestamp(datetime: OffsetDateTime) -> String {
     datetime
         .format(&Rfc3339)
@@ -630,16 +903,18 @@ pub fn resolve_now<R: Runtime>(app: &AppHandle<R>) -> Result<Option<ActiveProfil
         let router_state = app.state::<ProfileRouterState>();
         router_state.history()
     };
+    let rules = app.state::<ProfileRouterState>().rules();
 
-    let (profiles, audit) = {
+    let (profiles, revision, audit) = {
         let app_state = app.state::<AppState>();
         let guard = app_state
             .profiles
             .lock()
             .map_err(|_| anyhow!("profile store state poisoned"))?;
         let audit = app_state.audit().clone();
-        (guard.clone(), audit)
+        (guard.clone(), app_state.profiles_revision(), audit)
     };
+    rules.sync(revision);
 
     let status = {
         let system_state = app.state::<SystemState>();
@@ -655,6 +930,7 @@ pub fn resolve_now<R: Runtime>(app: &AppHandle<R>) -> Result<Option<ActiveProfil
         &status.window,
         &shared_state,
         &history_state,
+        &rules,
         &audit,
         |payload| {
             if let Err(err) = app.emit(
@@ -705,20 +981,24 @@ mod tests {
             is_regex: None,
             case_sensitive: None,
             screen_area: None,
+            rules: Vec::new(),
+        }
+    }
+
+    fn make_group(mode: ActivationMatchMode, rules: Vec<ActivationRule>) -> ActivationRule {
+        ActivationRule {
+            rules,
+            ..make_rule(mode, None)
         }
     }
 
     fn make_record(name: &str, rules: Vec<ActivationRule>) -> ProfileRecord {
         let root_menu = PieMenuId::new();
-        let action_id = crate::domain::ActionId::new();
-        let action = crate::domain::ActionDefinition {
-            id: action_id,
-            name: format!("{name} Action"),
-            description: None,
-            timeout_ms: 3000,
-            last_validated_at: None,
-            steps: Vec::new(),
-        };
+        let action = crate::domain::Action::new(
+            format!("{name} Action"),
+            crate::domain::ActionPayload::Macro { steps: Vec::new() },
+        );
+        let action_id = action.id;
 
         let menu = crate::domain::pie_menu::PieMenu {
             id: root_menu,
@@ -756,6 +1036,7 @@ mod tests {
                 activation_rules: rules,
                 root_menu,
                 hold_to_open: false,
+                priority: 0,
             },
             menus: vec![menu],
             actions: vec![action],
@@ -784,7 +1065,13 @@ mod tests {
 
         let history = Arc::new(Mutex::new(HashMap::new()));
 
-        let result = select_profile(&store, &snapshot(Some("chrome.exe"), None), &history).unwrap();
+        let result = select_profile(
+            &store,
+            &snapshot(Some("chrome.exe"), None),
+            &history,
+            &RuleCache::default(),
+        )
+        .unwrap();
         assert_eq!(result.name, "Chrome");
         assert_eq!(result.match_kind, MatchKind::ProcessName);
     }
@@ -806,6 +1093,7 @@ mod tests {
             &store,
             &snapshot(None, Some("Visual Studio Code")),
             &history,
+            &RuleCache::default(),
         )
         .unwrap();
         assert_eq!(result.name, "Editor");
@@ -836,6 +1124,7 @@ mod tests {
             &store,
             &snapshot(Some("code.exe"), Some("Visual Studio Code")),
             &history,
+            &RuleCache::default(),
         )
         .unwrap();
 
@@ -843,6 +1132,58 @@ mod tests {
         assert_eq!(result.match_kind, MatchKind::ProcessName);
     }
 
+    #[test]
+    fn ties_are_broken_by_priority_then_recency_of_the_same_profile() {
+        let editor = |name: &str| {
+            make_record(
+                name,
+                vec![make_rule(ActivationMatchMode::ProcessName, Some("code"))],
+            )
+        };
+        let mut store = empty_store();
+        store.profiles.push(editor("First"));
+        store.profiles.push(editor("Second"));
+        let window = snapshot(Some("code"), Some("main.rs"));
+        let history = Arc::new(Mutex::new(HashMap::new()));
+        let rules = RuleCache::default();
+        let selected = |store: &ProfileStore| {
+            select_profile(store, &window, &history, &rules)
+                .expect("profile selected")
+                .name
+        };
+
+        assert_eq!(selected(&store), "First");
+
+        let second = store.profiles[1].profile.id;
+        history
+            .lock()
+            .unwrap()
+            .insert(second, OffsetDateTime::now_utc());
+        assert_eq!(selected(&store), "Second");
+
+        // Recency follows the profile, not its position.
+        store.profiles.insert(0, editor("Inserted"));
+        store.profiles.remove(1);
+        assert_eq!(selected(&store), "Second");
+
+        // Priority outranks a more specific rule.
+        let mut titled = make_record(
+            "Titled",
+            vec![make_rule(ActivationMatchMode::WindowTitle, Some("main.rs"))],
+        );
+        titled.profile.priority = 1;
+        store.profiles.push(titled);
+        let result = select_profile(&store, &window, &history, &rules).expect("profile selected");
+        assert_eq!(result.name, "Titled");
+        assert_eq!(result.match_kind, MatchKind::WindowTitle);
+        assert_eq!(result.priority, 1);
+
+        assert_eq!(
+            serde_json::to_value(RANKING_ORDER).unwrap(),
+            json!(["priority", "specificity", "recency", "order"])
+        );
+    }
+
     #[test]
     fn select_profile_uses_specific_match_before_fallback_profiles() {
         let mut store = empty_store();
@@ -857,12 +1198,189 @@ mod tests {
 
         let history = Arc::new(Mutex::new(HashMap::new()));
 
-        let result = select_profile(&store, &snapshot(Some("chrome.exe"), None), &history).unwrap();
+        let result = select_profile(
+            &store,
+            &snapshot(Some("chrome.exe"), None),
+            &history,
+            &RuleCache::default(),
+        )
+        .unwrap();
 
         assert_eq!(result.name, "Chrome");
         assert_eq!(result.match_kind, MatchKind::ProcessName);
     }
 
+    #[test]
+    fn negated_rules_and_groups_combine() {
+        let mut store = empty_store();
+        // Process is code and the title does not mention Settings.
+        store.profiles.push(make_record(
+            "Code",
+            vec![make_group(
+                ActivationMatchMode::All,
+                vec![
+                    make_rule(ActivationMatchMode::ProcessName, Some("code")),
+                    make_group(
+                        ActivationMatchMode::Not,
+                        vec![make_rule(
+                            ActivationMatchMode::WindowTitle,
+                            Some("regex:Settings"),
+                        )],
+                    ),
+                ],
+            )],
+        ));
+        // Any browser but Chrome, negated the way the profile editor
+        // stores it.
+        let mut not_chrome = make_rule(
+            ActivationMatchMode::ProcessName,
+            Some(r#"json:{"version":1,"pattern":"chrome","caseSensitive":true}"#),
+        );
+        not_chrome.negate = Some(true);
+        store.profiles.push(make_record(
+            "Other browsers",
+            vec![make_group(
+                ActivationMatchMode::All,
+                vec![
+                    make_rule(
+                        ActivationMatchMode::WindowClass,
+                        Some("regex:^(Firefox|chrome)$"),
+                    ),
+                    not_chrome,
+                ],
+            )],
+        ));
+
+        let history = Arc::new(Mutex::new(HashMap::new()));
+        let rules = RuleCache::default();
+        let select = |process: &str, title: &str, class: &str| {
+            let mut window = snapshot(Some(process), Some(title));
+            window.window_class = Some(class.into());
+            select_profile(&store, &window, &history, &rules).unwrap()
+        };
+
+        let code = select("code", "main.rs - crate", "Code");
+        assert_eq!(code.name, "Code");
+        assert_eq!(code.match_kind, MatchKind::ProcessName);
+        assert_eq!(
+            code.matched_rule.as_deref(),
+            Some("all(code, not(regex:Settings))")
+        );
+        let settings = select("code", "Settings - crate", "Code");
+        assert_eq!(settings.match_kind, MatchKind::Fallback);
+
+        assert_eq!(select("firefox", "Docs", "Firefox").name, "Other browsers");
+        let chrome = select("chrome", "Docs", "chrome");
+        assert_eq!(chrome.match_kind, MatchKind::Fallback);
+        assert!(rules.take_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported_once_per_revision() {
+        let mut store = empty_store();
+        store.profiles.push(make_record(
+            "Terminal",
+            vec![
+                make_rule(ActivationMatchMode::WindowTitle, Some("regex:(unclosed")),
+                make_rule(ActivationMatchMode::ProcessName, Some("alacritty")),
+            ],
+        ));
+        let history = Arc::new(Mutex::new(HashMap::new()));
+        let rules = RuleCache::default();
+        let window = snapshot(Some("alacritty"), Some("~"));
+
+        rules.sync(1);
+        for _ in 0..3 {
+            let result = select_profile(&store, &window, &history, &rules).unwrap();
+            assert_eq!(result.match_kind, MatchKind::ProcessName);
+        }
+        let diagnostics = rules.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].profile_name, "Terminal");
+        assert_eq!(diagnostics[0].rule_index, 0);
+        assert_eq!(diagnostics[0].pattern, "(unclosed");
+        assert!(rules.take_diagnostics().is_empty());
+
+        // Saving the profiles recompiles them, so the problem is reported
+        // again.
+        rules.sync(2);
+        select_profile(&store, &window, &history, &rules);
+        assert_eq!(rules.take_diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn compiled_rules_are_reused_until_the_revision_changes() {
+        let record = make_record(
+            "Editor",
+            vec![make_rule(
+                ActivationMatchMode::WindowTitle,
+                Some(r"regex:^Editor - \w+$"),
+            )],
+        );
+        let rules = RuleCache::default();
+        rules.sync(1);
+        let first = rules.rules_for(&record);
+        assert!(Arc::ptr_eq(&first, &rules.rules_for(&record)));
+
+        rules.sync(1);
+        assert!(Arc::ptr_eq(&first, &rules.rules_for(&record)));
+
+        rules.sync(2);
+        assert!(!Arc::ptr_eq(&first, &rules.rules_for(&record)));
+    }
+
+    /// Timing comparison; run with `--ignored`. Compiling every rule costs
+    /// far more than matching, so the cached selections must be at least
+    /// twice as fast.
+    #[test]
+    #[ignore = "benchmark; timings depend on the machine"]
+    fn selection_with_hundreds_of_profiles_reuses_compiled_rules() {
+        const PROFILES: usize = 400;
+        const ROUNDS: u32 = 20;
+
+        let mut store = empty_store();
+        for index in 0..PROFILES {
+            store.profiles.push(make_record(
+                &format!("Project {index}"),
+                vec![
+                    make_rule(
+                        ActivationMatchMode::WindowTitle,
+                        Some(&format!(r"regex:^Project {index} - \w+ \((Editor|IDE)\)$")),
+                    ),
+                    make_rule(
+                        ActivationMatchMode::ProcessName,
+                        Some(&format!("tool-{index}.exe")),
+                    ),
+                ],
+            ));
+        }
+        let history = Arc::new(Mutex::new(HashMap::new()));
+        let last = PROFILES - 1;
+        let window = snapshot(None, Some(&format!("Project {last} - main (IDE)")));
+
+        let started = std::time::Instant::now();
+        for _ in 0..ROUNDS {
+            let result = select_profile(&store, &window, &history, &RuleCache::default());
+            assert_eq!(result.map(|profile| profile.index), Some(last));
+        }
+        let uncached = started.elapsed() / ROUNDS;
+
+        let rules = RuleCache::default();
+        rules.sync(0);
+        select_profile(&store, &window, &history, &rules);
+        let started = std::time::Instant::now();
+        for _ in 0..ROUNDS {
+            let result = select_profile(&store, &window, &history, &rules);
+            assert_eq!(result.map(|profile| profile.index), Some(last));
+        }
+        let cached = started.elapsed() / ROUNDS;
+
+        assert!(
+            cached * 2 < uncached,
+            "{PROFILES} profiles: {cached:?} per selection cached, {uncached:?} uncached"
+        );
+    }
+
     #[test]
     fn select_profile_falls_back_to_first_enabled() {
         let state = Arc::new(Mutex::new(None));
@@ -870,9 +1388,11 @@ mod tests {
         let now = OffsetDateTime::now_utc();
         let profile = Some(ActiveProfileSnapshot {
             index: 0,
+            profile_id: ProfileId::new(),
             name: "Default".into(),
             match_kind: MatchKind::Fallback,
             hold_to_open: false,
+            priority: 0,
             selector_score: Some(0),
             matched_rule: Some("fallback".into()),
             selected_at: None,
@@ -894,4 +1414,33 @@ mod tests {
         let notification = update_active_profile(&state, &history, current, now).unwrap();
         assert!(notification.is_none());
     }
+
+    // The paused clock advances only while every task is idle, so the
+    // timings below are exact rather than wall-clock estimates.
+    #[tokio::test(start_paused = true)]
+    async fn settle_collapses_flicker_but_not_forever() {
+        let flicker = |count: u32, every: u64| {
+            let wake = Arc::new(Notify::new());
+            let notifier = wake.clone();
+            tokio::spawn(async move {
+                for _ in 0..count {
+                    tokio::time::sleep(Duration::from_millis(every)).await;
+                    notifier.notify_one();
+                }
+            });
+            wake
+        };
+
+        // Four changes 40ms apart settle 120ms after the last one.
+        let wake = flicker(4, 40);
+        let started = Instant::now();
+        settle(&wake).await;
+        assert_eq!(started.elapsed(), Duration::from_millis(280));
+
+        // Endless flicker is cut off.
+        let wake = flicker(100, 30);
+        let started = Instant::now();
+        settle(&wake).await;
+        assert_eq!(started.elapsed(), MAX_SETTLE_DELAY);
+    }
 }
@@ -29,7 +29,7 @@ pub struct CursorPosition {
     pub y: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct ScreenAreaSnapshot {
     pub x: i32,
@@ -0,0 +1,421 @@
+//! `{{...}}` placeholders in action arguments, resolved when the action runs.
+//!
+//! Supported variables:
+//!
+//! | Placeholder          | Value                                          |
+//! |----------------------|------------------------------------------------|
+//! | `{{window.title}}`   | title of the foreground window                 |
+//! | `{{window.class}}`   | class of the foreground window                 |
+//! | `{{process.name}}`   | process owning the foreground window           |
+//! | `{{profile.name}}`   | active profile                                 |
+//! | `{{clipboard}}`      | clipboard text                                 |
+//! | `{{env.NAME}}`       | environment variable `NAME`                    |
+//! | `{{date}}`           | local date as `%Y-%m-%d`                       |
+//! | `{{date:FORMAT}}`    | local time formatted with strftime `FORMAT`    |
+//! | `{{steps.NAME.FIELD}}` | result of the composite step named `NAME`    |
+//!
+//! Step fields are `status`, `message`, `stdout`, `stderr`, `exitCode` and
+//! `httpStatus`; trailing newlines are stripped from the streams as a shell
+//! `$(...)` would.
+//! Values that are not available, including steps that have not run, expand
+//! to an empty string. Braces that do
+//! not name one of the roots above are left untouched, so SendKeys escapes
+//! such as `{{}` keep working.
+
+use crate::domain::{ActionEventStatus, ActionOutput, ProfileId};
+use crate::services::clipboard::ClipboardBackend;
+use crate::services::system_status::WindowSnapshot;
+use chrono::format::StrftimeItems;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const STEP_FIELDS: [&str; 6] = [
+    "status",
+    "message",
+    "stdout",
+    "stderr",
+    "exitCode",
+    "httpStatus",
+];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("unknown template variable '{0}'")]
+    UnknownVariable(String),
+    #[error("invalid date format '{0}'")]
+    InvalidDateFormat(String),
+}
+
+/// How a substituted value is quoted for the place it ends up in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Escaping {
+    /// Inserted verbatim; used for argv entries that never reach a shell.
+    Raw,
+    /// A single-quoted POSIX shell word.
+    Posix,
+    /// A single-quoted PowerShell string.
+    PowerShell,
+    /// Caret-escaped `cmd.exe` metacharacters.
+    Cmd,
+    /// A Python string literal.
+    Python,
+    /// A JavaScript string literal.
+    JavaScript,
+    /// SendKeys text, with modifier and brace characters typed literally.
+    SendKeys,
+    /// A JSON string literal.
+    Json,
+    /// Percent-encoded, for URLs and form bodies.
+    Url,
+}
+
+impl Escaping {
+    /// Escaping for the shell that runs `SystemCommand` payloads.
+    pub fn system_shell() -> Self {
+        if cfg!(target_os = "windows") {
+            Self::Cmd
+        } else {
+            Self::Posix
+        }
+    }
+
+    pub fn apply(self, value: &str) -> String {
+        match self {
+            Self::Raw => value.to_string(),
+            Self::Posix => format!("'{}'", value.replace('\'', r"'\''")),
+            Self::PowerShell => {
+                let mut quoted = String::with_capacity(value.len() + 2);
+                quoted.push('\'');
+                for ch in value.chars() {
+                    // PowerShell also closes strings on typographic quotes.
+                    if matches!(ch, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+                        quoted.push(ch);
+                    }
+                    quoted.push(ch);
+                }
+                quoted.push('\'');
+                quoted
+            }
+            Self::Cmd => {
+                let mut escaped = String::with_capacity(value.len());
+                for ch in value.chars() {
+                    if matches!(
+                        ch,
+                        '^' | '&' | '|' | '<' | '>' | '(' | ')' | '%' | '!' | '"'
+                    ) {
+                        escaped.push('^');
+                    }
+                    escaped.push(ch);
+                }
+                escaped
+            }
+            Self::Python | Self::JavaScript | Self::Json => {
+                serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+            }
+            Self::SendKeys => {
+                let mut escaped = String::with_capacity(value.len());
+                for ch in value.chars() {
+                    if matches!(ch, '^' | '+' | '!' | '#' | '{' | '}') {
+                        escaped.push('{');
+                        escaped.push(ch);
+                        escaped.push('}');
+                    } else {
+                        escaped.push(ch);
+                    }
+                }
+                escaped
+            }
+            Self::Url => {
+                let mut encoded = String::with_capacity(value.len());
+                for byte in value.bytes() {
+                    if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+                        encoded.push(char::from(byte));
+                    } else {
+                        encoded.push_str(&format!("%{byte:02X}"));
+                    }
+                }
+                encoded
+            }
+        }
+    }
+}
+
+/// Result of a named composite step.
+#[derive(Debug, Clone)]
+pub struct StepRecord {
+    pub status: ActionEventStatus,
+    pub message: Option<String>,
+    pub output: Option<ActionOutput>,
+}
+
+/// Values captured when a run starts, plus the results of named steps as
+/// they finish. The clipboard is read at most once, and only if a template
+/// asks for it; clipboard actions in the run update the cached text.
+#[derive(Default)]
+pub struct TemplateContext {
+    pub window: WindowSnapshot,
+    pub profile_name: Option<String>,
+    /// Recorded on the run's events; not available to templates.
+    pub profile_id: Option<ProfileId>,
+    clipboard: Option<Arc<dyn ClipboardBackend>>,
+    clipboard_text: Mutex<Option<Option<String>>>,
+    steps: Mutex<HashMap<String, StepRecord>>,
+    defer_steps: bool,
+}
+
+impl TemplateContext {
+    pub fn new(window: WindowSnapshot, profile_name: Option<String>) -> Self {
+        Self {
+            window,
+            profile_name,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_profile_id(mut self, profile_id: Option<ProfileId>) -> Self {
+        self.profile_id = profile_id;
+        self
+    }
+
+    pub fn with_clipboard(mut self, clipboard: Arc<dyn ClipboardBackend>) -> Self {
+        self.clipboard = Some(clipboard);
+        self
+    }
+
+    pub fn has_clipboard(&self) -> bool {
+        self.clipboard.is_some()
+    }
+
+    pub fn clipboard(&self) -> Option<Arc<dyn ClipboardBackend>> {
+        self.clipboard.clone()
+    }
+
+    /// Records clipboard text set during the run so later `{{clipboard}}`
+    /// placeholders see it without reading the clipboard again.
+    pub fn note_clipboard(&self, text: Option<String>) {
+        *self.clipboard_text.lock() = Some(text);
+    }
+
+    /// Leaves `{{steps.*}}` placeholders as written instead of resolving
+    /// them, for plans built before any step has run.
+    pub fn deferring_steps(mut self) -> Self {
+        self.defer_steps = true;
+        self
+    }
+
+    /// Stores a step result for later templates and conditions. A later
+    /// step with the same name replaces it.
+    pub fn record_step(&self, name: &str, record: StepRecord) {
+        self.steps.lock().insert(name.to_string(), record);
+    }
+
+    pub fn step(&self, name: &str) -> Option<StepRecord> {
+        self.steps.lock().get(name).cloned()
+    }
+
+    /// Replaces every placeholder in `template`, quoting each value with
+    /// `escaping`. Text outside placeholders is never modified.
+    pub fn render(&self, template: &str, escaping: Escaping) -> Result<String, TemplateError> {
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            rendered.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("}}") else {
+                rest = &rest[start..];
+                break;
+            };
+            let name = after[..end].trim();
+            match self.resolve(name)? {
+                Some(value) => {
+                    rendered.push_str(&escaping.apply(&value));
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    rendered.push_str("{{");
+                    rest = after;
+                }
+            }
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+
+    /// `Ok(None)` means `name` is not a placeholder and should stay as text.
+    fn resolve(&self, name: &str) -> Result<Option<String>, TemplateError> {
+        let unknown = || TemplateError::UnknownVariable(name.to_string());
+        let (root, field) = match name.find(['.', ':']) {
+            Some(index) => (&name[..index], Some(&name[index..])),
+            None => (name, None),
+        };
+
+        let value = match (root, field) {
+            ("window", Some(".title")) => self.window.window_title.clone(),
+            ("window", Some(".class")) => self.window.window_class.clone(),
+            ("process", Some(".name")) => self.window.process_name.clone(),
+            ("profile", Some(".name")) => self.profile_name.clone(),
+            ("clipboard", None) => self.clipboard_text(),
+            ("env", Some(field)) => {
+                let var = field.strip_prefix('.').ok_or_else(unknown)?;
+                if !is_env_name(var) {
+                    return Err(unknown());
+                }
+                std::env::var(var).ok()
+            }
+            ("date", None) => Some(format_date(DEFAULT_DATE_FORMAT)?),
+            ("date", Some(field)) => {
+                let format = field.strip_prefix(':').ok_or_else(unknown)?;
+                Some(format_date(format)?)
+            }
+            ("steps", Some(field)) => {
+                let (step, property) = field
+                    .strip_prefix('.')
+                    .and_then(|rest| rest.rsplit_once('.'))
+                    .ok_or_else(unknown)?;
+                if !STEP_FIELDS.contains(&property) {
+                    return Err(unknown());
+                }
+                if self.defer_steps {
+                    return Ok(None);
+                }
+                let record = self.step(step);
+                let output = record.as_ref().and_then(|record| record.output.as_ref());
+                match property {
+                    "status" => record.and_then(|record| {
+                        serde_json::to_value(record.status)
+                            .ok()
+                            .and_then(|value| value.as_str().map(str::to_string))
+                    }),
+                    "message" => record.and_then(|record| record.message),
+                    "stdout" => output.map(|output| trim_newlines(&output.stdout)),
+                    "stderr" => output.map(|output| trim_newlines(&output.stderr)),
+                    "exitCode" => output
+                        .and_then(|output| output.exit_code)
+                        .map(|code| code.to_string()),
+                    "httpStatus" => output
+                        .and_then(|output| output.http_status)
+                        .map(|status| status.to_string()),
+                    _ => None,
+                }
+            }
+            ("window" | "process" | "profile" | "clipboard" | "env" | "steps", _) => {
+                return Err(unknown())
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(value.unwrap_or_default()))
+    }
+
+    fn clipboard_text(&self) -> Option<String> {
+        self.clipboard_text
+            .lock()
+            .get_or_insert_with(|| {
+                self.clipboard
+                    .as_ref()
+                    .and_then(|clipboard| clipboard.read_text().ok().flatten())
+            })
+            .clone()
+    }
+}
+
+fn trim_newlines(value: &str) -> String {
+    value.trim_end_matches(['\r', '\n']).to_string()
+}
+
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(ch) if ch.is_ascii_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+fn format_date(format: &str) -> Result<String, TemplateError> {
+    let items = StrftimeItems::new(format)
+        .parse()
+        .map_err(|_| TemplateError::InvalidDateFormat(format.to_string()))?;
+    Ok(chrono::Local::now()
+        .format_with_items(items.iter())
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::clipboard::MemoryClipboard;
+
+    fn context() -> TemplateContext {
+        let window = WindowSnapshot {
+            window_title: Some("it's \"done\"".into()),
+            process_name: Some("code".into()),
+            ..WindowSnapshot::default()
+        };
+        let clipboard = Arc::new(MemoryClipboard::default());
+        clipboard.write_text("copied & pasted").expect("write");
+        TemplateContext::new(window, Some("Work".into())).with_clipboard(clipboard)
+    }
+
+    #[test]
+    fn renders_variables_with_per_language_escaping() {
+        let context = context();
+        let template = "echo {{window.title}}";
+        assert_eq!(
+            context.render(template, Escaping::Posix).unwrap(),
+            r#"echo 'it'\''s "done"'"#
+        );
+        assert_eq!(
+            context.render(template, Escaping::PowerShell).unwrap(),
+            r#"echo 'it''s "done"'"#
+        );
+        assert_eq!(
+            context.render(template, Escaping::Python).unwrap(),
+            r#"echo "it's \"done\"""#
+        );
+        assert_eq!(
+            context
+                .render("{{ clipboard }} in {{profile.name}}", Escaping::Cmd)
+                .unwrap(),
+            "copied ^& pasted in Work"
+        );
+        assert_eq!(
+            context
+                .render("{{process.name}}/{{window.class}}", Escaping::Raw)
+                .unwrap(),
+            "code/"
+        );
+        assert_eq!(
+            context.render("^a{{env.PATH}}", Escaping::Raw).unwrap(),
+            format!("^a{}", std::env::var("PATH").unwrap_or_default())
+        );
+
+        let date = context.render("{{date:%Y}}", Escaping::Raw).unwrap();
+        assert_eq!(date, chrono::Local::now().format("%Y").to_string());
+        assert_eq!(context.render("{{date}}", Escaping::Raw).unwrap().len(), 10);
+    }
+
+    #[test]
+    fn leaves_unrelated_braces_and_rejects_bad_placeholders() {
+        let context = context();
+        assert_eq!(
+            context.render("{{}{}} {{user}} {{", Escaping::Raw).unwrap(),
+            "{{}{}} {{user}} {{"
+        );
+        assert_eq!(
+            context.render("{{window.size}}", Escaping::Raw),
+            Err(TemplateError::UnknownVariable("window.size".into()))
+        );
+        assert_eq!(
+            context.render("{{env.A-B}}", Escaping::Raw),
+            Err(TemplateError::UnknownVariable("env.A-B".into()))
+        );
+        assert_eq!(
+            context.render("{{date:%Q}}", Escaping::Raw),
+            Err(TemplateError::InvalidDateFormat("%Q".into()))
+        );
+        assert_eq!(Escaping::SendKeys.apply("a+b {x}"), "a{+}b {{}x{}}");
+        assert_eq!(Escaping::Url.apply("a b&c/ü"), "a%20b%26c%2F%C3%BC");
+    }
+}
@@ -1,14 +1,18 @@
 #[cfg(feature = "tray-icon")]
 use tauri::{
-    menu::{IconMenuItemBuilder, Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder},
+    menu::{
+        CheckMenuItemBuilder, IconMenuItemBuilder, Menu, MenuBuilder, MenuItemBuilder,
+        SubmenuBuilder,
+    },
     tray::{ClickType, MouseButton, TrayIcon, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Emitter, Manager, Runtime,
+    AppHandle, Emitter, Listener, Manager, Runtime,
 };
 
 #[cfg(feature = "tray-icon")]
 use crate::{
     commands::{self, profiles::ProfileStore, AppState, SystemState},
-    services::profile_router,
+    domain::TriggerId,
+    services::{localization, profile_router, triggers},
     storage::profile_repository::ProfileId,
 };
 
@@ -29,6 +33,9 @@ const PROFILE_SUBMENU_ID: &str = "tray.profiles";
 #[cfg(feature = "tray-icon")]
 const PROFILE_MENU_PREFIX: &str = "tray.profile.";
 
+#[cfg(feature = "tray-icon")]
+const TRIGGER_MENU_PREFIX: &str = "tray.trigger.";
+
 #[cfg(feature = "tray-icon")]
 const PROFILE_REFRESH_ID: &str = "tray.profiles.refresh";
 
@@ -81,12 +88,11 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
     fn handle_menu_event<R: Runtime>(tray: &TrayIcon<R>, event: tauri::menu::MenuEvent) {
         let id = event.id().as_ref().to_string();
         let app = tray.app_handle().clone();
+        let tray = tray.clone();
         tauri::async_runtime::spawn(async move {
             if id == PROFILE_REFRESH_ID {
-                if let Some(controller) = TRAY_STATE.lock().await.clone() {
-                    if let Err(error) = controller.rebuild_menu(&app).await {
-                        eprintln!("failed to refresh profile tray menu: {error}");
-                    }
+                if let Err(error) = TrayController::sync_menu(&tray).await {
+                    eprintln!("failed to refresh profile tray menu: {error}");
                 }
                 return;
             }
@@ -107,6 +113,29 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
                         eprintln!("failed to activate profile from tray: {error}");
                     }
                 }
+                return;
+            }
+
+            if let Some(stripped) = id.strip_prefix(TRIGGER_MENU_PREFIX) {
+                if let Ok(uuid) = uuid::Uuid::parse_str(stripped) {
+                    let trigger_id = TriggerId::from(uuid);
+                    let state: tauri::State<'_, AppState> = app.state();
+                    let enabled = state
+                        .profiles_snapshot()
+                        .ok()
+                        .and_then(|store| {
+                            store
+                                .triggers
+                                .into_iter()
+                                .find(|trigger| trigger.id == trigger_id)
+                        })
+                        .map(|trigger| trigger.enabled);
+                    if let Some(enabled) = enabled {
+                        if let Err(error) = triggers::set_enabled(&app, trigger_id, !enabled) {
+                            eprintln!("failed to toggle trigger from tray: {error}");
+                        }
+                    }
+                }
             }
         });
     }
@@ -136,11 +165,7 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
         }
 
         async fn initialize<R: Runtime>(tray: &TrayIcon<R>) -> tauri::Result<()> {
-            if let Some(controller) = TRAY_STATE.lock().await.clone() {
-                controller.refresh_store().await?;
-                let menu = controller.rebuild_menu(tray.app_handle()).await?;
-                tray.set_menu(Some(menu))?;
-            }
+            Self::sync_menu(tray).await?;
 
             #[cfg(target_os = "macos")]
             {
@@ -155,6 +180,16 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
             Ok(())
         }
 
+        /// Reloads the cached store and installs a freshly built menu.
+        async fn sync_menu<R: Runtime>(tray: &TrayIcon<R>) -> tauri::Result<()> {
+            if let Some(controller) = TRAY_STATE.lock().await.clone() {
+                controller.refresh_store().await?;
+                let menu = controller.rebuild_menu(tray.app_handle()).await?;
+                tray.set_menu(Some(menu))?;
+            }
+            Ok(())
+        }
+
         async fn refresh_store(&self) -> tauri::Result<()> {
             let state: tauri::State<'_, commands::AppState> = self.app_handle.state();
             let snapshot = state
@@ -210,7 +245,24 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
                 )
                 .build()?;
 
-            menu = menu.submenu(&submenu)?.separator();
+            menu = menu.submenu(&submenu)?;
+
+            if !store.triggers.is_empty() {
+                let mut triggers_submenu =
+                    SubmenuBuilder::new(app, localization::text("tray.menu.triggers", "Triggers"));
+                for trigger in &store.triggers {
+                    let item = CheckMenuItemBuilder::with_id(
+                        format!("{TRIGGER_MENU_PREFIX}{}", trigger.id),
+                        trigger.name.clone(),
+                    )
+                    .checked(trigger.enabled)
+                    .build(app)?;
+                    triggers_submenu = triggers_submenu.item(&item);
+                }
+                menu = menu.submenu(&triggers_submenu.build()?)?;
+            }
+
+            menu = menu.separator();
 
             Ok(menu.build()?)
         }
@@ -220,6 +272,18 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<()> {
 
     let tray_icon = builder.build(app)?;
 
+    // Trigger toggles change outside the tray too (UI, schedules); keep the
+    // checkmarks in step with the store.
+    let listener_tray = tray_icon.clone();
+    app.listen_any(triggers::TRIGGERS_CHANGED_EVENT, move |_| {
+        let tray = listener_tray.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(error) = TrayController::sync_menu(&tray).await {
+                eprintln!("failed to refresh trigger tray menu: {error}");
+            }
+        });
+    });
+
     tauri::async_runtime::spawn(async move {
         if let Err(error) = ensure_tray_state(&tray_icon.app_handle())
             .await
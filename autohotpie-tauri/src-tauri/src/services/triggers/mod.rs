@@ -0,0 +1,349 @@
+//! Runs actions from triggers: cron schedules, profile switches, app startup
+//! and file changes. Trigger runs go through the execution policy without
+//! confirmation, so actions that need one are blocked.
+
+mod schedule;
+
+pub use schedule::{Schedule, ScheduleError};
+
+use crate::commands::{actions::template_context, AppError, AppState, SystemState};
+use crate::domain::{ProfileId, Trigger, TriggerEvent, TriggerId};
+use crate::services::profile_router::{ActiveProfileEvent, ProfileRouterState, PROFILE_EVENT};
+use crate::storage::profile_repository::ProfileStore;
+use chrono::{Local, Timelike};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Listener, Manager, Runtime};
+use uuid::Uuid;
+
+pub const TRIGGERS_CHANGED_EVENT: &str = "triggers://changed";
+/// Changes to one trigger's path closer together than this fire it once.
+const FILE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub struct TriggerState {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    active_profile: Mutex<Option<ProfileId>>,
+    last_file_run: Mutex<HashMap<TriggerId, Instant>>,
+}
+
+/// Fires the startup triggers and starts listening for the others. Expects
+/// [`TriggerState`] to be managed.
+pub fn start<R: Runtime>(app: AppHandle<R>) {
+    let handle = app.clone();
+    app.listen_any(PROFILE_EVENT, move |event| {
+        match serde_json::from_str::<ActiveProfileEvent>(event.payload()) {
+            Ok(event) => profile_changed(&handle, event),
+            Err(err) => eprintln!("failed to read profile change for triggers: {err}"),
+        }
+    });
+    if let Err(err) = reload_watchers(&app) {
+        eprintln!("failed to watch trigger paths: {err}");
+    }
+    fire_matching(&app, |on| matches!(on, TriggerEvent::Startup));
+    tauri::async_runtime::spawn(run_schedules(app));
+}
+
+/// Checks triggers before they are stored: names are set, ids are unique,
+/// cron expressions parse, paths are set and actions exist.
+pub fn validate(triggers: &[Trigger], store: &ProfileStore) -> Result<(), AppError> {
+    let mut ids = HashSet::new();
+    for trigger in triggers {
+        let name = trigger.name.trim();
+        if name.is_empty() {
+            return Err(AppError::Message("trigger name must not be empty".into()));
+        }
+        if !ids.insert(trigger.id) {
+            return Err(AppError::Message(format!(
+                "trigger id {} is used more than once",
+                trigger.id
+            )));
+        }
+        match &trigger.on {
+            TriggerEvent::Schedule { cron } => {
+                Schedule::parse(cron)
+                    .map_err(|err| AppError::Message(format!("trigger '{name}': {err}")))?;
+            }
+            TriggerEvent::FileChanged { path, .. } if path.trim().is_empty() => {
+                return Err(AppError::Message(format!(
+                    "trigger '{name}' has no path to watch"
+                )));
+            }
+            _ => {}
+        }
+        if store.find_action(None, &trigger.action).is_none() {
+            return Err(AppError::Message(format!(
+                "trigger '{name}' runs action {} which does not exist",
+                trigger.action
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn set_enabled<R: Runtime>(
+    app: &AppHandle<R>,
+    id: TriggerId,
+    enabled: bool,
+) -> Result<Trigger, AppError> {
+    let state = app.state::<AppState>();
+    let trigger = state.with_profiles_mut(|store| {
+        let trigger = store
+            .triggers
+            .iter_mut()
+            .find(|trigger| trigger.id == id)
+            .ok_or_else(|| AppError::Message(format!("trigger {id} not found")))?;
+        trigger.enabled = enabled;
+        Ok(trigger.clone())
+    })?;
+    let verb = if enabled { "enabled" } else { "disabled" };
+    state
+        .audit()
+        .log("INFO", &format!("Trigger '{}' {verb}", trigger.name))?;
+    triggers_changed(app);
+    Ok(trigger)
+}
+
+/// Re-arms the file watchers and tells the UI. Call after the stored
+/// triggers change.
+pub fn triggers_changed<R: Runtime>(app: &AppHandle<R>) {
+    if let Err(err) = reload_watchers(app) {
+        eprintln!("failed to watch trigger paths: {err}");
+    }
+    match app.state::<AppState>().profiles_snapshot() {
+        Ok(store) => {
+            if let Err(err) = app.emit(TRIGGERS_CHANGED_EVENT, store.triggers) {
+                eprintln!("failed to emit {TRIGGERS_CHANGED_EVENT}: {err}");
+            }
+        }
+        Err(err) => eprintln!("failed to read triggers: {err}"),
+    }
+}
+
+fn profile_changed<R: Runtime>(app: &AppHandle<R>, event: ActiveProfileEvent) {
+    let next = event.profile.map(|snapshot| snapshot.profile_id);
+    let previous = std::mem::replace(
+        &mut *app.state::<TriggerState>().active_profile.lock(),
+        next,
+    );
+    if previous != next {
+        fire_matching(app, |on| fires_on_switch(on, previous, next));
+    }
+}
+
+fn fires_on_switch(
+    on: &TriggerEvent,
+    previous: Option<ProfileId>,
+    next: Option<ProfileId>,
+) -> bool {
+    match on {
+        TriggerEvent::ProfileActivated { profile } => next == Some(*profile),
+        TriggerEvent::ProfileDeactivated { profile } => previous == Some(*profile),
+        _ => false,
+    }
+}
+
+/// Checks the schedules once a minute, just after the minute starts.
+async fn run_schedules<R: Runtime>(app: AppHandle<R>) {
+    let mut last_minute = None;
+    loop {
+        let now = Local::now();
+        let into_minute = u64::from(now.second()) * 1000 + u64::from(now.timestamp_subsec_millis());
+        let wait = 60_000u64.saturating_sub(into_minute).max(1) + 50;
+        tokio::time::sleep(Duration::from_millis(wait)).await;
+
+        let now = Local::now();
+        let minute = now.with_second(0).and_then(|now| now.with_nanosecond(0));
+        if minute == last_minute {
+            continue;
+        }
+        last_minute = minute;
+        fire_matching(&app, |on| match on {
+            TriggerEvent::Schedule { cron } => {
+                Schedule::parse(cron).is_ok_and(|schedule| schedule.matches(&now))
+            }
+            _ => false,
+        });
+    }
+}
+
+/// Watches the paths of the enabled `file_changed` triggers, replacing the
+/// previous watcher.
+fn reload_watchers<R: Runtime>(app: &AppHandle<R>) -> Result<(), AppError> {
+    let state = app.state::<AppState>();
+    let data_dir = state.storage().base_dir().to_path_buf();
+    let triggers = state.profiles_snapshot()?.triggers;
+    let watched: Vec<(PathBuf, bool)> = triggers
+        .iter()
+        .filter(|trigger| trigger.enabled)
+        .filter_map(|trigger| match &trigger.on {
+            TriggerEvent::FileChanged { path, recursive } => {
+                Some((resolve_path(&data_dir, path), *recursive))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let slot = &app.state::<TriggerState>().inner().watcher;
+    // Drop the old watcher first so its callbacks stop.
+    *slot.lock() = None;
+    if watched.is_empty() {
+        return Ok(());
+    }
+    let handle = app.clone();
+    let mut watcher = notify::recommended_watcher(move |res| match res {
+        Ok(event) => file_changed(&handle, event),
+        Err(err) => eprintln!("trigger watcher error: {err}"),
+    })
+    .map_err(|err| AppError::Message(format!("failed to start trigger watcher: {err}")))?;
+    for (path, recursive) in watched {
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        if let Err(err) = watcher.watch(&path, mode) {
+            eprintln!("failed to watch {} for triggers: {err}", path.display());
+        }
+    }
+    *slot.lock() = Some(watcher);
+    Ok(())
+}
+
+fn file_changed<R: Runtime>(app: &AppHandle<R>, event: notify::Event) {
+    if !matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        return;
+    }
+    let state = app.state::<AppState>();
+    let data_dir = state.storage().base_dir().to_path_buf();
+    let Ok(store) = state.profiles_snapshot() else {
+        return;
+    };
+
+    let now = Instant::now();
+    let mut last_run = app.state::<TriggerState>().inner().last_file_run.lock();
+    for trigger in store.triggers.into_iter().filter(|trigger| trigger.enabled) {
+        let TriggerEvent::FileChanged { path, recursive } = &trigger.on else {
+            continue;
+        };
+        let watched = resolve_path(&data_dir, path);
+        if !event
+            .paths
+            .iter()
+            .any(|changed| covers(&watched, *recursive, changed))
+        {
+            continue;
+        }
+        if last_run
+            .get(&trigger.id)
+            .is_some_and(|at| now.duration_since(*at) < FILE_DEBOUNCE)
+        {
+            continue;
+        }
+        last_run.insert(trigger.id, now);
+        fire(app.clone(), trigger);
+    }
+}
+
+fn resolve_path(data_dir: &Path, path: &str) -> PathBuf {
+    let path = PathBuf::from(path.trim());
+    if path.is_absolute() {
+        path
+    } else {
+        data_dir.join(path)
+    }
+}
+
+/// Whether a change to `changed` concerns a trigger watching `watched`.
+fn covers(watched: &Path, recursive: bool, changed: &Path) -> bool {
+    changed == watched
+        || changed.parent() == Some(watched)
+        || (recursive && changed.starts_with(watched))
+}
+
+/// Runs the action of every enabled trigger whose event `selects` accepts.
+fn fire_matching<R: Runtime>(app: &AppHandle<R>, selects: impl Fn(&TriggerEvent) -> bool) {
+    let triggers = match app.state::<AppState>().profiles_snapshot() {
+        Ok(store) => store.triggers,
+        Err(err) => {
+            eprintln!("failed to read triggers: {err}");
+            return;
+        }
+    };
+    for trigger in triggers
+        .into_iter()
+        .filter(|trigger| trigger.enabled && selects(&trigger.on))
+    {
+        fire(app.clone(), trigger);
+    }
+}
+
+fn fire<R: Runtime>(app: AppHandle<R>, trigger: Trigger) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = run_trigger(&app, &trigger).await {
+            eprintln!("trigger '{}' failed: {err}", trigger.name);
+        }
+    });
+}
+
+async fn run_trigger<R: Runtime>(app: &AppHandle<R>, trigger: &Trigger) -> Result<(), AppError> {
+    let state = app.state::<AppState>();
+    // Triggers belong to no profile and run shared library actions only.
+    let actions = state.actions_for(None);
+    let action = state
+        .lookup_action(None, &trigger.action)
+        .ok_or_else(|| AppError::Message(format!("action {} not found", trigger.action)))?;
+    state
+        .audit()
+        .log("INFO", &format!("Trigger '{}' fired", trigger.name))?;
+    state.action_runner.authorize(&action, &actions, false)?;
+    let context = template_context(
+        &state,
+        &app.state::<SystemState>(),
+        &app.state::<ProfileRouterState>(),
+    );
+    state
+        .action_runner
+        .run_with_invocation(&action, &actions, Uuid::new_v4(), context)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_switches_fire_activation_and_deactivation() {
+        let work = ProfileId::new();
+        let home = ProfileId::new();
+        let activated = TriggerEvent::ProfileActivated { profile: work };
+        let deactivated = TriggerEvent::ProfileDeactivated { profile: work };
+
+        assert!(fires_on_switch(&activated, Some(home), Some(work)));
+        assert!(!fires_on_switch(&deactivated, Some(home), Some(work)));
+        assert!(fires_on_switch(&deactivated, Some(work), None));
+        assert!(!fires_on_switch(&activated, Some(work), Some(home)));
+        assert!(!fires_on_switch(&TriggerEvent::Startup, None, Some(work)));
+    }
+
+    #[test]
+    fn file_triggers_cover_direct_children_unless_recursive() {
+        let watched = Path::new("/data/inbox");
+        assert!(covers(watched, false, Path::new("/data/inbox")));
+        assert!(covers(watched, false, Path::new("/data/inbox/new.txt")));
+        assert!(!covers(
+            watched,
+            false,
+            Path::new("/data/inbox/2026/new.txt")
+        ));
+        assert!(covers(watched, true, Path::new("/data/inbox/2026/new.txt")));
+        assert!(!covers(watched, true, Path::new("/data/inbox-old/new.txt")));
+    }
+}
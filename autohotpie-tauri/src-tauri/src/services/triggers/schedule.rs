@@ -0,0 +1,158 @@
+//! Five-field cron expressions for `schedule` triggers.
+
+use chrono::{Datelike, Timelike};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid cron expression '{expression}': {reason}")]
+pub struct ScheduleError {
+    expression: String,
+    reason: String,
+}
+
+/// A parsed `minute hour day-of-month month day-of-week` expression. Fields
+/// take `*`, numbers, ranges (`1-5`), steps (`*/15`, `0-30/10`) and
+/// comma-separated lists; day-of-week 0 and 7 are both Sunday. As in cron,
+/// when both day fields are restricted a day matches if either does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Schedule {
+    pub fn parse(expression: &str) -> Result<Self, ScheduleError> {
+        let error = |reason: String| ScheduleError {
+            expression: expression.to_string(),
+            reason,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(error(format!("expected 5 fields, found {}", fields.len())));
+        }
+        let field =
+            |index: usize, min: u32, max: u32| parse_field(fields[index], min, max).map_err(error);
+
+        let mut weekdays = field(4, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: field(0, 0, 59)?,
+            hours: field(1, 0, 23)?,
+            days: field(2, 1, 31)?,
+            months: field(3, 1, 12)?,
+            weekdays,
+            any_day: fields[2].starts_with('*'),
+            any_weekday: fields[4].starts_with('*'),
+        })
+    }
+
+    /// Whether the schedule fires in the minute of `time`.
+    pub fn matches<T: Datelike + Timelike>(&self, time: &T) -> bool {
+        let contains = |set: u64, value: u32| set & (1 << value) != 0;
+        let day = contains(self.days, time.day());
+        let weekday = contains(self.weekdays, time.weekday().num_days_from_sunday());
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+        contains(self.minutes, time.minute())
+            && contains(self.hours, time.hour())
+            && contains(self.months, time.month())
+            && day_matches
+    }
+}
+
+/// Bit set of the values `field` selects.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step in '{part}'"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (number(start, min, max)?, number(end, min, max)?)
+        } else {
+            // `5/15` means every 15 starting at 5.
+            let start = number(range, min, max)?;
+            (start, if step > 1 { max } else { start })
+        };
+        if start > end {
+            return Err(format!("range '{range}' is reversed"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+fn number(value: &str, min: u32, max: u32) -> Result<u32, String> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|value| (min..=max).contains(value))
+        .ok_or_else(|| format!("'{value}' is not a number from {min} to {max}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> chrono::NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_opt(hour, minute, 0))
+            .expect("valid time")
+    }
+
+    #[test]
+    fn fields_combine_like_cron() {
+        // Every 15 minutes during office hours on weekdays.
+        let office = Schedule::parse("*/15 9-17 * * 1-5").expect("parse");
+        assert!(office.matches(&at(2026, 10, 16, 9, 45)));
+        assert!(!office.matches(&at(2026, 10, 16, 9, 50)));
+        assert!(!office.matches(&at(2026, 10, 16, 18, 0)));
+        assert!(!office.matches(&at(2026, 10, 17, 10, 0)));
+
+        // Restricting both day fields matches either: the 1st, or Sundays.
+        let either = Schedule::parse("0 0 1 * 7").expect("parse");
+        assert!(either.matches(&at(2026, 10, 1, 0, 0)));
+        assert!(either.matches(&at(2026, 10, 18, 0, 0)));
+        assert!(!either.matches(&at(2026, 10, 19, 0, 0)));
+
+        let listed = Schedule::parse("5/20,59 0 * 1,6 *").expect("parse");
+        assert!(listed.matches(&at(2026, 6, 3, 0, 45)));
+        assert!(listed.matches(&at(2026, 1, 3, 0, 59)));
+        assert!(!listed.matches(&at(2026, 2, 3, 0, 5)));
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        for expression in [
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(Schedule::parse(expression).is_err(), "{expression}");
+        }
+    }
+}
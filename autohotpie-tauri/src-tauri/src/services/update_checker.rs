@@ -77,6 +77,11 @@ impl UpdateChecker {
         })
     }
 
+    /// Shared HTTP client; `http_request` actions send through it too.
+    pub fn http_client(&self) -> Client {
+        self.client.clone()
+    }
+
     fn now() -> Instant {
         Instant::now()
     }
@@ -0,0 +1,398 @@
+use super::{process_name_matches, Rect, WindowBackend, WindowError, WindowId};
+use parking_lot::Mutex;
+use std::fs;
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::ConnectionExt as _;
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ClientMessageEvent, ConnectionExt as _, EventMask, Window,
+};
+use x11rb::rust_connection::RustConnection;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        WM_CHANGE_STATE,
+        _NET_ACTIVE_WINDOW,
+        _NET_CLIENT_LIST_STACKING,
+        _NET_CLOSE_WINDOW,
+        _NET_CURRENT_DESKTOP,
+        _NET_FRAME_EXTENTS,
+        _NET_MOVERESIZE_WINDOW,
+        _NET_WM_PID,
+        _NET_WM_STATE,
+        _NET_WM_STATE_ABOVE,
+        _NET_WM_STATE_MAXIMIZED_HORZ,
+        _NET_WM_STATE_MAXIMIZED_VERT,
+        _NET_WORKAREA,
+    }
+}
+
+const STATE_REMOVE: u32 = 0;
+const STATE_ADD: u32 = 1;
+const STATE_TOGGLE: u32 = 2;
+/// Requests are sent as a pager would, i.e. on the user's behalf, so window
+/// managers do not apply focus-stealing prevention to them.
+const SOURCE_PAGER: u32 = 2;
+const ICONIC_STATE: u32 = 3;
+const NORTH_WEST_GRAVITY: u32 = 1;
+const MOVERESIZE_ALL: u32 = 0xf << 8;
+
+struct EwmhSession {
+    conn: RustConnection,
+    root: Window,
+    screen: Rect,
+    atoms: Atoms,
+}
+
+impl EwmhSession {
+    fn connect() -> Result<Self, WindowError> {
+        let (conn, screen) =
+            x11rb::connect(None).map_err(|err| WindowError::Unavailable(err.to_string()))?;
+        let (root, size) = {
+            let screen = &conn.setup().roots[screen];
+            (
+                screen.root,
+                Rect::new(
+                    0,
+                    0,
+                    u32::from(screen.width_in_pixels),
+                    u32::from(screen.height_in_pixels),
+                ),
+            )
+        };
+        let atoms = Atoms::new(&conn)
+            .map_err(backend_error)?
+            .reply()
+            .map_err(backend_error)?;
+        Ok(Self {
+            conn,
+            root,
+            screen: size,
+            atoms,
+        })
+    }
+
+    fn cardinals(
+        &self,
+        window: Window,
+        property: Atom,
+        kind: impl Into<Atom>,
+    ) -> Result<Vec<u32>, WindowError> {
+        let reply = self
+            .conn
+            .get_property(false, window, property, kind, 0, 4096)
+            .map_err(backend_error)?
+            .reply()
+            .map_err(backend_error)?;
+        Ok(reply
+            .value32()
+            .map(|values| values.collect())
+            .unwrap_or_default())
+    }
+
+    /// Sends an EWMH client message about `window` to the window manager.
+    fn request(&self, window: WindowId, message: Atom, data: [u32; 5]) -> Result<(), WindowError> {
+        let event = ClientMessageEvent::new(32, window_id(window)?, message, data);
+        self.conn
+            .send_event(
+                false,
+                self.root,
+                EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                event,
+            )
+            .map_err(backend_error)?;
+        self.conn.flush().map_err(backend_error)?;
+        Ok(())
+    }
+
+    fn set_maximized(&self, window: WindowId, action: u32) -> Result<(), WindowError> {
+        self.request(
+            window,
+            self.atoms._NET_WM_STATE,
+            [
+                action,
+                self.atoms._NET_WM_STATE_MAXIMIZED_VERT,
+                self.atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+                SOURCE_PAGER,
+                0,
+            ],
+        )
+    }
+
+    /// Decoration sizes as `[left, right, top, bottom]`.
+    fn frame_extents(&self, window: Window) -> Result<[u32; 4], WindowError> {
+        let extents = self.cardinals(window, self.atoms._NET_FRAME_EXTENTS, AtomEnum::CARDINAL)?;
+        Ok(match extents.as_slice() {
+            [left, right, top, bottom, ..] => [*left, *right, *top, *bottom],
+            _ => [0; 4],
+        })
+    }
+
+    fn geometry(&self, window: WindowId) -> Result<Rect, WindowError> {
+        let window = window_id(window)?;
+        let geometry = self
+            .conn
+            .get_geometry(window)
+            .map_err(backend_error)?
+            .reply()
+            .map_err(backend_error)?;
+        let origin = self
+            .conn
+            .translate_coordinates(window, self.root, 0, 0)
+            .map_err(backend_error)?
+            .reply()
+            .map_err(backend_error)?;
+        let [left, right, top, bottom] = self.frame_extents(window)?;
+        Ok(Rect::new(
+            i32::from(origin.dst_x) - left as i32,
+            i32::from(origin.dst_y) - top as i32,
+            u32::from(geometry.width) + left + right,
+            u32::from(geometry.height) + top + bottom,
+        ))
+    }
+
+    fn set_geometry(&self, window: WindowId, bounds: Rect) -> Result<(), WindowError> {
+        let [left, right, top, bottom] = self.frame_extents(window_id(window)?)?;
+        // Window managers ignore moves of maximized windows.
+        self.set_maximized(window, STATE_REMOVE)?;
+        // With north-west gravity the position is the frame's corner and the
+        // size that of the client area.
+        self.request(
+            window,
+            self.atoms._NET_MOVERESIZE_WINDOW,
+            [
+                NORTH_WEST_GRAVITY | MOVERESIZE_ALL | (SOURCE_PAGER << 12),
+                bounds.x as u32,
+                bounds.y as u32,
+                bounds.width.saturating_sub(left + right).max(1),
+                bounds.height.saturating_sub(top + bottom).max(1),
+            ],
+        )
+    }
+
+    /// RandR monitors clipped to the current desktop's `_NET_WORKAREA`. The
+    /// work area is one rectangle for the whole desktop, so panels on inner
+    /// edges between monitors are not accounted for.
+    fn monitors(&self) -> Result<Vec<Rect>, WindowError> {
+        let monitors = match self
+            .conn
+            .randr_get_monitors(self.root, true)
+            .map_err(backend_error)
+            .and_then(|cookie| cookie.reply().map_err(backend_error))
+        {
+            Ok(reply) if !reply.monitors.is_empty() => reply
+                .monitors
+                .iter()
+                .map(|monitor| {
+                    Rect::new(
+                        i32::from(monitor.x),
+                        i32::from(monitor.y),
+                        u32::from(monitor.width),
+                        u32::from(monitor.height),
+                    )
+                })
+                .collect(),
+            _ => vec![self.screen],
+        };
+
+        let desktop = self
+            .cardinals(
+                self.root,
+                self.atoms._NET_CURRENT_DESKTOP,
+                AtomEnum::CARDINAL,
+            )?
+            .first()
+            .copied()
+            .unwrap_or(0) as usize;
+        let workareas = self.cardinals(self.root, self.atoms._NET_WORKAREA, AtomEnum::CARDINAL)?;
+        let Some(&[x, y, width, height]) = workareas
+            .chunks_exact(4)
+            .nth(desktop)
+            .or_else(|| workareas.chunks_exact(4).next())
+            .and_then(|area| <&[u32; 4]>::try_from(area).ok())
+        else {
+            return Ok(monitors);
+        };
+        let workarea = Rect::new(x as i32, y as i32, width, height);
+        Ok(monitors
+            .into_iter()
+            .map(|monitor| intersect(&monitor, &workarea).unwrap_or(monitor))
+            .collect())
+    }
+
+    fn find_by_process(&self, name: &str) -> Result<Option<WindowId>, WindowError> {
+        // Stacking order is bottom to top.
+        let clients = self.cardinals(
+            self.root,
+            self.atoms._NET_CLIENT_LIST_STACKING,
+            AtomEnum::WINDOW,
+        )?;
+        for window in clients.into_iter().rev() {
+            let Some(pid) = self
+                .cardinals(window, self.atoms._NET_WM_PID, AtomEnum::CARDINAL)?
+                .first()
+                .copied()
+            else {
+                continue;
+            };
+            if process_names(pid)
+                .iter()
+                .any(|candidate| process_name_matches(name, candidate))
+            {
+                return Ok(Some(WindowId::from(window)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Executable name and `comm` of a local process. `comm` is cut to 15
+/// bytes, so the executable is the more reliable of the two.
+pub(crate) fn process_names(pid: u32) -> Vec<String> {
+    let mut names = Vec::new();
+    if let Ok(exe) = fs::read_link(format!("/proc/{pid}/exe")) {
+        if let Some(name) = exe.file_name() {
+            names.push(name.to_string_lossy().into_owned());
+        }
+    }
+    if let Ok(comm) = fs::read_to_string(format!("/proc/{pid}/comm")) {
+        names.push(comm.trim_end().to_string());
+    }
+    names
+}
+
+fn intersect(a: &Rect, b: &Rect) -> Option<Rect> {
+    let left = a.x.max(b.x);
+    let top = a.y.max(b.y);
+    let right = (i64::from(a.x) + i64::from(a.width)).min(i64::from(b.x) + i64::from(b.width));
+    let bottom = (i64::from(a.y) + i64::from(a.height)).min(i64::from(b.y) + i64::from(b.height));
+    if right <= i64::from(left) || bottom <= i64::from(top) {
+        return None;
+    }
+    Some(Rect::new(
+        left,
+        top,
+        (right - i64::from(left)) as u32,
+        (bottom - i64::from(top)) as u32,
+    ))
+}
+
+/// Talks to the window manager through EWMH client messages. The connection
+/// is opened on first use so the app starts fine without a display.
+#[derive(Default)]
+pub struct EwmhBackend {
+    session: Mutex<Option<EwmhSession>>,
+}
+
+impl EwmhBackend {
+    fn with_session<T>(
+        &self,
+        f: impl FnOnce(&EwmhSession) -> Result<T, WindowError>,
+    ) -> Result<T, WindowError> {
+        let mut guard = self.session.lock();
+        if guard.is_none() {
+            *guard = Some(EwmhSession::connect()?);
+        }
+        let result = f(guard.as_ref().expect("session initialised"));
+        if matches!(result, Err(WindowError::Backend(_))) {
+            // Drop broken connections so the next call reconnects.
+            *guard = None;
+        }
+        result
+    }
+}
+
+impl WindowBackend for EwmhBackend {
+    fn name(&self) -> &'static str {
+        "ewmh"
+    }
+
+    fn foreground(&self) -> Result<Option<WindowId>, WindowError> {
+        self.with_session(|session| {
+            Ok(session
+                .cardinals(
+                    session.root,
+                    session.atoms._NET_ACTIVE_WINDOW,
+                    AtomEnum::WINDOW,
+                )?
+                .first()
+                .copied()
+                .filter(|window| *window != 0)
+                .map(WindowId::from))
+        })
+    }
+
+    fn minimize(&self, window: WindowId) -> Result<(), WindowError> {
+        self.with_session(|session| {
+            session.request(
+                window,
+                session.atoms.WM_CHANGE_STATE,
+                [ICONIC_STATE, 0, 0, 0, 0],
+            )
+        })
+    }
+
+    fn maximize(&self, window: WindowId) -> Result<(), WindowError> {
+        self.with_session(|session| session.set_maximized(window, STATE_ADD))
+    }
+
+    fn close(&self, window: WindowId) -> Result<(), WindowError> {
+        self.with_session(|session| {
+            session.request(
+                window,
+                session.atoms._NET_CLOSE_WINDOW,
+                [0, SOURCE_PAGER, 0, 0, 0],
+            )
+        })
+    }
+
+    fn toggle_always_on_top(&self, window: WindowId) -> Result<(), WindowError> {
+        self.with_session(|session| {
+            session.request(
+                window,
+                session.atoms._NET_WM_STATE,
+                [
+                    STATE_TOGGLE,
+                    session.atoms._NET_WM_STATE_ABOVE,
+                    0,
+                    SOURCE_PAGER,
+                    0,
+                ],
+            )
+        })
+    }
+
+    fn focus(&self, window: WindowId) -> Result<(), WindowError> {
+        self.with_session(|session| {
+            session.request(
+                window,
+                session.atoms._NET_ACTIVE_WINDOW,
+                [SOURCE_PAGER, 0, 0, 0, 0],
+            )
+        })
+    }
+
+    fn geometry(&self, window: WindowId) -> Result<Rect, WindowError> {
+        self.with_session(|session| session.geometry(window))
+    }
+
+    fn set_geometry(&self, window: WindowId, bounds: Rect) -> Result<(), WindowError> {
+        self.with_session(|session| session.set_geometry(window, bounds))
+    }
+
+    fn monitors(&self) -> Result<Vec<Rect>, WindowError> {
+        self.with_session(|session| session.monitors())
+    }
+
+    fn find_by_process(&self, name: &str) -> Result<Option<WindowId>, WindowError> {
+        self.with_session(|session| session.find_by_process(name))
+    }
+}
+
+fn window_id(window: WindowId) -> Result<Window, WindowError> {
+    Window::try_from(window)
+        .map_err(|_| WindowError::Backend(format!("invalid X11 window id {window}")))
+}
+
+fn backend_error(err: impl std::fmt::Display) -> WindowError {
+    WindowError::Backend(err.to_string())
+}
@@ -0,0 +1,481 @@
+#![allow(dead_code)]
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "linux")]
+pub(crate) use linux::process_names;
+
+use crate::domain::{SnapPosition, WindowOperation};
+use parking_lot::Mutex;
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Native window handle; an X11 window id on Linux.
+pub type WindowId = u64;
+
+/// Rectangle in virtual-desktop coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && y >= self.y
+            && i64::from(x) < i64::from(self.x) + i64::from(self.width)
+            && i64::from(y) < i64::from(self.y) + i64::from(self.height)
+    }
+
+    fn overlap(&self, other: &Rect) -> u64 {
+        let span = |start: i32, len: u32, other_start: i32, other_len: u32| {
+            let end = (i64::from(start) + i64::from(len))
+                .min(i64::from(other_start) + i64::from(other_len));
+            (end - i64::from(start.max(other_start))).max(0) as u64
+        };
+        span(self.x, self.width, other.x, other.width)
+            * span(self.y, self.height, other.y, other.height)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum WindowError {
+    #[error("window management is not available: {0}")]
+    Unavailable(String),
+    #[error("there is no foreground window")]
+    NoForegroundWindow,
+    #[error("monitor {requested} does not exist ({available} connected)")]
+    NoSuchMonitor { requested: u32, available: usize },
+    #[error("window backend failed: {0}")]
+    Backend(String),
+}
+
+/// Low-level window operations. [`apply`] builds the payload operations on
+/// top of these, so backends never compute layouts themselves.
+pub trait WindowBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn foreground(&self) -> Result<Option<WindowId>, WindowError>;
+    fn minimize(&self, window: WindowId) -> Result<(), WindowError>;
+    fn maximize(&self, window: WindowId) -> Result<(), WindowError>;
+    fn close(&self, window: WindowId) -> Result<(), WindowError>;
+    fn toggle_always_on_top(&self, window: WindowId) -> Result<(), WindowError>;
+    fn focus(&self, window: WindowId) -> Result<(), WindowError>;
+    /// Outer bounds, decorations included.
+    fn geometry(&self, window: WindowId) -> Result<Rect, WindowError>;
+    /// Moves and resizes the outer bounds, un-maximizing the window first.
+    fn set_geometry(&self, window: WindowId, bounds: Rect) -> Result<(), WindowError>;
+    /// Usable area of every monitor, without panels and docks.
+    fn monitors(&self) -> Result<Vec<Rect>, WindowError>;
+    /// Topmost window owned by a process named `name`.
+    fn find_by_process(&self, name: &str) -> Result<Option<WindowId>, WindowError>;
+}
+
+/// Result of [`apply`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowResult {
+    Applied,
+    /// `focus_or_launch` found no window; the caller launches the program.
+    NotRunning,
+}
+
+pub fn apply(
+    backend: &dyn WindowBackend,
+    operation: &WindowOperation,
+) -> Result<WindowResult, WindowError> {
+    if let WindowOperation::FocusOrLaunch { process_name, .. } = operation {
+        return match backend.find_by_process(process_name)? {
+            Some(window) => backend.focus(window).map(|_| WindowResult::Applied),
+            None => Ok(WindowResult::NotRunning),
+        };
+    }
+
+    let window = backend
+        .foreground()?
+        .ok_or(WindowError::NoForegroundWindow)?;
+    match operation {
+        WindowOperation::Minimize => backend.minimize(window)?,
+        WindowOperation::Maximize => backend.maximize(window)?,
+        WindowOperation::Close => backend.close(window)?,
+        WindowOperation::ToggleAlwaysOnTop => backend.toggle_always_on_top(window)?,
+        WindowOperation::MoveToMonitor { monitor } => {
+            let monitors = backend.monitors()?;
+            let target = monitor
+                .checked_sub(1)
+                .and_then(|index| monitors.get(index as usize))
+                .ok_or(WindowError::NoSuchMonitor {
+                    requested: *monitor,
+                    available: monitors.len(),
+                })?;
+            let bounds = backend.geometry(window)?;
+            let source = monitor_of(&bounds, &monitors).unwrap_or(*target);
+            backend.set_geometry(window, move_between(&bounds, &source, target))?;
+        }
+        WindowOperation::Snap { position } => {
+            let monitors = backend.monitors()?;
+            let bounds = backend.geometry(window)?;
+            let area = monitor_of(&bounds, &monitors)
+                .ok_or_else(|| WindowError::Backend("no monitor information available".into()))?;
+            backend.set_geometry(window, snap_area(&area, *position))?;
+        }
+        WindowOperation::FocusOrLaunch { .. } => unreachable!("handled above"),
+    }
+    Ok(WindowResult::Applied)
+}
+
+/// Monitor holding the window's centre, else the one it overlaps most.
+fn monitor_of(bounds: &Rect, monitors: &[Rect]) -> Option<Rect> {
+    let centre_x = bounds.x.saturating_add((bounds.width / 2) as i32);
+    let centre_y = bounds.y.saturating_add((bounds.height / 2) as i32);
+    monitors
+        .iter()
+        .find(|monitor| monitor.contains(centre_x, centre_y))
+        .or_else(|| {
+            monitors
+                .iter()
+                .max_by_key(|monitor| monitor.overlap(bounds))
+        })
+        .copied()
+}
+
+/// Keeps the window's offset proportional to the monitor size and shrinks
+/// it when it would not fit on the target.
+fn move_between(bounds: &Rect, source: &Rect, target: &Rect) -> Rect {
+    let width = bounds.width.min(target.width);
+    let height = bounds.height.min(target.height);
+    let scale = |offset: i32, from: u32, to: u32, size: u32| {
+        let room = i64::from(to.saturating_sub(size));
+        if from == 0 {
+            return 0;
+        }
+        (i64::from(offset) * i64::from(to) / i64::from(from)).clamp(0, room) as i32
+    };
+    Rect::new(
+        target.x + scale(bounds.x - source.x, source.width, target.width, width),
+        target.y + scale(bounds.y - source.y, source.height, target.height, height),
+        width,
+        height,
+    )
+}
+
+fn snap_area(area: &Rect, position: SnapPosition) -> Rect {
+    let half_width = area.width / 2;
+    let half_height = area.height / 2;
+    let right = area.x + half_width as i32;
+    let bottom = area.y + half_height as i32;
+    let (x, y, width, height) = match position {
+        SnapPosition::LeftHalf => (area.x, area.y, half_width, area.height),
+        SnapPosition::RightHalf => (right, area.y, area.width - half_width, area.height),
+        SnapPosition::TopHalf => (area.x, area.y, area.width, half_height),
+        SnapPosition::BottomHalf => (area.x, bottom, area.width, area.height - half_height),
+        SnapPosition::TopLeft => (area.x, area.y, half_width, half_height),
+        SnapPosition::TopRight => (right, area.y, area.width - half_width, half_height),
+        SnapPosition::BottomLeft => (area.x, bottom, half_width, area.height - half_height),
+        SnapPosition::BottomRight => (
+            right,
+            bottom,
+            area.width - half_width,
+            area.height - half_height,
+        ),
+    };
+    Rect::new(x, y, width, height)
+}
+
+/// Compares process names ignoring case and a trailing `.exe`, so profiles
+/// written on Windows keep working.
+pub(crate) fn process_name_matches(expected: &str, actual: &str) -> bool {
+    let normalize = |name: &str| {
+        let name = Path::new(name.trim())
+            .file_name()
+            .map(|name| name.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        name.strip_suffix(".exe")
+            .map(str::to_string)
+            .unwrap_or(name)
+    };
+    let expected = normalize(expected);
+    !expected.is_empty() && expected == normalize(actual)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowCall {
+    Minimize(WindowId),
+    Maximize(WindowId),
+    Close(WindowId),
+    ToggleAlwaysOnTop(WindowId),
+    Focus(WindowId),
+    SetGeometry(WindowId, Rect),
+}
+
+#[derive(Default)]
+struct Desktop {
+    monitors: Vec<Rect>,
+    /// Bottom to top; the last window has focus.
+    windows: Vec<(WindowId, String, Rect)>,
+    calls: Vec<WindowCall>,
+}
+
+/// Simulated desktop that records every call instead of touching the OS.
+#[derive(Default)]
+pub struct RecordingWindows {
+    desktop: Mutex<Desktop>,
+}
+
+impl RecordingWindows {
+    pub fn new(monitors: Vec<Rect>) -> Self {
+        Self {
+            desktop: Mutex::new(Desktop {
+                monitors,
+                ..Desktop::default()
+            }),
+        }
+    }
+
+    /// Opens a window on top of the others, giving it focus.
+    pub fn open(&self, window: WindowId, process_name: &str, bounds: Rect) {
+        self.desktop
+            .lock()
+            .windows
+            .push((window, process_name.to_string(), bounds));
+    }
+
+    pub fn calls(&self) -> Vec<WindowCall> {
+        self.desktop.lock().calls.clone()
+    }
+
+    fn record(&self, call: WindowCall) -> Result<(), WindowError> {
+        let mut desktop = self.desktop.lock();
+        match &call {
+            WindowCall::Close(window) => desktop.windows.retain(|(id, ..)| id != window),
+            WindowCall::Focus(window) => {
+                if let Some(index) = desktop.windows.iter().position(|(id, ..)| id == window) {
+                    let entry = desktop.windows.remove(index);
+                    desktop.windows.push(entry);
+                }
+            }
+            WindowCall::SetGeometry(window, bounds) => {
+                if let Some(entry) = desktop.windows.iter_mut().find(|(id, ..)| id == window) {
+                    entry.2 = *bounds;
+                }
+            }
+            _ => {}
+        }
+        desktop.calls.push(call);
+        Ok(())
+    }
+}
+
+impl WindowBackend for RecordingWindows {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn foreground(&self) -> Result<Option<WindowId>, WindowError> {
+        Ok(self.desktop.lock().windows.last().map(|(id, ..)| *id))
+    }
+
+    fn minimize(&self, window: WindowId) -> Result<(), WindowError> {
+        self.record(WindowCall::Minimize(window))
+    }
+
+    fn maximize(&self, window: WindowId) -> Result<(), WindowError> {
+        self.record(WindowCall::Maximize(window))
+    }
+
+    fn close(&self, window: WindowId) -> Result<(), WindowError> {
+        self.record(WindowCall::Close(window))
+    }
+
+    fn toggle_always_on_top(&self, window: WindowId) -> Result<(), WindowError> {
+        self.record(WindowCall::ToggleAlwaysOnTop(window))
+    }
+
+    fn focus(&self, window: WindowId) -> Result<(), WindowError> {
+        self.record(WindowCall::Focus(window))
+    }
+
+    fn geometry(&self, window: WindowId) -> Result<Rect, WindowError> {
+        self.desktop
+            .lock()
+            .windows
+            .iter()
+            .find(|(id, ..)| *id == window)
+            .map(|(.., bounds)| *bounds)
+            .ok_or_else(|| WindowError::Backend(format!("unknown window {window}")))
+    }
+
+    fn set_geometry(&self, window: WindowId, bounds: Rect) -> Result<(), WindowError> {
+        self.record(WindowCall::SetGeometry(window, bounds))
+    }
+
+    fn monitors(&self) -> Result<Vec<Rect>, WindowError> {
+        Ok(self.desktop.lock().monitors.clone())
+    }
+
+    fn find_by_process(&self, name: &str) -> Result<Option<WindowId>, WindowError> {
+        Ok(self
+            .desktop
+            .lock()
+            .windows
+            .iter()
+            .rev()
+            .find(|(_, process, _)| process_name_matches(name, process))
+            .map(|(id, ..)| *id))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+struct UnsupportedBackend;
+
+#[cfg(not(target_os = "linux"))]
+impl UnsupportedBackend {
+    fn unavailable<T>(&self) -> Result<T, WindowError> {
+        Err(WindowError::Unavailable(
+            "window actions are not supported on this platform yet".into(),
+        ))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl WindowBackend for UnsupportedBackend {
+    fn name(&self) -> &'static str {
+        "unsupported"
+    }
+
+    fn foreground(&self) -> Result<Option<WindowId>, WindowError> {
+        self.unavailable()
+    }
+
+    fn minimize(&self, _window: WindowId) -> Result<(), WindowError> {
+        self.unavailable()
+    }
+
+    fn maximize(&self, _window: WindowId) -> Result<(), WindowError> {
+        self.unavailable()
+    }
+
+    fn close(&self, _window: WindowId) -> Result<(), WindowError> {
+        self.unavailable()
+    }
+
+    fn toggle_always_on_top(&self, _window: WindowId) -> Result<(), WindowError> {
+        self.unavailable()
+    }
+
+    fn focus(&self, _window: WindowId) -> Result<(), WindowError> {
+        self.unavailable()
+    }
+
+    fn geometry(&self, _window: WindowId) -> Result<Rect, WindowError> {
+        self.unavailable()
+    }
+
+    fn set_geometry(&self, _window: WindowId, _bounds: Rect) -> Result<(), WindowError> {
+        self.unavailable()
+    }
+
+    fn monitors(&self) -> Result<Vec<Rect>, WindowError> {
+        self.unavailable()
+    }
+
+    fn find_by_process(&self, _name: &str) -> Result<Option<WindowId>, WindowError> {
+        self.unavailable()
+    }
+}
+
+/// Backend used by the app: X11/EWMH on Linux, unsupported elsewhere.
+pub fn default_backend() -> Arc<dyn WindowBackend> {
+    #[cfg(target_os = "linux")]
+    {
+        Arc::new(linux::EwmhBackend::default())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Arc::new(UnsupportedBackend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn desktop() -> RecordingWindows {
+        // A 1920x1080 monitor with a 40px panel, and a 1280x1024 one to
+        // its right.
+        let windows = RecordingWindows::new(vec![
+            Rect::new(0, 0, 1920, 1040),
+            Rect::new(1920, 0, 1280, 1024),
+        ]);
+        windows.open(1, "Code", Rect::new(100, 100, 800, 600));
+        windows.open(2, "firefox", Rect::new(480, 260, 960, 520));
+        windows
+    }
+
+    #[test]
+    fn snap_and_move_use_the_monitor_work_area() {
+        let windows = desktop();
+        let snap = |position| WindowOperation::Snap { position };
+
+        apply(&windows, &snap(SnapPosition::RightHalf)).expect("snap right");
+        assert_eq!(windows.geometry(2).unwrap(), Rect::new(960, 0, 960, 1040));
+
+        apply(&windows, &WindowOperation::MoveToMonitor { monitor: 2 }).expect("move");
+        assert_eq!(
+            windows.geometry(2).unwrap(),
+            Rect::new(1920 + 320, 0, 960, 1024)
+        );
+
+        apply(&windows, &snap(SnapPosition::BottomLeft)).expect("snap quarter");
+        assert_eq!(windows.geometry(2).unwrap(), Rect::new(1920, 512, 640, 512));
+
+        let err = apply(&windows, &WindowOperation::MoveToMonitor { monitor: 3 })
+            .expect_err("missing monitor");
+        assert!(matches!(
+            err,
+            WindowError::NoSuchMonitor {
+                requested: 3,
+                available: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn focus_or_launch_focuses_matching_window() {
+        let windows = desktop();
+        let focus_or_launch = |name: &str| WindowOperation::FocusOrLaunch {
+            process_name: name.into(),
+            executable: None,
+            arguments: Vec::new(),
+        };
+
+        assert_eq!(
+            apply(&windows, &focus_or_launch("code.exe")).expect("focus"),
+            WindowResult::Applied
+        );
+        assert_eq!(windows.foreground().unwrap(), Some(1));
+        assert_eq!(
+            apply(&windows, &focus_or_launch("gimp")).expect("not running"),
+            WindowResult::NotRunning
+        );
+
+        apply(&windows, &WindowOperation::Close).expect("close");
+        assert_eq!(windows.foreground().unwrap(), Some(2));
+        assert_eq!(
+            windows.calls(),
+            vec![WindowCall::Focus(1), WindowCall::Close(1)]
+        );
+    }
+}
@@ -0,0 +1,378 @@
+use super::{ForegroundWindow, ForegroundWindowSource};
+use crate::services::system_status::ScreenAreaSnapshot;
+use crate::services::window_control::process_names;
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::ConnectionExt as _;
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt as _, EventMask, Window,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        UTF8_STRING,
+        _NET_ACTIVE_WINDOW,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_STATE,
+        _NET_WM_STATE_FULLSCREEN,
+    }
+}
+
+struct Session {
+    conn: RustConnection,
+    root: Window,
+    screen: ScreenAreaSnapshot,
+    atoms: Atoms,
+}
+
+impl Session {
+    fn connect(display: Option<&str>) -> Result<Self> {
+        let (conn, screen) = x11rb::connect(display).context("cannot connect to the X server")?;
+        let (root, size) = {
+            let screen = &conn.setup().roots[screen];
+            (
+                screen.root,
+                ScreenAreaSnapshot {
+                    x: 0,
+                    y: 0,
+                    width: i32::from(screen.width_in_pixels),
+                    height: i32::from(screen.height_in_pixels),
+                },
+            )
+        };
+        let atoms = Atoms::new(&conn)?.reply()?;
+        Ok(Self {
+            conn,
+            root,
+            screen: size,
+            atoms,
+        })
+    }
+
+    fn property(&self, window: Window, property: Atom, kind: impl Into<Atom>) -> Result<Vec<u8>> {
+        Ok(self
+            .conn
+            .get_property(false, window, property, kind, 0, 4096)?
+            .reply()?
+            .value)
+    }
+
+    fn cardinals(&self, window: Window, property: Atom, kind: impl Into<Atom>) -> Result<Vec<u32>> {
+        Ok(self
+            .conn
+            .get_property(false, window, property, kind, 0, 4096)?
+            .reply()?
+            .value32()
+            .map(|values| values.collect())
+            .unwrap_or_default())
+    }
+
+    fn active_window(&self) -> Result<Option<Window>> {
+        Ok(self
+            .cardinals(self.root, self.atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW)?
+            .first()
+            .copied()
+            .filter(|window| *window != 0))
+    }
+
+    fn select_property_changes(&self, window: Window, enabled: bool) -> Result<()> {
+        let mask = if enabled {
+            EventMask::PROPERTY_CHANGE
+        } else {
+            EventMask::NO_EVENT
+        };
+        self.conn
+            .change_window_attributes(window, &ChangeWindowAttributesAux::new().event_mask(mask))?;
+        Ok(())
+    }
+
+    fn foreground(&self) -> Result<Option<ForegroundWindow>> {
+        let Some(window) = self.active_window()? else {
+            return Ok(None);
+        };
+
+        let pid = self
+            .cardinals(window, self.atoms._NET_WM_PID, AtomEnum::CARDINAL)?
+            .first()
+            .copied();
+        let (screen_area, covers_monitor) = match self.monitor_of(window) {
+            Ok(placement) => placement,
+            // The window may close between the two requests.
+            Err(_) => (None, false),
+        };
+        let state = self.cardinals(window, self.atoms._NET_WM_STATE, AtomEnum::ATOM)?;
+
+        Ok(Some(ForegroundWindow {
+            pid,
+            process_name: pid.and_then(|pid| process_names(pid).into_iter().next()),
+            title: self.title(window)?,
+            class: self.class(window)?,
+            screen_area,
+            is_fullscreen: covers_monitor || state.contains(&self.atoms._NET_WM_STATE_FULLSCREEN),
+        }))
+    }
+
+    /// `_NET_WM_NAME`, or the legacy `WM_NAME` for clients without EWMH.
+    fn title(&self, window: Window) -> Result<Option<String>> {
+        let mut title = self.property(window, self.atoms._NET_WM_NAME, self.atoms.UTF8_STRING)?;
+        if title.is_empty() {
+            title = self.property(window, AtomEnum::WM_NAME.into(), AtomEnum::ANY)?;
+        }
+        Ok((!title.is_empty()).then(|| String::from_utf8_lossy(&title).into_owned()))
+    }
+
+    /// The class half of `WM_CLASS`, which holds `instance\0class\0`.
+    fn class(&self, window: Window) -> Result<Option<String>> {
+        let value = self.property(window, AtomEnum::WM_CLASS.into(), AtomEnum::STRING)?;
+        let mut parts = value
+            .split(|byte| *byte == 0)
+            .filter(|part| !part.is_empty())
+            .map(|part| String::from_utf8_lossy(part).into_owned());
+        let instance = parts.next();
+        Ok(parts.next().or(instance))
+    }
+
+    /// Monitor holding the window's centre, and whether the window covers it.
+    fn monitor_of(&self, window: Window) -> Result<(Option<ScreenAreaSnapshot>, bool)> {
+        let geometry = self.conn.get_geometry(window)?.reply()?;
+        let origin = self
+            .conn
+            .translate_coordinates(window, self.root, 0, 0)?
+            .reply()?;
+        let (x, y) = (i32::from(origin.dst_x), i32::from(origin.dst_y));
+        let (width, height) = (i32::from(geometry.width), i32::from(geometry.height));
+        let (centre_x, centre_y) = (x + width / 2, y + height / 2);
+
+        let monitors = match self
+            .conn
+            .randr_get_monitors(self.root, true)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+        {
+            Some(reply) if !reply.monitors.is_empty() => reply
+                .monitors
+                .iter()
+                .map(|monitor| ScreenAreaSnapshot {
+                    x: i32::from(monitor.x),
+                    y: i32::from(monitor.y),
+                    width: i32::from(monitor.width),
+                    height: i32::from(monitor.height),
+                })
+                .collect(),
+            _ => vec![self.screen.clone()],
+        };
+        let monitor = monitors.into_iter().find(|monitor| {
+            centre_x >= monitor.x
+                && centre_y >= monitor.y
+                && centre_x < monitor.x + monitor.width
+                && centre_y < monitor.y + monitor.height
+        });
+        let covers = monitor.as_ref().is_some_and(|monitor| {
+            x <= monitor.x
+                && y <= monitor.y
+                && x + width >= monitor.x + monitor.width
+                && y + height >= monitor.y + monitor.height
+        });
+        Ok((monitor, covers))
+    }
+}
+
+/// Reports `_NET_ACTIVE_WINDOW` changes on the root window and title changes
+/// of the active window, until the connection fails or `changes` is dropped.
+fn watch(session: Session, changes: UnboundedSender<()>) {
+    let mut watched = None;
+    loop {
+        let active = session.active_window().ok().flatten();
+        if watched != active {
+            if let Some(previous) = watched {
+                // The window may be gone already.
+                let _ = session.select_property_changes(previous, false);
+            }
+            watched = active;
+            if let Some(window) = watched {
+                let _ = session.select_property_changes(window, true);
+            }
+            let _ = session.conn.flush();
+        }
+
+        let event = match session.conn.wait_for_event() {
+            Ok(event) => event,
+            Err(err) => {
+                eprintln!("x11 focus watch stopped: {err}");
+                return;
+            }
+        };
+        let Event::PropertyNotify(event) = event else {
+            continue;
+        };
+        let relevant = if event.window == session.root {
+            event.atom == session.atoms._NET_ACTIVE_WINDOW
+        } else {
+            event.atom == session.atoms._NET_WM_NAME || event.atom == AtomEnum::WM_NAME.into()
+        };
+        if relevant && changes.send(()).is_err() {
+            return;
+        }
+    }
+}
+
+/// Reads the focused window from EWMH properties. The connection is opened
+/// on first use and dropped after an error, so the source recovers when the
+/// X server comes back.
+#[derive(Default)]
+pub struct X11Source {
+    display: Option<String>,
+    session: Mutex<Option<Session>>,
+}
+
+impl X11Source {
+    /// Connects to `display` instead of `$DISPLAY`.
+    pub fn with_display(display: impl Into<String>) -> Self {
+        Self {
+            display: Some(display.into()),
+            session: Mutex::new(None),
+        }
+    }
+}
+
+impl ForegroundWindowSource for X11Source {
+    fn name(&self) -> &'static str {
+        "x11"
+    }
+
+    fn foreground(&self) -> Result<Option<ForegroundWindow>> {
+        let mut guard = self.session.lock();
+        if guard.is_none() {
+            *guard = Some(Session::connect(self.display.as_deref())?);
+        }
+        let result = guard.as_ref().expect("session initialised").foreground();
+        if result.is_err() {
+            *guard = None;
+        }
+        result
+    }
+
+    /// Watches on a connection of its own, since waiting for events would
+    /// otherwise block `foreground`.
+    fn changes(&self) -> Result<Option<UnboundedReceiver<()>>> {
+        let session = Session::connect(self.display.as_deref())?;
+        session.select_property_changes(session.root, true)?;
+        session.conn.flush()?;
+        let (sender, receiver) = unbounded_channel();
+        std::thread::Builder::new()
+            .name("x11-focus-watch".into())
+            .spawn(move || watch(session, sender))?;
+        Ok(Some(receiver))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use x11rb::protocol::xproto::{CreateWindowAux, PropMode, WindowClass};
+    use x11rb::wrapper::ConnectionExt as _;
+
+    fn notified(changes: &mut UnboundedReceiver<()>) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if changes.try_recv().is_ok() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+
+    /// Without a window manager nothing maintains `_NET_ACTIVE_WINDOW`, so
+    /// the test plays that part itself.
+    #[test]
+    #[ignore = "needs an X server; run with `xvfb-run cargo test -- --ignored`"]
+    fn reads_the_active_window_from_ewmh_properties() {
+        let source = X11Source::default();
+        let mut changes = source.changes().unwrap().expect("x11 reports changes");
+        let session = Session::connect(None).expect("X server");
+        let conn = &session.conn;
+        let atoms = &session.atoms;
+
+        let window = conn.generate_id().unwrap();
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            session.root,
+            10,
+            20,
+            300,
+            200,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new(),
+        )
+        .unwrap();
+        conn.change_property8(
+            PropMode::REPLACE,
+            window,
+            AtomEnum::WM_CLASS,
+            AtomEnum::STRING,
+            b"pie-probe\0PieProbe\0",
+        )
+        .unwrap();
+        conn.change_property8(
+            PropMode::REPLACE,
+            window,
+            atoms._NET_WM_NAME,
+            atoms.UTF8_STRING,
+            "Pröbe – window".as_bytes(),
+        )
+        .unwrap();
+        conn.change_property32(
+            PropMode::REPLACE,
+            window,
+            atoms._NET_WM_PID,
+            AtomEnum::CARDINAL,
+            &[std::process::id()],
+        )
+        .unwrap();
+        conn.map_window(window).unwrap();
+        conn.change_property32(
+            PropMode::REPLACE,
+            session.root,
+            atoms._NET_ACTIVE_WINDOW,
+            AtomEnum::WINDOW,
+            &[window],
+        )
+        .unwrap();
+        conn.sync().unwrap();
+        assert!(notified(&mut changes));
+
+        let found = source.foreground().unwrap().expect("active window");
+        assert_eq!(found.pid, Some(std::process::id()));
+        assert_eq!(found.class.as_deref(), Some("PieProbe"));
+        assert_eq!(found.title.as_deref(), Some("Pröbe – window"));
+        let exe = std::env::current_exe().unwrap();
+        assert_eq!(
+            found.process_name.as_deref(),
+            exe.file_name().and_then(|name| name.to_str())
+        );
+        assert!(found.screen_area.is_some());
+        assert!(!found.is_fullscreen);
+
+        conn.change_property32(
+            PropMode::REPLACE,
+            session.root,
+            atoms._NET_ACTIVE_WINDOW,
+            AtomEnum::WINDOW,
+            &[0],
+        )
+        .unwrap();
+        conn.sync().unwrap();
+        assert!(notified(&mut changes));
+        assert!(source.foreground().unwrap().is_none());
+    }
+}
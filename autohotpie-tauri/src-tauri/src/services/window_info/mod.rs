@@ -0,0 +1,309 @@
+#[cfg(target_os = "linux")]
+mod linux;
+
+use crate::services::system_status::{
+    CursorPosition, ScreenAreaSnapshot, SystemStatus, WindowSnapshot,
+};
+use anyhow::{anyhow, Result};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::time::sleep;
+
+#[cfg(target_os = "linux")]
+pub use linux::X11Source;
+
+pub(crate) const WINDOW_EVENT: &str = "system://window-info";
+const POLL_INTERVAL: Duration = Duration::from_millis(750);
+/// While the source reports changes, polling only picks up what it does not
+/// report, such as a window moving to another monitor.
+const WATCHED_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// The window the user is working in, as reported by the desktop.
+#[derive(Debug, Clone, Default)]
+pub struct ForegroundWindow {
+    pub pid: Option<u32>,
+    pub process_name: Option<String>,
+    pub title: Option<String>,
+    pub class: Option<String>,
+    /// Monitor holding the window.
+    pub screen_area: Option<ScreenAreaSnapshot>,
+    pub is_fullscreen: bool,
+}
+
+/// Reports which window has focus. Sources connect lazily and return
+/// `Ok(None)` while nothing has focus.
+pub trait ForegroundWindowSource: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn foreground(&self) -> Result<Option<ForegroundWindow>>;
+
+    /// Notifications sent whenever the foreground window or its title may
+    /// have changed. The channel closes when the source loses its
+    /// connection; `None` means the source can only be polled.
+    fn changes(&self) -> Result<Option<UnboundedReceiver<()>>> {
+        Ok(None)
+    }
+}
+
+/// Source used by the app: X11/EWMH on Linux. Elsewhere there is none and
+/// the monitor only describes the app's own window.
+pub fn default_source() -> Option<Arc<dyn ForegroundWindowSource>> {
+    #[cfg(target_os = "linux")]
+    {
+        Some(Arc::new(X11Source::default()))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Replays a fixed sequence of foreground windows, one per call, then keeps
+/// returning the last one.
+#[derive(Default)]
+pub struct ScriptedWindows {
+    /// The script and the index of the next entry to return.
+    script: parking_lot::Mutex<(Vec<Option<ForegroundWindow>>, usize)>,
+}
+
+impl ScriptedWindows {
+    pub fn new(script: impl IntoIterator<Item = Option<ForegroundWindow>>) -> Self {
+        Self {
+            script: parking_lot::Mutex::new((script.into_iter().collect(), 0)),
+        }
+    }
+
+    /// Appends to the script; once the script has run out, the next call
+    /// returns `window`.
+    pub fn push(&self, window: Option<ForegroundWindow>) {
+        self.script.lock().0.push(window);
+    }
+}
+
+impl ForegroundWindowSource for ScriptedWindows {
+    fn name(&self) -> &'static str {
+        "scripted"
+    }
+
+    fn foreground(&self) -> Result<Option<ForegroundWindow>> {
+        let mut guard = self.script.lock();
+        let (script, next) = &mut *guard;
+        let window = script
+            .get((*next).min(script.len().saturating_sub(1)))
+            .cloned()
+            .flatten();
+        *next = (*next + 1).min(script.len());
+        Ok(window)
+    }
+}
+
+pub fn start_monitor<R: Runtime>(
+    app: AppHandle<R>,
+    status: Arc<Mutex<SystemStatus>>,
+    source: Option<Arc<dyn ForegroundWindowSource>>,
+) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = run_loop(app.clone(), status.clone(), source).await {
+            eprintln!("window info monitor exited: {err}");
+        }
+    });
+}
+
+async fn run_loop<R: Runtime>(
+    app: AppHandle<R>,
+    status: Arc<Mutex<SystemStatus>>,
+    source: Option<Arc<dyn ForegroundWindowSource>>,
+) -> Result<()> {
+    let source = source.as_deref();
+    let mut last_error = None;
+    let mut changes = None;
+    loop {
+        if changes.is_none() {
+            changes = source.and_then(|source| source.changes().ok().flatten());
+        }
+        publish_snapshot(&app, &status, source, &mut last_error).await?;
+
+        let poll = if changes.is_some() {
+            WATCHED_POLL_INTERVAL
+        } else {
+            POLL_INTERVAL
+        };
+        tokio::select! {
+            _ = sleep(poll) => {}
+            changed = next_change(&mut changes) => {
+                if changed.is_none() {
+                    changes = None;
+                }
+            }
+        }
+    }
+}
+
+async fn next_change(changes: &mut Option<UnboundedReceiver<()>>) -> Option<()> {
+    match changes {
+        Some(changes) => changes.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn publish_snapshot<R: Runtime>(
+    app: &AppHandle<R>,
+    status: &Arc<Mutex<SystemStatus>>,
+    source: Option<&dyn ForegroundWindowSource>,
+    last_error: &mut Option<String>,
+) -> Result<()> {
+    let Some(snapshot) = collect_snapshot(app, source, last_error).await? else {
+        return Ok(());
+    };
+    let changed = {
+        let mut guard = status
+            .lock()
+            .map_err(|_| anyhow!("system status poisoned"))?;
+        let changed = !same_window(&guard.window, &snapshot);
+        guard.update_window(snapshot.clone());
+        changed
+    };
+    // The profile router wakes up on this event, so it is only sent when
+    // the window is a different one or has changed in a way rules can see.
+    if changed {
+        let _ = app.emit(WINDOW_EVENT, snapshot);
+    }
+    Ok(())
+}
+
+/// Compares everything but the timestamp and cursor position.
+fn same_window(a: &WindowSnapshot, b: &WindowSnapshot) -> bool {
+    a.process_name == b.process_name
+        && a.window_title == b.window_title
+        && a.window_class == b.window_class
+        && a.screen_area == b.screen_area
+        && a.is_fullscreen == b.is_fullscreen
+}
+
+/// `None` keeps the previous snapshot: the pie overlay takes focus while it
+/// is open, and the profile it serves must stay active meanwhile. When the
+/// source fails, e.g. without an X server, the app's own window is described
+/// and the error is logged once until it changes.
+async fn collect_snapshot<R: Runtime>(
+    app: &AppHandle<R>,
+    source: Option<&dyn ForegroundWindowSource>,
+    last_error: &mut Option<String>,
+) -> Result<Option<WindowSnapshot>> {
+    let mut snapshot = match source.map(|source| (source.name(), source.foreground())) {
+        Some((_, Ok(window))) => {
+            *last_error = None;
+            match window {
+                Some(window) if window.pid == Some(std::process::id()) => return Ok(None),
+                Some(window) => describe(window),
+                None => WindowSnapshot::now(),
+            }
+        }
+        Some((name, Err(err))) => {
+            let message = err.to_string();
+            if last_error.as_deref() != Some(message.as_str()) {
+                eprintln!("{name} foreground window lookup failed: {message}");
+                *last_error = Some(message);
+            }
+            own_window_snapshot(app)
+        }
+        None => own_window_snapshot(app),
+    };
+
+    if let Some(window) = app.get_webview_window("main") {
+        if let Ok(position) = window.cursor_position() {
+            snapshot.cursor_position = Some(CursorPosition {
+                x: position.x,
+                y: position.y,
+            });
+        }
+    }
+
+    Ok(Some(snapshot))
+}
+
+fn describe(window: ForegroundWindow) -> WindowSnapshot {
+    WindowSnapshot {
+        process_name: window.process_name,
+        window_title: window.title.filter(|title| !title.is_empty()),
+        window_class: window.class.filter(|class| !class.is_empty()),
+        screen_area: window.screen_area,
+        is_fullscreen: window.is_fullscreen,
+        ..WindowSnapshot::now()
+    }
+}
+
+/// Describes the app's own `main` window, for when no source is available.
+fn own_window_snapshot<R: Runtime>(app: &AppHandle<R>) -> WindowSnapshot {
+    let mut snapshot = WindowSnapshot::now();
+
+    snapshot.process_name = current_process_name(app);
+
+    if let Some(window) = app.get_webview_window("main") {
+        if let Ok(title) = window.title() {
+            if !title.is_empty() {
+                snapshot.window_title = Some(title);
+            }
+        }
+
+        if let Ok(fullscreen) = window.is_fullscreen() {
+            snapshot.is_fullscreen = fullscreen;
+        }
+    }
+
+    snapshot
+}
+
+fn current_process_name<R: Runtime>(app: &AppHandle<R>) -> Option<String> {
+    if let Some(name) = app.config().product_name.clone() {
+        if !name.is_empty() {
+            return Some(name);
+        }
+    }
+
+    std::env::current_exe().ok().and_then(|path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor() -> ForegroundWindow {
+        ForegroundWindow {
+            pid: Some(4242),
+            process_name: Some("code".into()),
+            title: Some("main.rs - crate".into()),
+            class: Some("Code".into()),
+            screen_area: Some(ScreenAreaSnapshot {
+                x: 1920,
+                y: 0,
+                width: 2560,
+                height: 1440,
+            }),
+            is_fullscreen: false,
+        }
+    }
+
+    #[test]
+    fn scripted_windows_replay_then_hold_the_last_window() {
+        let source = ScriptedWindows::new([None, Some(editor())]);
+        assert!(source.foreground().unwrap().is_none());
+        for _ in 0..2 {
+            let window = source.foreground().unwrap().expect("editor focused");
+            assert_eq!(window.pid, Some(4242));
+        }
+
+        source.push(Some(ForegroundWindow {
+            title: Some(String::new()),
+            ..editor()
+        }));
+        let snapshot = describe(source.foreground().unwrap().expect("window"));
+        assert_eq!(snapshot.process_name.as_deref(), Some("code"));
+        assert_eq!(snapshot.window_class.as_deref(), Some("Code"));
+        assert_eq!(snapshot.window_title, None);
+        assert_eq!(snapshot.screen_area.map(|area| area.x), Some(1920));
+    }
+}
@@ -1,7 +1,6 @@
 pub mod profile_repository;
 
 use crate::domain::profile::ProfileId;
-use crate::domain::Action;
 use crate::models::{AppProfile, Settings};
 use crate::storage::profile_repository::{
     legacy_settings_file, read_legacy_settings, ProfileRepository, ProfileStore,
@@ -19,7 +18,6 @@ pub const SETTINGS_FILE_NAME: &str = "AHPSettings.json";
 const BACKUP_DIR_NAME: &str = "backups";
 const SCHEMA_VERSION: u32 = 1;
 const MAX_BACKUPS: usize = 5;
-const ACTIONS_FILE_NAME: &str = "actions.json";
 
 fn other_error(message: impl Into<String>) -> io::Error {
     io::Error::new(io::ErrorKind::Other, message.into())
@@ -56,7 +54,6 @@ pub struct StorageManager {
     settings_path: PathBuf,
     backups_path: PathBuf,
     cache_path: PathBuf,
-    actions_path: PathBuf,
     profiles_repo: ProfileRepository,
 }
 
@@ -78,14 +75,12 @@ impl StorageManager {
             fs::create_dir_all(&backups_path)?;
         }
         let cache_path = base_dir.join("settings.cache.json");
-        let actions_path = base_dir.join(ACTIONS_FILE_NAME);
         let profiles_repo = ProfileRepository::new(&base_dir, &backups_path);
         Ok(Self {
             base_dir,
             settings_path,
             backups_path,
             cache_path,
-            actions_path,
             profiles_repo,
         })
     }
@@ -98,14 +93,12 @@ impl StorageManager {
             fs::create_dir_all(&backups_path)?;
         }
         let cache_path = base_dir.join("settings.cache.json");
-        let actions_path = base_dir.join(ACTIONS_FILE_NAME);
         let profiles_repo = ProfileRepository::new(&base_dir, &backups_path);
         Ok(Self {
             base_dir,
             settings_path,
             backups_path,
             cache_path,
-            actions_path,
             profiles_repo,
         })
     }
@@ -171,25 +164,6 @@ impl StorageManager {
         Ok(dir)
     }
 
-    pub fn load_actions(&self) -> io::Result<Vec<Action>> {
-        if !self.actions_path.exists() {
-            return Ok(Vec::new());
-        }
-
-        let data = fs::read_to_string(&self.actions_path)?;
-        let actions = serde_json::from_str::<Vec<Action>>(&data)
-            .map_err(|err| other_error(format!("failed to parse actions: {err}")))?;
-        Ok(actions)
-    }
-
-    pub fn save_actions(&self, actions: &[Action]) -> io::Result<()> {
-        self.ensure_dirs()?;
-        let payload = serde_json::to_string_pretty(actions)
-            .map_err(|err| other_error(format!("failed to serialize actions: {err}")))?;
-        fs::write(&self.actions_path, payload)?;
-        Ok(())
-    }
-
     fn ensure_dirs(&self) -> io::Result<()> {
         if !self.base_dir.exists() {
             fs::create_dir_all(&self.base_dir)?;
@@ -1,11 +1,12 @@
-use crate::domain::action::MacroStepDefinition;
+use crate::domain::action::{ConcurrencyPolicy, MacroStepDefinition, DEFAULT_MACRO_TIMEOUT_MS};
 use crate::domain::context_rules::ScreenArea;
 use crate::domain::pie_menu::{PieMenu, PieMenuId, PieSlice, PieSliceId};
 use crate::domain::profile::{ActivationMatchMode, ActivationRule, Profile, ProfileId};
-use crate::domain::{ActionDefinition, ActionId, MacroStepKind};
+use crate::domain::{Action, ActionId, ActionPayload, MacroStepKind, Trigger};
 use crate::models::AppProfile;
 use crate::storage::SETTINGS_FILE_NAME;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -14,7 +15,11 @@ use time::OffsetDateTime;
 
 pub const PROFILES_FILE_NAME: &str = "profiles.v1.json";
 const PROFILES_BACKUP_DIR: &str = "profiles";
-pub const PROFILES_SCHEMA_VERSION: u32 = 1;
+/// Version 2 folded the standalone `actions.json` library into the store and
+/// replaced per-profile macro definitions with the canonical [`Action`] model.
+pub const PROFILES_SCHEMA_VERSION: u32 = 2;
+pub const LEGACY_ACTIONS_FILE_NAME: &str = "actions.json";
+const MIGRATED_ACTIONS_FILE_NAME: &str = "actions.v1.migrated.json";
 
 fn other_error(message: impl Into<String>) -> io::Error {
     io::Error::new(io::ErrorKind::Other, message.into())
@@ -42,6 +47,7 @@ pub(crate) fn build_default_profile_record(
             app_path: "calc".to_string(),
             arguments: None,
         },
+        retry: None,
         note: None,
     }];
 
@@ -52,25 +58,38 @@ pub(crate) fn build_default_profile_record(
             app_path: "explorer".to_string(),
             arguments: Some("%USERPROFILE%\\Downloads".to_string()),
         },
+        retry: None,
         note: None,
     }];
 
     let actions = vec![
-        ActionDefinition {
+        Action {
             id: action_launch_id,
             name: "Launch Calculator".to_string(),
             description: Some("Open system calculator".to_string()),
-            timeout_ms: 3000,
+            payload: ActionPayload::Macro {
+                steps: launch_steps,
+            },
+            enabled: true,
+            timeout_ms: Some(DEFAULT_MACRO_TIMEOUT_MS),
+            concurrency: ConcurrencyPolicy::default(),
+            retry: None,
+            untrusted: false,
             last_validated_at: None,
-            steps: launch_steps,
         },
-        ActionDefinition {
+        Action {
             id: action_open_downloads_id,
             name: "Open Downloads".to_string(),
             description: Some("Open Downloads folder".to_string()),
-            timeout_ms: 3000,
+            payload: ActionPayload::Macro {
+                steps: open_downloads_steps,
+            },
+            enabled: true,
+            timeout_ms: Some(DEFAULT_MACRO_TIMEOUT_MS),
+            concurrency: ConcurrencyPolicy::default(),
+            retry: None,
+            untrusted: false,
             last_validated_at: None,
-            steps: open_downloads_steps,
         },
     ];
 
@@ -120,6 +139,7 @@ pub(crate) fn build_default_profile_record(
             activation_rules: Vec::new(),
             root_menu: root_menu_id,
             hold_to_open: false,
+            priority: 0,
         },
         menus: vec![menu],
         actions,
@@ -219,7 +239,7 @@ fn normalize_profile_store(store: &mut ProfileStore) {
             .actions
             .iter()
             .cloned()
-            .map(normalize_action_definition)
+            .map(normalize_action)
             .collect();
         record.profile.activation_rules = record
             .profile
@@ -229,9 +249,15 @@ fn normalize_profile_store(store: &mut ProfileStore) {
             .map(normalize_activation_rule)
             .collect();
     });
+    store.actions = store
+        .actions
+        .iter()
+        .cloned()
+        .map(normalize_action)
+        .collect();
 }
 
-fn normalize_action_definition(mut action: ActionDefinition) -> ActionDefinition {
+fn normalize_action(mut action: Action) -> Action {
     action.name = action.name.trim().to_string();
     action.description = action.description.and_then(|value| {
         let trimmed = value.trim();
@@ -241,8 +267,14 @@ fn normalize_action_definition(mut action: ActionDefinition) -> ActionDefinition
             Some(trimmed.to_string())
         }
     });
-    action.steps = action
-        .steps
+    if let ActionPayload::Macro { steps } = &mut action.payload {
+        *steps = normalize_macro_steps(std::mem::take(steps));
+    }
+    action
+}
+
+fn normalize_macro_steps(steps: Vec<MacroStepDefinition>) -> Vec<MacroStepDefinition> {
+    steps
         .into_iter()
         .enumerate()
         .map(|(index, mut step)| {
@@ -288,13 +320,26 @@ fn normalize_action_definition(mut action: ActionDefinition) -> ActionDefinition
             }
             step
         })
-        .collect();
-    action
+        .collect()
 }
 
 fn normalize_activation_rule(mut rule: ActivationRule) -> ActivationRule {
     rule.negate = normalize_bool(rule.negate);
 
+    if rule.mode.is_group() {
+        rule.value = None;
+        rule.is_regex = None;
+        rule.case_sensitive = None;
+        rule.screen_area = None;
+        rule.rules = rule
+            .rules
+            .into_iter()
+            .map(normalize_activation_rule)
+            .collect();
+        return rule;
+    }
+    rule.rules.clear();
+
     let mut flags = RuleFlags {
         is_regex: rule.is_regex,
         case_sensitive: rule.case_sensitive,
@@ -456,7 +501,7 @@ pub struct ProfileRecord {
     #[serde(default)]
     pub menus: Vec<PieMenu>,
     #[serde(default)]
-    pub actions: Vec<ActionDefinition>,
+    pub actions: Vec<Action>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub created_at: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -473,6 +518,12 @@ pub struct ProfileStore {
     pub active_profile_id: Option<ProfileId>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub migrated_from_settings: Option<String>,
+    /// Shared action library available to every profile.
+    #[serde(default)]
+    pub actions: Vec<Action>,
+    /// Schedules and other non-hotkey triggers, across all profiles.
+    #[serde(default)]
+    pub triggers: Vec<Trigger>,
 }
 
 impl Default for ProfileStore {
@@ -482,7 +533,82 @@ impl Default for ProfileStore {
             profiles: Vec::new(),
             active_profile_id: None,
             migrated_from_settings: None,
+            actions: Vec::new(),
+            triggers: Vec::new(),
+        }
+    }
+}
+
+impl ProfileStore {
+    /// Resolves an action id the same way everywhere (validation, the runner,
+    /// the UI): the given profile's own actions first, then the shared
+    /// library. Without a profile only the shared library is searched; other
+    /// profiles' actions are never used.
+    pub fn find_action(&self, profile_id: Option<ProfileId>, id: &ActionId) -> Option<&Action> {
+        let scoped = profile_id.and_then(|profile_id| {
+            self.profiles
+                .iter()
+                .find(|record| record.profile.id == profile_id)
+                .and_then(|record| record.actions.iter().find(|action| action.id == *id))
+        });
+        scoped.or_else(|| self.actions.iter().find(|action| action.id == *id))
+    }
+
+    /// Whether a stored action with this id, shared or profile-owned, is
+    /// still flagged `untrusted`. Saves OR this into incoming actions so an
+    /// editor round trip cannot clear the flag; only
+    /// `mark_actions_reviewed` does.
+    pub fn is_untrusted(&self, id: &ActionId) -> bool {
+        self.actions
+            .iter()
+            .chain(
+                self.profiles
+                    .iter()
+                    .flat_map(|record| record.actions.iter()),
+            )
+            .any(|action| action.id == *id && action.untrusted)
+    }
+
+    /// Every stored action: the shared library followed by each profile's
+    /// own actions.
+    pub fn all_actions_mut(&mut self) -> impl Iterator<Item = &mut Action> + '_ {
+        self.actions.iter_mut().chain(
+            self.profiles
+                .iter_mut()
+                .flat_map(|record| record.actions.iter_mut()),
+        )
+    }
+
+    /// Actions visible to a profile, in resolution order.
+    pub fn actions_in_scope<'a>(
+        &'a self,
+        record: &'a ProfileRecord,
+    ) -> impl Iterator<Item = &'a Action> + 'a {
+        record.actions.iter().chain(self.actions.iter())
+    }
+
+    /// Appends legacy library actions to the shared library, skipping ids the
+    /// store already knows. Returns the skipped actions.
+    fn fold_shared_actions(&mut self, actions: Vec<Action>) -> Vec<Action> {
+        let mut known: HashSet<ActionId> = self
+            .actions
+            .iter()
+            .chain(
+                self.profiles
+                    .iter()
+                    .flat_map(|record| record.actions.iter()),
+            )
+            .map(|action| action.id)
+            .collect();
+        let mut skipped = Vec::new();
+        for action in actions {
+            if known.insert(action.id) {
+                self.actions.push(action);
+            } else {
+                skipped.push(action);
+            }
         }
+        skipped
     }
 }
 
@@ -490,15 +616,18 @@ impl Default for ProfileStore {
 pub struct ProfileRepository {
     file_path: PathBuf,
     backups_path: PathBuf,
+    legacy_actions_path: PathBuf,
 }
 
 impl ProfileRepository {
     pub fn new(base_dir: &Path, backups_dir: &Path) -> Self {
         let file_path = base_dir.join(PROFILES_FILE_NAME);
         let backups_path = backups_dir.join(PROFILES_BACKUP_DIR);
+        let legacy_actions_path = base_dir.join(LEGACY_ACTIONS_FILE_NAME);
         Self {
             file_path,
             backups_path,
+            legacy_actions_path,
         }
     }
 
@@ -519,9 +648,13 @@ impl ProfileRepository {
                 Some("Control+Shift+P"),
             ));
             store.active_profile_id = store.profiles.first().map(|record| record.profile.id);
+            let folded = self.fold_legacy_actions(&mut store)?;
             normalize_profile_store(&mut store);
             self.save(&store)
                 .map_err(|err| ProfileStoreLoadError::io(self.file_path.clone(), err))?;
+            if folded {
+                self.retire_legacy_actions();
+            }
             return Ok(store);
         }
         let data = fs::read_to_string(&self.file_path)
@@ -533,10 +666,76 @@ impl ProfileRepository {
                 self.backups_path.clone(),
             )
         })?;
+        let migrated_from = store.schema_version;
+        let folded = self.migrate(&mut store)?;
         normalize_profile_store(&mut store);
+        if migrated_from < PROFILES_SCHEMA_VERSION {
+            self.save(&store)
+                .map_err(|err| ProfileStoreLoadError::io(self.file_path.clone(), err))?;
+            if folded {
+                self.retire_legacy_actions();
+            }
+        }
         Ok(store)
     }
 
+    /// Upgrades a store read from disk to [`PROFILES_SCHEMA_VERSION`], one
+    /// version at a time. Returns whether the legacy action library was folded
+    /// in and can be retired once the store is saved.
+    fn migrate(&self, store: &mut ProfileStore) -> Result<bool, ProfileStoreLoadError> {
+        let mut folded = false;
+        if store.schema_version < 2 {
+            // v1 -> v2: profile macros already deserialize into `Action`; the
+            // standalone action library moves into the store.
+            folded = self.fold_legacy_actions(store)?;
+            store.schema_version = 2;
+        }
+        Ok(folded)
+    }
+
+    /// A library that cannot be read at all stops the load, so nothing is
+    /// saved and the migration runs again once the file is fixed. Single
+    /// entries that do not parse are skipped and stay in the retired file.
+    fn fold_legacy_actions(&self, store: &mut ProfileStore) -> Result<bool, ProfileStoreLoadError> {
+        let legacy = read_legacy_actions(&self.legacy_actions_path).map_err(|err| {
+            ProfileStoreLoadError::corrupted(
+                self.legacy_actions_path.clone(),
+                format!("legacy actions could not be migrated: {err}"),
+                self.backups_path.clone(),
+            )
+        })?;
+        let Some(legacy) = legacy else {
+            return Ok(false);
+        };
+        for (index, error) in &legacy.skipped {
+            eprintln!(
+                "skipped legacy action #{index} in {} (kept in {MIGRATED_ACTIONS_FILE_NAME}): {error}",
+                self.legacy_actions_path.display()
+            );
+        }
+        for action in store.fold_shared_actions(legacy.actions) {
+            eprintln!(
+                "skipped legacy action '{}' ({}) in {} (kept in {MIGRATED_ACTIONS_FILE_NAME}): its id is already in use",
+                action.name,
+                action.id,
+                self.legacy_actions_path.display()
+            );
+        }
+        Ok(true)
+    }
+
+    fn retire_legacy_actions(&self) {
+        let target = self
+            .legacy_actions_path
+            .with_file_name(MIGRATED_ACTIONS_FILE_NAME);
+        if let Err(err) = fs::rename(&self.legacy_actions_path, &target) {
+            eprintln!(
+                "failed to retire legacy actions file {}: {err}",
+                self.legacy_actions_path.display()
+            );
+        }
+    }
+
     pub fn save(&self, store: &ProfileStore) -> io::Result<()> {
         self.ensure_dirs()?;
         self.create_backup()?;
@@ -620,6 +819,7 @@ fn convert_legacy_profile(source: &AppProfile) -> ProfileRecord {
         activation_rules: Vec::new(),
         root_menu: PieMenuId::new(),
         hold_to_open,
+        priority: 0,
     };
 
     let mut menus = Vec::new();
@@ -690,6 +890,7 @@ fn convert_activation_rules(handles: &[String]) -> Vec<ActivationRule> {
             is_regex: None,
             case_sensitive: None,
             screen_area: None,
+            rules: Vec::new(),
         }];
     }
 
@@ -709,6 +910,7 @@ fn parse_activation_rule(raw: &str) -> ActivationRule {
             is_regex: None,
             case_sensitive: None,
             screen_area: None,
+            rules: Vec::new(),
         };
     }
 
@@ -720,6 +922,7 @@ fn parse_activation_rule(raw: &str) -> ActivationRule {
             is_regex: None,
             case_sensitive: None,
             screen_area: None,
+            rules: Vec::new(),
         };
     }
 
@@ -731,6 +934,7 @@ fn parse_activation_rule(raw: &str) -> ActivationRule {
             is_regex: None,
             case_sensitive: None,
             screen_area: None,
+            rules: Vec::new(),
         };
     }
 
@@ -741,6 +945,7 @@ fn parse_activation_rule(raw: &str) -> ActivationRule {
         is_regex: None,
         case_sensitive: None,
         screen_area: None,
+        rules: Vec::new(),
     }
 }
 
@@ -809,6 +1014,31 @@ pub fn read_legacy_settings(path: &Path) -> io::Result<Option<Vec<AppProfile>>>
     }
 }
 
+/// The legacy action library, read entry by entry.
+#[derive(Debug, Default)]
+pub struct LegacyActions {
+    pub actions: Vec<Action>,
+    /// Position and parse error of every entry that was left out.
+    pub skipped: Vec<(usize, String)>,
+}
+
+pub fn read_legacy_actions(path: &Path) -> io::Result<Option<LegacyActions>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(path)?;
+    let entries = serde_json::from_str::<Vec<serde_json::Value>>(&data)
+        .map_err(|err| other_error(format!("failed to parse actions: {err}")))?;
+    let mut legacy = LegacyActions::default();
+    for (index, entry) in entries.into_iter().enumerate() {
+        match serde_json::from_value::<Action>(entry) {
+            Ok(action) => legacy.actions.push(action),
+            Err(err) => legacy.skipped.push((index, err.to_string())),
+        }
+    }
+    Ok(Some(legacy))
+}
+
 pub fn legacy_settings_file(base_dir: &Path) -> PathBuf {
     base_dir.join(SETTINGS_FILE_NAME)
 }
@@ -1,4 +1,5 @@
 use super::*;
+use crate::domain::{Action, ActionPayload};
 use crate::storage::profile_repository::ProfileRecord;
 use std::fs;
 use tempfile::TempDir;
@@ -14,6 +15,7 @@ fn sample_store() -> ProfileStore {
         activation_rules: Vec::new(),
         root_menu: PieMenuId::new(),
         hold_to_open: false,
+        priority: 0,
     };
     store.profiles.push(ProfileRecord {
         profile,
@@ -76,3 +78,250 @@ fn to_recovery_maps_payload() {
     assert!(info.file_path.ends_with(PROFILES_FILE_NAME));
     assert!(info.backups_dir.ends_with("backups"));
 }
+
+fn v1_store_json(profile_id: ProfileId, menu_id: PieMenuId, action_id: ActionId) -> String {
+    serde_json::json!({
+        "schemaVersion": 1,
+        "profiles": [{
+            "profile": {
+                "id": profile_id,
+                "name": "Legacy",
+                "enabled": true,
+                "activationRules": [],
+                "rootMenu": menu_id,
+            },
+            "menus": [],
+            "actions": [{
+                "id": action_id,
+                "name": "Legacy Macro",
+                "timeoutMs": 1500,
+                "steps": [{
+                    "id": ActionId::new(),
+                    "order": 0,
+                    "kind": "delay",
+                    "duration_ms": 20,
+                }],
+            }],
+        }],
+        "activeProfileId": profile_id,
+    })
+    .to_string()
+}
+
+#[test]
+fn load_migrates_v1_store_and_folds_legacy_actions() {
+    let tmp = TempDir::new().expect("tempdir");
+    let backups_root = tmp.path().join("backups");
+    let repo = ProfileRepository::new(tmp.path(), &backups_root);
+
+    let profile_id = ProfileId::new();
+    let macro_id = ActionId::new();
+    fs::write(
+        tmp.path().join(PROFILES_FILE_NAME),
+        v1_store_json(profile_id, PieMenuId::new(), macro_id),
+    )
+    .expect("write v1 store");
+
+    let shared = Action::new(
+        "Shared",
+        ActionPayload::SystemCommand {
+            command: "true".into(),
+        },
+    );
+    let duplicate = Action {
+        id: macro_id,
+        ..Action::new("Shadowed", ActionPayload::Macro { steps: vec![] })
+    };
+    fs::write(
+        tmp.path().join(LEGACY_ACTIONS_FILE_NAME),
+        serde_json::to_string(&vec![shared.clone(), duplicate]).expect("serialize actions"),
+    )
+    .expect("write legacy actions");
+
+    let store = repo.load().expect("load migrated store");
+    assert_eq!(store.schema_version, PROFILES_SCHEMA_VERSION);
+
+    let migrated = &store.profiles[0].actions[0];
+    assert_eq!(migrated.id, macro_id);
+    assert_eq!(migrated.timeout_ms, Some(1500));
+    match &migrated.payload {
+        ActionPayload::Macro { steps } => assert_eq!(steps.len(), 1),
+        other => panic!("expected macro payload, got {other:?}"),
+    }
+
+    assert_eq!(store.actions.len(), 1, "duplicate ids must not be folded");
+    assert_eq!(store.actions[0].id, shared.id);
+    assert_eq!(
+        store
+            .find_action(Some(profile_id), &macro_id)
+            .map(|action| action.name.as_str()),
+        Some("Legacy Macro")
+    );
+    assert!(
+        store.find_action(None, &macro_id).is_none(),
+        "profile actions are only found from their own profile"
+    );
+    assert_eq!(
+        store.find_action(None, &shared.id).map(|action| action.id),
+        Some(shared.id)
+    );
+
+    assert!(!tmp.path().join(LEGACY_ACTIONS_FILE_NAME).exists());
+    assert!(tmp.path().join(MIGRATED_ACTIONS_FILE_NAME).exists());
+
+    let persisted: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(tmp.path().join(PROFILES_FILE_NAME)).expect("read store"),
+    )
+    .expect("parse store");
+    assert_eq!(persisted["schemaVersion"], PROFILES_SCHEMA_VERSION);
+    assert_eq!(
+        persisted["profiles"][0]["actions"][0]["payload"]["kind"],
+        "macro"
+    );
+}
+
+#[test]
+fn unreadable_legacy_actions_stop_the_migration_until_fixed() {
+    let tmp = TempDir::new().expect("tempdir");
+    let backups_root = tmp.path().join("backups");
+    let repo = ProfileRepository::new(tmp.path(), &backups_root);
+    let store_path = tmp.path().join(PROFILES_FILE_NAME);
+    let legacy_path = tmp.path().join(LEGACY_ACTIONS_FILE_NAME);
+    let v1 = v1_store_json(ProfileId::new(), PieMenuId::new(), ActionId::new());
+    fs::write(&store_path, &v1).expect("write v1 store");
+    fs::write(&legacy_path, "{ not an array").expect("write legacy actions");
+
+    match repo.load().expect_err("library cannot be read") {
+        ProfileStoreLoadError::Corrupted { file_path, .. } => assert_eq!(file_path, legacy_path),
+        other => panic!("unexpected error: {other:?}"),
+    }
+    assert_eq!(
+        fs::read_to_string(&store_path).expect("read store"),
+        v1,
+        "the store stays at v1 so the fold is retried"
+    );
+
+    let shared = Action::new(
+        "Shared",
+        ActionPayload::SystemCommand {
+            command: "true".into(),
+        },
+    );
+    let entries = serde_json::json!([
+        shared,
+        { "id": ActionId::new(), "name": "Broken", "payload": { "kind": "teleport" } },
+    ]);
+    fs::write(&legacy_path, entries.to_string()).expect("write legacy actions");
+
+    let legacy = read_legacy_actions(&legacy_path)
+        .expect("read legacy actions")
+        .expect("legacy actions present");
+    assert_eq!(legacy.skipped.len(), 1);
+    assert_eq!(legacy.skipped[0].0, 1);
+    assert!(
+        legacy.skipped[0].1.contains("teleport"),
+        "the error names the bad field: {}",
+        legacy.skipped[0].1
+    );
+
+    let store = repo.load().expect("load migrated store");
+    assert_eq!(store.actions.len(), 1);
+    assert_eq!(store.actions[0].id, shared.id);
+    let retired = fs::read_to_string(tmp.path().join(MIGRATED_ACTIONS_FILE_NAME))
+        .expect("read retired library");
+    assert!(retired.contains("Broken"), "skipped entries are kept");
+}
+
+#[test]
+fn untrusted_flag_is_found_in_profile_actions() {
+    let mut store = sample_store();
+    let mut imported = Action::new(
+        "Imported",
+        ActionPayload::SystemCommand {
+            command: "echo hi".into(),
+        },
+    );
+    imported.untrusted = true;
+    let reviewed = Action::new("Reviewed", imported.payload.clone());
+    let (imported_id, reviewed_id) = (imported.id, reviewed.id);
+    store.profiles[0].actions.push(imported);
+    store.actions.push(reviewed);
+
+    assert!(store.is_untrusted(&imported_id));
+    assert!(!store.is_untrusted(&reviewed_id));
+    assert!(!store.is_untrusted(&ActionId::new()));
+}
+
+#[test]
+fn load_leaves_current_store_untouched() {
+    let tmp = TempDir::new().expect("tempdir");
+    let backups_root = tmp.path().join("backups");
+    let repo = ProfileRepository::new(tmp.path(), &backups_root);
+    write_store(&tmp, &sample_store());
+    fs::write(tmp.path().join(LEGACY_ACTIONS_FILE_NAME), "[]").expect("write legacy actions");
+
+    let store = repo.load().expect("load store");
+    assert!(store.actions.is_empty());
+    assert!(
+        tmp.path().join(LEGACY_ACTIONS_FILE_NAME).exists(),
+        "legacy file is only consumed by the v1 migration"
+    );
+}
+
+#[test]
+fn rule_groups_round_trip_through_save_and_load() {
+    let tmp = TempDir::new().expect("tempdir");
+    let repo = ProfileRepository::new(tmp.path(), &tmp.path().join("backups"));
+    let rule = |mode, value: Option<&str>| ActivationRule {
+        mode,
+        value: value.map(str::to_string),
+        negate: None,
+        is_regex: None,
+        case_sensitive: None,
+        screen_area: None,
+        rules: Vec::new(),
+    };
+    let mut store = sample_store();
+    store.profiles[0].profile.activation_rules = vec![ActivationRule {
+        // Groups carry no value of their own.
+        value: Some("ignored".into()),
+        rules: vec![
+            ActivationRule {
+                rules: vec![rule(ActivationMatchMode::Always, None)],
+                ..rule(ActivationMatchMode::ProcessName, Some(" code "))
+            },
+            ActivationRule {
+                negate: Some(true),
+                rules: vec![rule(
+                    ActivationMatchMode::WindowTitle,
+                    Some("regex: Settings "),
+                )],
+                ..rule(ActivationMatchMode::Any, None)
+            },
+        ],
+        ..rule(ActivationMatchMode::All, None)
+    }];
+
+    repo.save(&store).expect("save store");
+    let loaded = repo.load().expect("load store");
+    let group = &loaded.profiles[0].profile.activation_rules[0];
+    assert_eq!(group.mode, ActivationMatchMode::All);
+    assert_eq!(group.value, None);
+    assert_eq!(group.rules.len(), 2);
+
+    let process = &group.rules[0];
+    assert_eq!(process.value.as_deref(), Some("code"));
+    assert!(process.rules.is_empty(), "only groups keep nested rules");
+
+    let not_settings = &group.rules[1];
+    assert_eq!(not_settings.mode, ActivationMatchMode::Any);
+    assert_eq!(not_settings.negate, Some(true));
+    let title = &not_settings.rules[0];
+    assert_eq!(title.value.as_deref(), Some("regex:Settings"));
+    assert_eq!(title.is_regex, Some(true));
+
+    let json = serde_json::to_value(group).expect("serialize group");
+    assert_eq!(json["mode"], "all");
+    assert_eq!(json["rules"][1]["rules"][0]["mode"], "window_title");
+    assert!(json["rules"][0].get("rules").is_none());
+}
@@ -367,6 +367,9 @@ export function App() {
     handleSelect,
     lastAction,
     clearLastAction,
+    pendingConfirmation,
+    confirmPendingAction,
+    cancelPendingAction,
     lastSafeModeReason,
     activeProfile: pieMenuActiveProfile,
     activationMode: pieMenuActivationMode,
@@ -418,11 +421,44 @@ export function App() {
         id: slice.id,
         label: slice.label || `Slice ${order + 1}`,
         order: slice.order ?? order,
+        actionId: slice.action ?? null,
       }));
 
     return derivedSlices.length ? derivedSlices : fallbackSlices;
   }, [activeProfileId, pieMenuActiveProfile, profiles, systemActiveProfile]);
 
+  // Selections made in the overlay window are forwarded here to run.
+  useEffect(() => {
+    if (!isTauriEnvironment()) {
+      return;
+    }
+
+    let disposed = false;
+    let unlisten: (() => void) | undefined;
+    void import('@tauri-apps/api/event')
+      .then(({ listen }) =>
+        listen<{ sliceId: string }>('pie-overlay://select', (event) => {
+          const { sliceId } = event.payload;
+          handleSelect(sliceId, menuSlices.find((slice) => slice.id === sliceId));
+        }),
+      )
+      .then((dispose) => {
+        if (disposed) {
+          dispose();
+        } else {
+          unlisten = dispose;
+        }
+      })
+      .catch((error) => {
+        console.error('Failed to listen for pie overlay selections', error);
+      });
+
+    return () => {
+      disposed = true;
+      unlisten?.();
+    };
+  }, [handleSelect, menuSlices]);
+
   const previousMatchKindRef = useRef<string | null>(null);
 
   const isProfileConflictOnly = useMemo(
@@ -861,7 +897,13 @@ export function App() {
           />
         </div>
       )}
-      <ActionToast action={lastAction} onDismiss={clearLastAction} />
+      <ActionToast
+        action={lastAction}
+        onDismiss={clearLastAction}
+        confirmation={pendingConfirmation}
+        onConfirm={confirmPendingAction}
+        onCancel={cancelPendingAction}
+      />
       <FullscreenNotice visible={!!lastSafeModeReason} reason={lastSafeModeReason ?? ''} />
       <LogPanel isOpen={isLogPanelOpen} onClose={() => setIsLogPanelOpen(false)} />
       {isLinux && (
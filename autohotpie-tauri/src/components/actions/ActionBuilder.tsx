@@ -352,11 +352,11 @@ function validateMacro(action: ActionDefinition): ActionValidationResult {
     }
   });
 
-  if (action.timeoutMs < 500) {
+  if (action.timeoutMs != null && action.timeoutMs < 500) {
     warnings.push('Timeout below 500 ms may interrupt longer scripts.');
   }
 
-  if (action.timeoutMs > 10000) {
+  if (action.timeoutMs != null && action.timeoutMs > 10000) {
     warnings.push('Timeout above 10s may block input too long.');
   }
 
@@ -426,6 +426,10 @@ export function ActionBuilder({ value, disabled = false, onChange, onValidate }:
 
   const handleTimeoutChange = useCallback(
     (event: React.ChangeEvent<HTMLInputElement>) => {
+      if (!event.target.value.trim().length) {
+        handleChange({ ...action, timeoutMs: null });
+        return;
+      }
       const nextTimeout = Number.parseInt(event.target.value, 10);
       handleChange({
         ...action,
@@ -553,7 +557,8 @@ export function ActionBuilder({ value, disabled = false, onChange, onValidate }:
                 max={15000}
                 step={100}
                 className="mt-1 w-full rounded-2xl border border-white/10 bg-black/30 px-4 py-2 text-sm text-white focus:border-accent/60 focus:outline-none focus:ring-2 focus:ring-accent/20"
-                value={action.timeoutMs}
+                placeholder="No limit"
+                value={action.timeoutMs ?? ''}
                 onChange={handleTimeoutChange}
                 disabled={disabled}
               />
@@ -1,33 +1,43 @@
 import { useEffect } from 'react';
 import { AnimatePresence, motion } from 'framer-motion';
-import type { LastActionState } from '../../hooks/usePieMenuHotkey';
+import type { LastActionState, PendingConfirmation } from '../../hooks/usePieMenuHotkey';
 
 interface ActionToastProps {
   action: LastActionState | null;
   onDismiss?: () => void;
+  /** Shown instead of the last result until the user answers it. */
+  confirmation?: PendingConfirmation | null;
+  onConfirm?: () => void;
+  onCancel?: () => void;
 }
 
 const statusStyles: Record<LastActionState['status'], string> = {
   success: 'border-emerald-400/70 text-emerald-100 shadow-[0_0_30px_rgba(16,185,129,0.25)]',
   failure: 'border-rose-400/70 text-rose-100 shadow-[0_0_30px_rgba(244,63,94,0.25)]',
   skipped: 'border-amber-400/70 text-amber-100 shadow-[0_0_30px_rgba(251,191,36,0.2)]',
+  timed_out: 'border-orange-400/70 text-orange-100 shadow-[0_0_30px_rgba(251,146,60,0.25)]',
+  cancelled: 'border-slate-400/70 text-slate-100 shadow-[0_0_30px_rgba(148,163,184,0.2)]',
 };
 
 const statusLabel: Record<LastActionState['status'], string> = {
   success: 'Action completed',
   failure: 'Action failed',
   skipped: 'Action skipped',
+  timed_out: 'Action timed out',
+  cancelled: 'Action cancelled',
 };
 
 const statusBadge: Record<LastActionState['status'], string> = {
   success: 'SUCCESS',
   failure: 'ERROR',
   skipped: 'SKIPPED',
+  timed_out: 'TIMEOUT',
+  cancelled: 'CANCELLED',
 };
 
-export function ActionToast({ action, onDismiss }: ActionToastProps) {
+export function ActionToast({ action, onDismiss, confirmation, onConfirm, onCancel }: ActionToastProps) {
   useEffect(() => {
-    if (!action || !onDismiss) {
+    if (!action || !onDismiss || confirmation) {
       return;
     }
 
@@ -36,16 +46,69 @@ export function ActionToast({ action, onDismiss }: ActionToastProps) {
     }, 5000);
 
     return () => window.clearTimeout(timeout);
-  }, [action, onDismiss]);
+  }, [action, confirmation, onDismiss]);
 
   const helperMessage = action
     ? action.status === 'failure'
       ? 'Try launching the action again or review the audit log for details.'
       : action.status === 'skipped'
         ? 'This action was skipped. Adjust its configuration if you expected it to run.'
-        : 'Action completed successfully.'
+        : action.status === 'timed_out'
+          ? 'This action ran past its time limit and was stopped.'
+          : action.status === 'cancelled'
+            ? 'This action was cancelled before it finished.'
+            : 'Action completed successfully.'
     : '';
 
+  if (confirmation) {
+    return (
+      <AnimatePresence>
+        <motion.div
+          key={`confirm-${confirmation.actionId}`}
+          initial={{ opacity: 0, y: 24, scale: 0.95 }}
+          animate={{ opacity: 1, y: 0, scale: 1 }}
+          exit={{ opacity: 0, y: 24, scale: 0.95 }}
+          transition={{ type: 'spring', stiffness: 220, damping: 26 }}
+          className="pointer-events-auto fixed bottom-10 left-1/2 z-50 w-[min(420px,92vw)] -translate-x-1/2"
+        >
+          <div
+            className={`relative overflow-hidden rounded-3xl border bg-[#0f111a]/95 backdrop-blur-xl ${statusStyles.skipped}`}
+            role="alertdialog"
+            aria-live="assertive"
+          >
+            <div className="absolute inset-0 bg-[radial-gradient(circle_at_top,_rgba(255,255,255,0.12),transparent_70%)]" />
+            <div className="relative px-6 py-5">
+              <span className="text-xs font-semibold uppercase tracking-[0.4em] text-text-muted">CONFIRM</span>
+              <h4 className="mt-3 text-lg font-semibold text-white">Run this action?</h4>
+              <p className="mt-1 line-clamp-2 text-sm text-white/80">{confirmation.actionName}</p>
+              {confirmation.reasons.map((reason) => (
+                <p key={reason} className="mt-2 text-xs text-white/60">
+                  {reason}
+                </p>
+              ))}
+              <div className="mt-4 flex justify-end gap-2">
+                <button
+                  type="button"
+                  className="rounded-full border border-white/10 bg-white/5 px-3 py-1 text-[11px] uppercase tracking-[0.3em] text-white/70 transition hover:bg-white/10"
+                  onClick={onCancel}
+                >
+                  CANCEL
+                </button>
+                <button
+                  type="button"
+                  className="rounded-full border border-amber-400/70 bg-amber-400/10 px-3 py-1 text-[11px] uppercase tracking-[0.3em] text-amber-100 transition hover:bg-amber-400/20"
+                  onClick={onConfirm}
+                >
+                  RUN
+                </button>
+              </div>
+            </div>
+          </div>
+        </motion.div>
+      </AnimatePresence>
+    );
+  }
+
   return (
     <AnimatePresence>
       {action && (
@@ -9,6 +9,8 @@ export interface PieSliceDefinition {
   accentToken?: string;
   disabled?: boolean;
   color?: string | null;
+  /** Action run when the slice is selected. */
+  actionId?: string | null;
 }
 
 export interface PieMenuProps {
@@ -1,12 +1,15 @@
 import { useEffect, useMemo, useState } from 'react';
 import clsx from 'clsx';
+import { invoke } from '@tauri-apps/api/core';
 import {
   type ActivationMatchMode,
   type ActivationRule,
   type ProfileRecord,
+  type RankingCriterion,
   useProfileStore,
 } from '../../state/profileStore';
 import { useLocalization } from '../../hooks/useLocalization';
+import { isTauriEnvironment } from '../../utils/tauriEnvironment';
 
 interface ScreenArea {
   x: number;
@@ -63,6 +66,16 @@ const MODE_KEYS: Record<ActivationMatchMode, { label: string; hint: string }> =
   },
 };
 
+// Used in browser preview; the app asks the router for its order.
+const DEFAULT_RANKING: RankingCriterion[] = ['priority', 'specificity', 'recency', 'order'];
+
+const RANKING_KEYS: Record<RankingCriterion, string> = {
+  priority: 'contextPanel.ranking.priority',
+  specificity: 'contextPanel.ranking.specificity',
+  recency: 'contextPanel.ranking.recency',
+  order: 'contextPanel.ranking.order',
+};
+
 function generateRuleId(): string {
   if (typeof crypto !== 'undefined' && typeof crypto.randomUUID === 'function') {
     return crypto.randomUUID();
@@ -157,8 +170,30 @@ function normalizeScreenArea(candidate: Record<string, unknown>): ScreenArea | n
   return null;
 }
 
+type ValueRule = ActivationRule & { mode: ActivationMatchMode };
+
+function isValueRule(rule: ActivationRule): rule is ValueRule {
+  return rule.mode !== 'all' && rule.mode !== 'any' && rule.mode !== 'not';
+}
+
+// Groups are not editable here yet; they are kept as they are on save.
+// Edited value rules fill the value-rule slots in order, so each group
+// stays between the same rules; extra value rules go at the end.
+function mergeValueRules(original: ActivationRule[], values: ActivationRule[]): ActivationRule[] {
+  const pending = [...values];
+  const merged: ActivationRule[] = [];
+  for (const rule of original) {
+    if (!isValueRule(rule)) {
+      merged.push(rule);
+    } else if (pending.length) {
+      merged.push(pending.shift() as ActivationRule);
+    }
+  }
+  return [...merged, ...pending];
+}
+
 function deserializeRules(rules: ActivationRule[]): RuleDraft[] {
-  return rules.map((rule) => {
+  return rules.filter(isValueRule).map((rule) => {
     const decoded = decodeRuleValue(rule);
     return {
       id: generateRuleId(),
@@ -277,6 +312,22 @@ export function ContextConditionsPanel({ profile }: ContextConditionsPanelProps)
   const [messages, setMessages] = useState<string[]>([]);
   const [isSaving, setIsSaving] = useState(false);
   const [modePickerValue, setModePickerValue] = useState<string>('');
+  const savedPriority = profile.profile.priority ?? 0;
+  const [priority, setPriority] = useState<number>(savedPriority);
+  const [ranking, setRanking] = useState<RankingCriterion[]>(DEFAULT_RANKING);
+
+  useEffect(() => {
+    if (!isTauriEnvironment()) {
+      return;
+    }
+    invoke<RankingCriterion[]>('profile_ranking_order')
+      .then(setRanking)
+      .catch((error: unknown) => console.error('Failed to load profile ranking order', error));
+  }, []);
+
+  useEffect(() => {
+    setPriority(savedPriority);
+  }, [savedPriority, profile.profile.id]);
 
   useEffect(() => {
     const drafts = deserializeRules(profile.profile.activationRules ?? []);
@@ -293,7 +344,7 @@ export function ContextConditionsPanel({ profile }: ContextConditionsPanelProps)
 
   const originalSignature = useMemo(() => {
     return JSON.stringify(
-      (profile.profile.activationRules ?? []).map((rule) => ({
+      (profile.profile.activationRules ?? []).filter(isValueRule).map((rule) => ({
         mode: rule.mode,
         value: rule.value ?? null,
         negate: Boolean(rule.negate),
@@ -302,7 +353,7 @@ export function ContextConditionsPanel({ profile }: ContextConditionsPanelProps)
   }, [profile.profile.activationRules, profile.profile.id]);
 
   const currentSignature = useMemo(() => serializeDrafts(ruleDrafts), [ruleDrafts]);
-  const hasChanges = originalSignature !== currentSignature;
+  const hasChanges = originalSignature !== currentSignature || priority !== savedPriority;
 
   const activeRule = useMemo(
     () => ruleDrafts.find((rule) => rule.id === activeRuleId) ?? null,
@@ -333,7 +384,11 @@ export function ContextConditionsPanel({ profile }: ContextConditionsPanelProps)
     try {
       const saved = await updateProfileActivationRules(
         profile.profile.id,
-        ruleDrafts.map((draft) => encodeRule(draft)),
+        mergeValueRules(
+          profile.profile.activationRules ?? [],
+          ruleDrafts.map((draft) => encodeRule(draft)),
+        ),
+        priority,
       );
       if (saved) {
         setMessages([t('contextPanel.savedMessage')]);
@@ -677,6 +732,34 @@ export function ContextConditionsPanel({ profile }: ContextConditionsPanelProps)
         </div>
       )}
 
+      <div className="grid gap-3 rounded-2xl border border-white/10 bg-black/20 p-4 md:grid-cols-2">
+        <div className="space-y-2">
+          <label className="text-xs uppercase tracking-[0.3em] text-white/40" htmlFor="context-priority">
+            {t('contextPanel.priorityLabel')}
+          </label>
+          <input
+            id="context-priority"
+            type="number"
+            step={1}
+            className="w-full rounded-2xl border border-white/10 bg-black/40 px-3 py-2 text-sm text-white/80"
+            value={priority}
+            onChange={(event) => {
+              const value = Number.parseInt(event.target.value, 10);
+              setPriority(Number.isFinite(value) ? value : 0);
+            }}
+          />
+          <p className="text-xs text-white/50">{t('contextPanel.priorityHint')}</p>
+        </div>
+        <div className="space-y-2">
+          <p className="text-xs uppercase tracking-[0.3em] text-white/40">{t('contextPanel.rankingTitle')}</p>
+          <ol className="list-decimal space-y-1 pl-5 text-xs text-white/60">
+            {ranking.map((criterion) => (
+              <li key={criterion}>{t(RANKING_KEYS[criterion])}</li>
+            ))}
+          </ol>
+        </div>
+      </div>
+
       {(messages.length > 0 || validationErrors.length > 0) && (
         <div className="space-y-2 rounded-2xl border border-white/10 bg-white/5 p-3 text-xs text-white/70">
           {messages.map((message, index) => (
@@ -700,6 +783,7 @@ export function ContextConditionsPanel({ profile }: ContextConditionsPanelProps)
             className="rounded-2xl border border-white/15 px-4 py-2 text-sm text-white/70 transition hover:border-white/25 hover:text-white/85"
             onClick={() => {
               setRuleDrafts(deserializeRules(profile.profile.activationRules ?? []));
+              setPriority(savedPriority);
               setMessages([]);
             }}
             disabled={!hasChanges || isSaving}
@@ -139,6 +139,10 @@ export function MenuBarToggle({
             return t('common.status.error');
           case 'skipped':
             return t('common.status.skipped');
+          case 'timed_out':
+            return t('common.status.timedOut');
+          case 'cancelled':
+            return t('common.status.cancelled');
           default:
             return t('common.status.error');
         }
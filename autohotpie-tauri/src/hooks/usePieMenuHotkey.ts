@@ -3,6 +3,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { isTauriEnvironment } from '../utils/tauriEnvironment';
 import type { PieSliceDefinition } from '../components/pie/PieMenu';
 import type { ActionEventPayload, ActionEventStatus } from '../types/actionEvents';
+import type { PolicyDecision } from '../types/actions';
 import type { ActiveProfileSnapshot } from '../types/hotkeys';
 
 import { useHotkeyStore } from '../state/hotkeyStore';
@@ -219,6 +220,13 @@ export interface LastActionState {
   invocationId: string | null;
 }
 
+/** A slice action the execution policy wants the user to confirm before it runs. */
+export interface PendingConfirmation {
+  actionId: string;
+  actionName: string;
+  reasons: string[];
+}
+
 export interface UsePieMenuHotkeyOptions {
   hotkeyEvent?: string;
   autoCloseMs?: number;
@@ -243,6 +251,9 @@ export interface PieMenuHotkeyState {
   setActiveSlice: (sliceId: string | null) => void;
   handleSelect: (sliceId: string, slice?: PieSliceDefinition) => void;
   clearLastAction: () => void;
+  pendingConfirmation: PendingConfirmation | null;
+  confirmPendingAction: () => void;
+  cancelPendingAction: () => void;
   recordActionOutcome: (payload: {
     id: string;
     name: string;
@@ -372,6 +383,7 @@ export function usePieMenuHotkey(options: UsePieMenuHotkeyOptions = {}): PieMenu
   }, [isOpen]);
   const [activeSliceId, setActiveSliceId] = useState<string | null>(null);
   const [lastAction, setLastAction] = useState<LastActionState | null>(null);
+  const [pendingConfirmation, setPendingConfirmation] = useState<PendingConfirmation | null>(null);
   const [lastSafeModeReason, setLastSafeModeReason] = useState<string | null>(null);
   const [activeProfile, setActiveProfile] = useState<ActiveProfileSnapshot | null>(initialActiveProfile ?? null);
   const [activationMode, setActivationMode] = useState<'toggle' | 'hold'>(resolvedActivationModeRef.current);
@@ -1428,6 +1440,60 @@ export function usePieMenuHotkey(options: UsePieMenuHotkeyOptions = {}): PieMenu
     clearTimer();
   }, [clearTimer, setIsOpenSafe]);
 
+  // Results arrive through the action event listeners; only outcomes that
+  // never reach the runner are recorded here.
+  const runSliceAction = useCallback(
+    async (actionId: string, actionName: string, confirmed: boolean) => {
+      const invokeFn = getTauriInvoke();
+      if (!invokeFn) {
+        return;
+      }
+      try {
+        if (!confirmed) {
+          const decision = (await invokeFn('check_action_policy', { actionId })) as PolicyDecision;
+          if (decision.verdict === 'deny') {
+            recordActionOutcome({
+              id: actionId,
+              name: actionName,
+              status: 'skipped',
+              message: decision.reasons.join('; ') || null,
+            });
+            return;
+          }
+          if (decision.verdict === 'confirm') {
+            setPendingConfirmation({ actionId, actionName, reasons: decision.reasons });
+            return;
+          }
+        }
+        await invokeFn('run_action', { actionId, confirmed });
+      } catch (error) {
+        console.error('Failed to run slice action', error);
+      }
+    },
+    [recordActionOutcome],
+  );
+
+  const confirmPendingAction = useCallback(() => {
+    if (!pendingConfirmation) {
+      return;
+    }
+    setPendingConfirmation(null);
+    void runSliceAction(pendingConfirmation.actionId, pendingConfirmation.actionName, true);
+  }, [pendingConfirmation, runSliceAction]);
+
+  const cancelPendingAction = useCallback(() => {
+    if (!pendingConfirmation) {
+      return;
+    }
+    setPendingConfirmation(null);
+    recordActionOutcome({
+      id: pendingConfirmation.actionId,
+      name: pendingConfirmation.actionName,
+      status: 'skipped',
+      message: 'Run cancelled at the confirmation prompt.',
+    });
+  }, [pendingConfirmation, recordActionOutcome]);
+
   const handleSelect = useCallback(
     (sliceId: string, slice?: PieSliceDefinition) => {
       setActiveSliceId(sliceId);
@@ -1438,13 +1504,16 @@ export function usePieMenuHotkey(options: UsePieMenuHotkeyOptions = {}): PieMenu
           status: 'success',
         });
       }
+      if (isTauriEnvironment() && slice?.actionId) {
+        void runSliceAction(slice.actionId, slice.label, false);
+      }
       if (autoCloseMs === 0) {
         setIsOpenSafe(false);
       } else {
         scheduleAutoClose();
       }
     },
-    [autoCloseMs, recordActionOutcome, scheduleAutoClose],
+    [autoCloseMs, recordActionOutcome, runSliceAction, scheduleAutoClose],
   );
 
   const setActiveSlice = useCallback((sliceId: string | null) => {
@@ -1471,20 +1540,26 @@ export function usePieMenuHotkey(options: UsePieMenuHotkeyOptions = {}): PieMenu
       setActiveSlice: setActiveSliceId,
       handleSelect,
       clearLastAction,
+      pendingConfirmation,
+      confirmPendingAction,
+      cancelPendingAction,
       recordActionOutcome,
     }),
     [
       activationMode,
       activeProfile,
       activeSliceId,
+      cancelPendingAction,
       clearLastAction,
       close,
+      confirmPendingAction,
       handleSelect,
       isOpen,
       lastAction,
       lastSafeModeReason,
       currentSafeModeReason,
       open,
+      pendingConfirmation,
       recordActionOutcome,
       setActiveSlice,
       toggle,
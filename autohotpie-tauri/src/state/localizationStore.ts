@@ -143,6 +143,7 @@ const FALLBACK_STRINGS: Record<string, string> = {
   'localization.switcher.runtimeLabel': 'Runtime missing',
   'tray.menu.refresh': 'Refresh localization',
   'tray.menu.open': 'Open application',
+  'tray.menu.triggers': 'Triggers',
 };
 
 const FALLBACK_PACK: LocalizationPack = {
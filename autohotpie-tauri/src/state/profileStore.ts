@@ -5,8 +5,8 @@ import type { UnlistenFn } from '@tauri-apps/api/event';
 import { isTauriEnvironment } from '../utils/tauriEnvironment';
 import mockContextProfilesJson from '../mocks/context-profiles.json' assert { type: 'json' };
 import type { HotkeyRegistrationStatus } from '../types/hotkeys';
-import type { ActionDefinition } from '../types/actions';
-import { cloneActionDefinition } from '../types/actions';
+import type { ActionDefinition, StoredAction } from '../types/actions';
+import { fromStoredAction, toStoredAction } from '../types/actions';
 
 export type ActivationMatchMode =
   | 'always'
@@ -16,6 +16,9 @@ export type ActivationMatchMode =
   | 'screen_area'
   | 'custom';
 
+/** Modes that combine the nested `rules` instead of matching a value. */
+export type ActivationGroupMode = 'all' | 'any' | 'not';
+
 export interface ScreenArea {
   x: number;
   y: number;
@@ -24,12 +27,13 @@ export interface ScreenArea {
 }
 
 export interface ActivationRule {
-  mode: ActivationMatchMode;
+  mode: ActivationMatchMode | ActivationGroupMode;
   value?: string | null;
   negate?: boolean | null;
   isRegex?: boolean | null;
   caseSensitive?: boolean | null;
   screenArea?: ScreenArea | null;
+  rules?: ActivationRule[];
 }
 
 export interface Profile {
@@ -41,8 +45,13 @@ export interface Profile {
   activationRules: ActivationRule[];
   rootMenu: string;
   holdToOpen?: boolean | null;
+  /** Higher wins when several profiles match the same window. */
+  priority?: number | null;
 }
 
+/** How the router breaks ties between matching profiles; see `profile_ranking_order`. */
+export type RankingCriterion = 'priority' | 'specificity' | 'recency' | 'order';
+
 export interface PieSlice {
   id: string;
   label: string;
@@ -75,7 +84,7 @@ export interface ProfileRecord {
 }
 
 type ProfileRecordLike = Omit<ProfileRecord, 'actions'> & {
-  actions?: ActionDefinition[] | null;
+  actions?: (StoredAction | ActionDefinition)[] | null;
 };
 
 export interface ProfileStorePayload {
@@ -83,6 +92,7 @@ export interface ProfileStorePayload {
   profiles: ProfileRecordLike[];
   activeProfileId?: string | null;
   migratedFromSettings?: string | null;
+  actions?: StoredAction[] | null;
 }
 
 export interface ProfileRecoveryState {
@@ -110,6 +120,7 @@ interface ProfileStoreState {
   updateProfileActivationRules: (
     profileId: string,
     rules: ActivationRule[],
+    priority?: number,
   ) => Promise<ProfileRecord | null>;
   deleteProfile: (profileId: string) => Promise<void>;
   activateProfile: (profileId: string) => Promise<void>;
@@ -147,7 +158,7 @@ function normalizeProfileRecord(record: ProfileRecordLike): ProfileRecord {
       appearance: { ...menu.appearance },
       slices: (menu.slices ?? []).map((slice) => ({ ...slice })),
     })),
-    actions: (record.actions ?? []).map((action) => cloneActionDefinition(action)),
+    actions: (record.actions ?? []).map((action) => fromStoredAction(action)),
     createdAt: record.createdAt ?? null,
     updatedAt: record.updatedAt ?? null,
   };
@@ -492,7 +503,7 @@ export const useProfileStore = create<ProfileStoreState>()((set, get) => ({
       markProfilesReady(true);
     }
   },
-  async updateProfileActivationRules(profileId, rules) {
+  async updateProfileActivationRules(profileId, rules, priority) {
     if (!isTauriEnvironment()) {
       set({ error: 'Profiles cannot be modified in browser preview mode.' });
       return null;
@@ -508,6 +519,7 @@ export const useProfileStore = create<ProfileStoreState>()((set, get) => ({
         ...record.profile,
         activationRules: rules,
         holdToOpen: record.profile.holdToOpen ?? false,
+        priority: priority ?? record.profile.priority ?? 0,
       },
     };
     const saved = await get().saveProfile(updated);
@@ -569,9 +581,9 @@ export const useProfileStore = create<ProfileStoreState>()((set, get) => ({
         globalHotkey: input?.globalHotkey ?? null,
         holdToOpen: false,
       };
-      const record = await invoke<ProfileRecord>('create_profile', { payload });
+      const record = await invoke<ProfileRecordLike>('create_profile', { payload });
       await get().refreshProfiles();
-      return record;
+      return normalizeProfileRecord(record);
     } catch (error) {
       const message = toErrorMessage(error);
       if (message.startsWith('{')) {
@@ -599,10 +611,12 @@ export const useProfileStore = create<ProfileStoreState>()((set, get) => ({
       return null;
     }
     try {
-      const saved = await invoke<ProfileRecord>('save_profile', { record });
+      const saved = await invoke<ProfileRecordLike>('save_profile', {
+        record: { ...record, actions: record.actions.map((action) => toStoredAction(action)) },
+      });
       set({ suppressHotkeyConflicts: false, validationErrors: [] });
       await get().refreshProfiles();
-      return saved;
+      return normalizeProfileRecord(saved);
     } catch (error) {
       const message = toErrorMessage(error);
       if (message.startsWith('{')) {
@@ -1,4 +1,14 @@
-export type ActionEventStatus = 'success' | 'failure' | 'skipped';
+export type ActionEventStatus = 'success' | 'failure' | 'skipped' | 'timed_out' | 'cancelled';
+
+export interface ActionOutput {
+  exitCode?: number | null;
+  /** Response status of an HTTP request action. */
+  httpStatus?: number | null;
+  stdout: string;
+  stderr: string;
+  stdoutTruncated: boolean;
+  stderrTruncated: boolean;
+}
 
 export interface ActionEventPayload {
   eventId: string;
@@ -9,4 +19,45 @@ export interface ActionEventPayload {
   message?: string | null;
   timestamp: string;
   invocationId?: string | null;
+  output?: ActionOutput | null;
+  /** 1 for the first attempt; retried actions emit one event per attempt. */
+  attempt: number;
+  profileId?: string | null;
+}
+
+/** Filters for `query_action_history`; times are Unix milliseconds. */
+export interface HistoryQuery {
+  actionId?: string;
+  statuses?: ActionEventStatus[];
+  profileId?: string;
+  invocationId?: string;
+  fromMs?: number;
+  toMs?: number;
+  limit?: number;
+}
+
+export interface ActionStats {
+  actionId: string;
+  name: string;
+  runs: number;
+  failures: number;
+  failureRate: number;
+  p50DurationMs: number;
+  p95DurationMs: number;
+}
+
+export interface HistoryReport {
+  events: ActionEventPayload[];
+  truncated: boolean;
+  stats: ActionStats[];
+}
+
+export interface RunningAction {
+  invocationId: string;
+  actionId: string;
+  actionName: string;
+  concurrency: 'exclusive' | 'parallel' | 'drop-if-running' | 'queue';
+  state: 'running' | 'queued';
+  queuedAt: string;
+  startedAt?: string | null;
 }
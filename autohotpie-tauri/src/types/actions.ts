@@ -3,10 +3,20 @@ import { nanoid } from 'nanoid/non-secure';
 export type ActionKind = 'launch' | 'macro' | 'sequence' | 'system';
 export type MacroStepKind = 'launch' | 'keys' | 'delay' | 'script';
 
+export interface RetryPolicy {
+  /** Total attempts including the first one. */
+  maxAttempts: number;
+  backoff?: 'fixed' | 'exponential';
+  delayMs?: number;
+  maxDelayMs?: number;
+  retryOn?: Array<'error' | 'failure' | 'timed_out'>;
+}
+
 export interface MacroStepBase {
   id: string;
   order: number;
   kind: MacroStepKind;
+  retry?: RetryPolicy | null;
   note?: string | null;
 }
 
@@ -35,14 +45,144 @@ export interface ScriptStep extends MacroStepBase {
 
 export type MacroStep = LaunchStep | KeysStep | DelayStep | ScriptStep;
 
+export type ConcurrencyPolicy = 'exclusive' | 'parallel' | 'drop-if-running' | 'queue';
+
+export type StepCondition =
+  | { kind: 'process_matches'; value: string }
+  | { kind: 'window_title_matches'; value: string }
+  | { kind: 'previous_succeeded' }
+  | { kind: 'previous_failed' }
+  | { kind: 'step_succeeded'; step: string }
+  | { kind: 'step_failed'; step: string };
+
+/** Composite child; plain children are stored as a bare action id. */
+export type CompositeChild =
+  | string
+  | {
+      action: string;
+      /** Exposes the result as `{{steps.<name>.stdout}}` etc. */
+      name?: string;
+      when?: StepCondition;
+      continueOnError?: boolean;
+    };
+
+export interface ParallelGroup {
+  parallel: CompositeChild[];
+  join?: 'all' | 'any';
+  name?: string;
+  when?: StepCondition;
+  continueOnError?: boolean;
+}
+
+export type CompositeStep = CompositeChild | ParallelGroup;
+
+export type SnapPosition =
+  | 'left_half'
+  | 'right_half'
+  | 'top_half'
+  | 'bottom_half'
+  | 'top_left'
+  | 'top_right'
+  | 'bottom_left'
+  | 'bottom_right';
+
+/** `operation` of a `window` payload; all but `focus_or_launch` act on the foreground window. */
+export type WindowOperation =
+  | { type: 'minimize' }
+  | { type: 'maximize' }
+  | { type: 'close' }
+  /** `monitor` counts from 1. */
+  | { type: 'move_to_monitor'; monitor: number }
+  | { type: 'snap'; position: SnapPosition }
+  | { type: 'toggle_always_on_top' }
+  | { type: 'focus_or_launch'; process_name: string; executable?: string; arguments?: string[] };
+
+/** `operation` of a `clipboard` payload. `read` reports the text as the action's stdout. */
+export type ClipboardOperation =
+  | { type: 'read' }
+  | { type: 'set'; text: string }
+  | { type: 'append'; text: string }
+  | { type: 'uppercase' }
+  | { type: 'lowercase' }
+  | { type: 'trim' }
+  /** `replacement` may refer to groups as `$1` or `${name}`. */
+  | { type: 'regex_replace'; pattern: string; replacement: string };
+
+export type LaunchMode = 'detached' | 'wait';
+
+/** Optional fields of a `launch_program` payload; relative paths are under the data directory. */
+export interface LaunchOptions {
+  env?: { set?: Record<string, string>; unset?: string[]; path_prepend?: string[] };
+  stdin?: string;
+  stdout?: string;
+  stderr?: string;
+  mode?: LaunchMode;
+  single_instance?: boolean;
+}
+
+/** Accepted statuses of an `http_request` payload: a code, or a pattern such as `2xx` or `200-299`. */
+export type StatusMatcher = number | string;
+
+export interface ActionPayload {
+  kind: string;
+  steps?: MacroStep[];
+  [field: string]: unknown;
+}
+
+/** Action as persisted by the backend (profile store schema v2). */
+export interface StoredAction {
+  id: string;
+  name: string;
+  description?: string | null;
+  payload: ActionPayload;
+  enabled?: boolean;
+  timeoutMs?: number | null;
+  concurrency?: ConcurrencyPolicy;
+  retry?: RetryPolicy | null;
+  /** Imported and not yet reviewed; running it asks for confirmation. */
+  untrusted?: boolean;
+  lastValidatedAt?: string | null;
+}
+
 export interface ActionDefinition {
   id: string;
   name: string;
   description?: string | null;
   kind: ActionKind;
   steps: MacroStep[];
-  timeoutMs: number;
+  /** Run time limit; `null` means the action has no limit of its own. */
+  timeoutMs: number | null;
   lastValidatedAt?: string | null;
+  /** Original payload for non-macro actions so saving round-trips it unchanged. */
+  payload?: ActionPayload | null;
+  enabled?: boolean;
+  concurrency?: ConcurrencyPolicy;
+  retry?: RetryPolicy | null;
+  untrusted?: boolean;
+}
+
+export interface CustomHandlerInfo {
+  name: string;
+  description: string;
+  paramsSchema: Record<string, unknown>;
+  source: 'builtin' | 'external';
+}
+
+/** Entry returned by `available_script_languages`. */
+export interface ScriptLanguageInfo {
+  language: string;
+  aliases: string[];
+  executable: string;
+  mode: 'inline' | 'file';
+  source: 'builtin' | 'settings';
+  resolvedPath: string | null;
+  available: boolean;
+}
+
+/** Result of `check_action_policy`; prompt on `confirm`, then pass `confirmed: true` to `run_action`. */
+export interface PolicyDecision {
+  verdict: 'allow' | 'confirm' | 'deny';
+  reasons: string[];
 }
 
 export interface ActionValidationResult {
@@ -58,6 +198,60 @@ export function cloneActionDefinition(action: ActionDefinition): ActionDefinitio
   };
 }
 
+function kindFromPayload(payload: ActionPayload): ActionKind {
+  switch (payload.kind) {
+    case 'macro':
+      return 'macro';
+    case 'launch_program':
+      return 'launch';
+    case 'composite':
+      return 'sequence';
+    default:
+      return 'system';
+  }
+}
+
+export function fromStoredAction(action: StoredAction | ActionDefinition): ActionDefinition {
+  if (!('payload' in action) || !action.payload) {
+    return cloneActionDefinition(action as ActionDefinition);
+  }
+  const { payload } = action;
+  const isMacro = payload.kind === 'macro';
+  return {
+    id: action.id,
+    name: action.name,
+    description: action.description ?? null,
+    kind: kindFromPayload(payload),
+    steps: (payload.steps ?? []).map((step) => ({ ...step })),
+    timeoutMs: action.timeoutMs ?? null,
+    lastValidatedAt: action.lastValidatedAt ?? null,
+    payload: isMacro ? null : { ...payload },
+    enabled: action.enabled ?? true,
+    concurrency: action.concurrency ?? 'queue',
+    retry: action.retry ?? null,
+    untrusted: action.untrusted ?? false,
+  };
+}
+
+export function toStoredAction(action: ActionDefinition): StoredAction {
+  const payload =
+    action.kind !== 'macro' && action.payload
+      ? { ...action.payload }
+      : { kind: 'macro', steps: action.steps.map((step) => ({ ...step })) };
+  return {
+    id: action.id,
+    name: action.name,
+    description: action.description ?? null,
+    payload,
+    enabled: action.enabled ?? true,
+    timeoutMs: action.timeoutMs ?? null,
+    concurrency: action.concurrency ?? 'queue',
+    retry: action.retry ?? null,
+    untrusted: action.untrusted ?? false,
+    lastValidatedAt: action.lastValidatedAt ?? null,
+  };
+}
+
 export function createEmptyActionDefinition(id?: string, name?: string): ActionDefinition {
   return {
     id: id ?? nanoid(),
@@ -123,3 +317,83 @@ export function reorderSteps(steps: MacroStep[]): MacroStep[] {
     .map((step, index) => ({ ...step, order: index }))
     .sort((a, b) => a.order - b.order);
 }
+
+export interface TemplateResolution {
+  template: string;
+  rendered: string;
+}
+
+export type PlanStep =
+  | {
+      kind: 'launch_program';
+      executable: string;
+      resolvedPath: string;
+      arguments: string[];
+      workingDir?: string;
+      /** Changed variables; `null` marks a removed one. */
+      env?: Record<string, string | null>;
+      stdin?: string;
+      stdout?: string;
+      stderr?: string;
+      mode: LaunchMode;
+      singleInstance: boolean;
+    }
+  | { kind: 'send_keys'; sequence: string; keyEvents: number; repeat: number; delayMs?: number }
+  | {
+      kind: 'run_script';
+      language: string;
+      interpreter: string;
+      interpreterPath: string | null;
+      arguments: string[];
+    }
+  | { kind: 'system_command'; shell: string; shellPath: string | null; arguments: string[] }
+  | { kind: 'delay'; durationMs: number }
+  | { kind: 'composite'; steps: CompositeStepPlan[] }
+  | { kind: 'custom'; handler: string; registered: boolean; params: unknown }
+  | { kind: 'macro'; steps: MacroStepPlan[] }
+  | { kind: 'window'; operation: WindowOperation; backend: string }
+  | { kind: 'clipboard'; operation: ClipboardOperation }
+  | { kind: 'type_text'; text: string; pasteKeys: string; restoreClipboard: boolean }
+  | {
+      kind: 'http_request';
+      method: string;
+      url: string;
+      /** Credential headers are shown as `***`. */
+      headers?: Record<string, string>;
+      body?: string;
+      timeoutMs?: number;
+    }
+  /** `resolved` is the URL, or the absolute path of a file or folder. */
+  | { kind: 'open'; target: string; resolved: string; exists: boolean };
+
+export type PlanNode = PlanStep & { templates?: TemplateResolution[] };
+
+export type MacroStepPlan = PlanNode & { stepId: string; order: number };
+
+export interface CompositeChildPlan {
+  name?: string;
+  when?: StepCondition;
+  /** Absent when there is no condition or it depends on step results. */
+  conditionHolds?: boolean;
+  continueOnError: boolean;
+  plan: ActionPlan;
+}
+
+export type CompositeStepPlan =
+  | ({ kind: 'child' } & CompositeChildPlan)
+  | {
+      kind: 'parallel';
+      join: 'all' | 'any';
+      name?: string;
+      when?: StepCondition;
+      conditionHolds?: boolean;
+      continueOnError: boolean;
+      branches: CompositeChildPlan[];
+    };
+
+/** Result of `explain_action`: what an action would do, without running it. */
+export type ActionPlan = PlanNode & {
+  actionId: string;
+  actionName: string;
+  timeoutMs?: number;
+};
@@ -42,7 +42,9 @@ export type MatchKind =
 
 export interface ActiveProfileSnapshot {
   index: number;
+  profileId?: string;
   name: string;
   matchKind: MatchKind;
   holdToOpen: boolean;
+  priority?: number;
 }
@@ -0,0 +1,19 @@
+export type TriggerEvent =
+  /** Five-field cron expression in local time. */
+  | { type: 'schedule'; cron: string }
+  | { type: 'profile_activated'; profile: string }
+  | { type: 'profile_deactivated'; profile: string }
+  | { type: 'startup' }
+  /** Relative paths resolve against the data directory. */
+  | { type: 'file_changed'; path: string; recursive?: boolean };
+
+export interface Trigger {
+  id: string;
+  name: string;
+  enabled: boolean;
+  /** Id of the action in the shared library to run. */
+  action: string;
+  on: TriggerEvent;
+}
+
+export const TRIGGERS_CHANGED_EVENT = 'triggers://changed';